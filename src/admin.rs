@@ -1,16 +1,84 @@
+// create_match takes the odds of every outcome, allowed for the whole module so it also
+// covers the wrapper generated by #[near]
+#![allow(clippy::too_many_arguments)]
+
 use near_sdk::{env, near, require, Gas, NearToken, PromiseOrValue};
 
+use crate::betting::pricing::determine_initial_pools;
+use crate::dex::gas::settle_gas;
 use crate::events::Event;
 pub use crate::ext::*;
+use crate::markets::lines::{determine_line_outcome, determine_winner};
 use crate::*;
 
 #[near]
 impl Contract {
-    // Changes the admin of the contract
-    pub fn change_admin(&mut self, new_admin: AccountId) {
+    // Proposes a new admin of the contract which only takes effect once the new admin accepts
+    // Executed through the timelock with execute_action
+    pub(crate) fn propose_admin(&mut self, new_admin: AccountId, expires_at: Option<U64>) {
+        if let Some(expires_at) = expires_at {
+            require!(
+                expires_at.0 > env::block_timestamp(),
+                "The expiry must be in the future"
+            );
+        }
+
+        // A new proposal replaces any previous proposal
+        self.pending_admin = Some(AdminProposal {
+            new_admin: new_admin.clone(),
+            expires_at,
+        });
+
+        Event::ProposeAdmin {
+            new_admin: &new_admin,
+            expires_at,
+        }
+        .emit();
+    }
+
+    // The proposed admin accepts becoming the admin
+    pub fn accept_admin(&mut self) {
+        let new_admin = env::predecessor_account_id();
+
+        let proposal = self
+            .pending_admin
+            .take()
+            .unwrap_or_else(|| panic!("There is no admin proposal"));
+
+        require!(
+            proposal.new_admin == new_admin,
+            "Only the proposed admin can accept"
+        );
+
+        if let Some(expires_at) = proposal.expires_at {
+            require!(
+                env::block_timestamp() <= expires_at.0,
+                "The admin proposal has expired"
+            );
+        }
+
+        let old_admin = std::mem::replace(&mut self.admin, new_admin);
+
+        Event::AcceptAdmin {
+            old_admin: &old_admin,
+            new_admin: &self.admin,
+        }
+        .emit();
+    }
+
+    // Cancels a proposal to change the admin
+    pub fn cancel_admin_proposal(&mut self) {
         self.assert_admin();
 
-        self.admin = new_admin;
+        let proposal = self
+            .pending_admin
+            .take()
+            .unwrap_or_else(|| panic!("There is no admin proposal"));
+
+        Event::CancelAdminProposal {
+            new_admin: &proposal.new_admin,
+        }
+        .emit();
     }
 
     // Creates a new match
@@ -19,40 +87,69 @@ impl Contract {
         game: String,
         team_1: String,
         team_2: String,
-        in_odds_1: f64,
-        in_odds_2: f64,
+        in_odds_1: U128,
+        in_odds_2: U128,
+        in_odds_draw: Option<U128>,
         date: String,
+        start_time: U64,
+        external_ref: Option<String>,
     ) {
-        self.assert_admin();
+        self.assert_role(Role::MatchCreator);
+
+        require!(
+            start_time.0 > env::block_timestamp(),
+            "The start time must be in the future"
+        );
 
         let match_id: MatchId = format!("{}-{}-{}", team_1, team_2, date);
 
-        // Calculate inital pool sizes
-        let in_prob_1: f64 = 1.0 / in_odds_1;
-        let in_prob_2: f64 = 1.0 / in_odds_2;
-        let divider = in_prob_1 + in_prob_2;
-        let actual_prob_1 = in_prob_1 / divider;
-        let actual_prob_2 = in_prob_2 / divider;
-        let team_1_total_bets = U128(ONE_USDC * (actual_prob_1 * WEIGHT_FACTOR).round() as u128);
-        let team_2_total_bets = U128(ONE_USDC * (actual_prob_2 * WEIGHT_FACTOR).round() as u128);
+        require!(
+            self.matches.get(&match_id).is_none() && self.legacy_matches.get(&match_id).is_none(),
+            format!("A match already exists with match id: {}", match_id)
+        );
+
+        // Record the external reference so the match can be found by it
+        if let Some(external_ref) = &external_ref {
+            require!(
+                self.match_ids_by_external_ref.get(external_ref).is_none(),
+                format!("A match already exists with external ref: {}", external_ref)
+            );
+
+            self.match_ids_by_external_ref
+                .insert(external_ref.clone(), match_id.clone());
+        }
+
+        // Calculate inital pool sizes, odds are decimal odds scaled by FIXED_POINT_ONE
+        // if odds for a draw are given then the match is a three-way match
+        let mut in_odds = vec![in_odds_1, in_odds_2];
+        in_odds.extend(in_odds_draw);
+        let initial_pools = determine_initial_pools(&in_odds, self.config.weight_factor);
 
         let match_state = MatchState::Future;
         let winner: Option<Team> = None;
-        let team_1_potential_winnings = U128(0);
-        let team_2_potential_winnings = U128(0);
+
+        let outcomes = initial_pools
+            .iter()
+            .map(|initial_pool| OutcomePool {
+                total_bets: *initial_pool,
+                initial_pool: *initial_pool,
+                potential_winnings: U128(0),
+            })
+            .collect();
 
         let new_match = Match {
             game: game.clone(),
             team_1: team_1.clone(),
             team_2: team_2.clone(),
-            team_1_total_bets,
-            team_2_total_bets,
-            team_1_initial_pool: team_1_total_bets,
-            team_2_initial_pool: team_2_total_bets,
-            team_1_potential_winnings,
-            team_2_potential_winnings,
+            outcomes,
+            sub_markets: Vec::new(),
+            start_time,
             match_state,
             winner,
+            proposed_result: None,
+            result_votes: Vec::new(),
+            disputed_by: Vec::new(),
+            external_ref: external_ref.clone(),
         };
 
         // Insert new match
@@ -64,15 +161,18 @@ impl Contract {
             date,
             team_1,
             team_2,
-            team_1_initial_pool: team_1_total_bets,
-            team_2_initial_pool: team_2_total_bets,
+            team_1_initial_pool: initial_pools[Team::Team1.index()],
+            team_2_initial_pool: initial_pools[Team::Team2.index()],
+            draw_initial_pool: initial_pools.get(Team::Draw.index()).copied(),
+            start_time,
+            external_ref,
         }
         .emit();
     }
 
     // When a match starts
     pub fn end_betting(&mut self, match_id: &MatchId) {
-        self.assert_admin();
+        self.assert_role(Role::MatchCreator);
 
         let relevant_match = self
             .matches
@@ -85,94 +185,59 @@ impl Contract {
         );
 
         relevant_match.match_state = MatchState::Current;
+        let sub_markets = relevant_match.sub_markets.clone();
 
         Event::EndBetting {
             match_id: match_id.clone(),
         }
         .emit();
+
+        // Close betting on the match's over/under and handicap markets
+        for market_id in sub_markets {
+            let relevant_market = self.markets.get_mut(&market_id).unwrap();
+
+            if matches!(relevant_market.market_state, MatchState::Future) {
+                relevant_market.market_state = MatchState::Current;
+
+                Event::EndMarketBetting { market_id }.emit();
+            }
+        }
     }
 
-    // When a match finishes
+    // When a match finishes, the admin can finish a match straight away
+    // overriding any result proposed by a resolver
     pub fn finish_match(&mut self, match_id: &MatchId, winner: Team) -> PromiseOrValue<()> {
-        require!(
-            env::prepaid_gas() >= Gas::from_tgas(300),
-            "You need to attach 300 TGas"
-        );
+        self.require_dex_gas(settle_gas);
 
         self.assert_admin();
 
-        let relevant_match = self
-            .matches
-            .get_mut(match_id)
-            .unwrap_or_else(|| panic!("No match exists with match id: {}", match_id));
+        self.emit_override_result(match_id, &winner);
 
-        require!(
-            matches!(relevant_match.match_state, MatchState::Current),
-            "Match state must be Current to finish the match"
-        );
+        self.resolve_match(match_id, winner, None)
+    }
 
-        relevant_match.match_state = MatchState::Finished;
-        relevant_match.winner = Some(winner.clone());
+    // When a match finishes, the winner and the result of every over/under
+    // and handicap market on the match are determined from the final score
+    pub fn finish_match_with_score(
+        &mut self,
+        match_id: &MatchId,
+        team_1_score: u32,
+        team_2_score: u32,
+    ) -> PromiseOrValue<()> {
+        self.require_dex_gas(settle_gas);
 
-        Event::FinishMatch {
-            match_id: match_id.clone(),
-            winner: winner.clone(),
-        }
-        .emit(); // Change this to be emitted after the first callback down both paths
+        self.assert_admin();
 
-        let total_bets = relevant_match.team_1_total_bets.0 + relevant_match.team_2_total_bets.0
-            - relevant_match.team_1_initial_pool.0
-            - relevant_match.team_2_initial_pool.0;
+        let winner = determine_winner(team_1_score, team_2_score);
 
-        // Calculate the difference between the total bets and the potential winnings
-        // and whether it is a profit or loss
-        let (difference, is_profit) = match winner {
-            Team::Team1 => {
-                self.funds_to_payout =
-                    U128(self.funds_to_payout.0 + relevant_match.team_1_potential_winnings.0);
-                if total_bets > relevant_match.team_1_potential_winnings.0 {
-                    (
-                        total_bets - relevant_match.team_1_potential_winnings.0,
-                        true,
-                    )
-                } else if total_bets == relevant_match.team_1_potential_winnings.0 {
-                    return PromiseOrValue::Value(()); // No profit or loss
-                } else {
-                    (
-                        relevant_match.team_1_potential_winnings.0 - total_bets,
-                        false,
-                    )
-                }
-            }
-            Team::Team2 => {
-                self.funds_to_payout =
-                    U128(self.funds_to_payout.0 + relevant_match.team_2_potential_winnings.0);
-                if total_bets > relevant_match.team_2_potential_winnings.0 {
-                    (
-                        total_bets - relevant_match.team_2_potential_winnings.0,
-                        true,
-                    )
-                } else if total_bets == relevant_match.team_2_potential_winnings.0 {
-                    return PromiseOrValue::Value(());
-                } else {
-                    (
-                        relevant_match.team_2_potential_winnings.0 - total_bets,
-                        false,
-                    )
-                }
-            }
-        };
+        self.emit_override_result(match_id, &winner);
 
-        // Send to relevant function to handle profit or loss scenario
-        match is_profit {
-            true => self.handle_profit(difference),
-            false => self.handle_loss(difference),
-        }
+        self.resolve_match(match_id, winner, Some((team_1_score, team_2_score)))
     }
 
     // Cancels a match and puts it in an error state
     pub fn cancel_match(&mut self, match_id: &MatchId) {
-        self.assert_admin();
+        self.assert_role(Role::MatchCreator);
 
         let relevant_match = self
             .matches
@@ -182,23 +247,43 @@ impl Contract {
         require!(
             matches!(
                 relevant_match.match_state,
-                MatchState::Future | MatchState::Current
+                MatchState::Future
+                    | MatchState::Current
+                    | MatchState::PendingResult
+                    | MatchState::Disputed
             ),
-            "Match state must be Future or Current to cancel the match"
+            "Match state must be Future, Current, PendingResult or Disputed to cancel the match"
         );
 
         relevant_match.match_state = MatchState::Error;
+        relevant_match.proposed_result = None;
+        relevant_match.result_votes.clear();
+        relevant_match.disputed_by.clear();
+        let sub_markets = relevant_match.sub_markets.clone();
 
         Event::CancelMatch {
             match_id: match_id.clone(),
         }
         .emit();
+
+        // Cancel the match's over/under and handicap markets so their bets are refunded
+        for market_id in sub_markets {
+            let relevant_market = self.markets.get_mut(&market_id).unwrap();
+
+            if matches!(
+                relevant_market.market_state,
+                MatchState::Future | MatchState::Current
+            ) {
+                relevant_market.market_state = MatchState::Error;
+
+                Event::CancelMarket { market_id }.emit();
+            }
+        }
     }
 
     // Removes an amount of USDC from the fees fund and sends it to the receiver
-    pub fn take_from_fees_fund(&mut self, amount: U128, receiver: AccountId) -> U128 {
-        self.assert_admin();
-
+    // Executed through the timelock with execute_action
+    pub(crate) fn take_from_fees_fund(&mut self, amount: U128, receiver: AccountId) {
         require!(
             self.fees_fund >= amount,
             "Not enough funds in the fees fund"
@@ -210,15 +295,11 @@ impl Contract {
             .ft_transfer(receiver, amount);
 
         self.fees_fund = U128(self.fees_fund.0 - amount.0);
-
-        // Returns how much is left in the fees fund
-        self.fees_fund
     }
 
     // Removes an amount of USDC from the insurance fund and sends it to the receiver
-    pub fn take_from_insurance_fund(&mut self, amount: U128, receiver: AccountId) -> U128 {
-        self.assert_admin();
-
+    // Executed through the timelock with execute_action
+    pub(crate) fn take_from_insurance_fund(&mut self, amount: U128, receiver: AccountId) {
         require!(
             self.insurance_fund >= amount,
             "Not enough funds in the insurance fund"
@@ -230,9 +311,455 @@ impl Contract {
             .ft_transfer(receiver, amount);
 
         self.insurance_fund = U128(self.insurance_fund.0 - amount.0);
+    }
+
+    // Creates a new market with any number of outcomes, e.g. the winner of a tournament
+    pub fn create_market(
+        &mut self,
+        game: String,
+        name: String,
+        outcome_labels: Vec<String>,
+        in_odds: Vec<U128>,
+        date: String,
+        start_time: U64,
+    ) {
+        self.assert_role(Role::MatchCreator);
+
+        require!(
+            start_time.0 > env::block_timestamp(),
+            "The start time must be in the future"
+        );
+
+        require!(
+            (MIN_MARKET_OUTCOMES..=MAX_MARKET_OUTCOMES).contains(&outcome_labels.len()),
+            format!(
+                "A market must have between {} and {} outcomes",
+                MIN_MARKET_OUTCOMES, MAX_MARKET_OUTCOMES
+            )
+        );
+
+        require!(
+            outcome_labels.len() == in_odds.len(),
+            "There must be odds for every outcome"
+        );
+
+        let market_id: MarketId = format!("{}-{}", name, date);
+
+        require!(
+            self.markets.get(&market_id).is_none(),
+            format!("A market already exists with market id: {}", market_id)
+        );
+
+        // Calculate inital pool sizes, odds are decimal odds scaled by FIXED_POINT_ONE
+        let initial_pools = determine_initial_pools(&in_odds, self.config.weight_factor);
 
-        // Returns how much is left in the insurance fund
-        self.insurance_fund
+        let outcomes = initial_pools
+            .iter()
+            .map(|initial_pool| OutcomePool {
+                total_bets: *initial_pool,
+                initial_pool: *initial_pool,
+                potential_winnings: U128(0),
+            })
+            .collect();
+
+        let new_market = Market {
+            game: game.clone(),
+            name: name.clone(),
+            outcome_labels: outcome_labels.clone(),
+            outcomes,
+            market_state: MatchState::Future,
+            winner: None,
+            line: None,
+            start_time,
+        };
+
+        // Insert new market
+        self.markets.insert(market_id.clone(), new_market);
+
+        Event::NewMarket {
+            market_id,
+            game,
+            name,
+            date,
+            outcome_labels,
+            initial_pools,
+            start_time,
+        }
+        .emit();
+    }
+
+    // Adds an over/under or handicap market to a match
+    // the line is scaled by LINE_SCALE and for handicap markets is added to team 1's score
+    pub fn create_sub_market(
+        &mut self,
+        match_id: MatchId,
+        kind: LineKind,
+        line: i64,
+        in_odds_1: U128,
+        in_odds_2: U128,
+    ) {
+        self.assert_role(Role::MatchCreator);
+
+        let relevant_match = self
+            .matches
+            .get(&match_id)
+            .unwrap_or_else(|| panic!("No match exists with match id: {}", match_id));
+
+        require!(
+            matches!(relevant_match.effective_state(), MatchState::Future),
+            "Match state must be Future to add a market to it"
+        );
+
+        require!(
+            kind == LineKind::Handicap || line >= 0,
+            "An over/under line cannot be negative"
+        );
+
+        let market_id: MarketId = format!("{}-{:?}-{}", match_id, kind, line);
+
+        require!(
+            self.markets.get(&market_id).is_none(),
+            format!("A market already exists with market id: {}", market_id)
+        );
+
+        let (name, outcome_labels) = match kind {
+            LineKind::OverUnder => (
+                "Over/Under".to_string(),
+                vec!["Over".to_string(), "Under".to_string()],
+            ),
+            LineKind::Handicap => (
+                "Handicap".to_string(),
+                vec![relevant_match.team_1.clone(), relevant_match.team_2.clone()],
+            ),
+        };
+
+        // Calculate inital pool sizes, odds are decimal odds scaled by FIXED_POINT_ONE
+        let initial_pools =
+            determine_initial_pools(&[in_odds_1, in_odds_2], self.config.weight_factor);
+
+        let outcomes = initial_pools
+            .iter()
+            .map(|initial_pool| OutcomePool {
+                total_bets: *initial_pool,
+                initial_pool: *initial_pool,
+                potential_winnings: U128(0),
+            })
+            .collect();
+
+        let new_market = Market {
+            game: relevant_match.game.clone(),
+            name,
+            outcome_labels: outcome_labels.clone(),
+            outcomes,
+            market_state: MatchState::Future,
+            winner: None,
+            line: Some(MarketLine {
+                match_id: match_id.clone(),
+                kind: kind.clone(),
+                value: line,
+            }),
+            start_time: relevant_match.start_time,
+        };
+
+        // Insert new market and attach it to the match
+        self.markets.insert(market_id.clone(), new_market);
+        self.matches
+            .get_mut(&match_id)
+            .unwrap()
+            .sub_markets
+            .push(market_id.clone());
+
+        Event::NewSubMarket {
+            market_id,
+            match_id,
+            kind,
+            line,
+            outcome_labels,
+            initial_pools,
+        }
+        .emit();
+    }
+
+    // When a market's event starts
+    pub fn end_market_betting(&mut self, market_id: &MarketId) {
+        self.assert_role(Role::MatchCreator);
+
+        let relevant_market = self
+            .markets
+            .get_mut(market_id)
+            .unwrap_or_else(|| panic!("No market exists with market id: {}", market_id));
+
+        require!(
+            relevant_market.line.is_none(),
+            "Over/under and handicap markets follow the state of their match"
+        );
+
+        require!(
+            matches!(relevant_market.market_state, MatchState::Future),
+            "Market state must be Future to end betting"
+        );
+
+        relevant_market.market_state = MatchState::Current;
+
+        Event::EndMarketBetting {
+            market_id: market_id.clone(),
+        }
+        .emit();
+    }
+
+    // When a market's event finishes, winner is the index of the winning outcome
+    pub fn finish_market(&mut self, market_id: &MarketId, winner: u32) -> PromiseOrValue<()> {
+        self.require_dex_gas(settle_gas);
+
+        self.assert_admin();
+
+        let relevant_market = self
+            .markets
+            .get_mut(market_id)
+            .unwrap_or_else(|| panic!("No market exists with market id: {}", market_id));
+
+        require!(
+            relevant_market.line.is_none(),
+            "Over/under and handicap markets are finished with the final score of their match"
+        );
+
+        require!(
+            matches!(relevant_market.effective_state(), MatchState::Current),
+            "Market state must be Current to finish the market"
+        );
+
+        require!(
+            (winner as usize) < relevant_market.outcomes.len(),
+            "The winner must be a possible outcome of the market"
+        );
+
+        relevant_market.market_state = MatchState::Finished;
+        relevant_market.winner = Some(winner);
+
+        Event::FinishMarket {
+            market_id: market_id.clone(),
+            winner,
+        }
+        .emit();
+
+        // Total bets made by users across all outcomes, excluding the initial pools
+        let total_bets: u128 = relevant_market
+            .outcomes
+            .iter()
+            .map(|outcome| outcome.total_bets.0 - outcome.initial_pool.0)
+            .sum();
+
+        let potential_winnings = relevant_market.outcomes[winner as usize]
+            .potential_winnings
+            .0;
+
+        self.settle(total_bets, potential_winnings)
+    }
+
+    // Cancels a market and puts it in an error state
+    pub fn cancel_market(&mut self, market_id: &MarketId) {
+        self.assert_role(Role::MatchCreator);
+
+        let relevant_market = self
+            .markets
+            .get_mut(market_id)
+            .unwrap_or_else(|| panic!("No market exists with market id: {}", market_id));
+
+        require!(
+            matches!(
+                relevant_market.market_state,
+                MatchState::Future | MatchState::Current
+            ),
+            "Market state must be Future or Current to cancel the market"
+        );
+
+        relevant_market.market_state = MatchState::Error;
+
+        Event::CancelMarket {
+            market_id: market_id.clone(),
+        }
+        .emit();
+    }
+
+    // Finishes a match and its over/under and handicap markets from its result
+    // and handles the profit or loss
+    pub(crate) fn resolve_match(
+        &mut self,
+        match_id: &MatchId,
+        winner: Team,
+        score: Option<(u32, u32)>,
+    ) -> PromiseOrValue<()> {
+        let relevant_match = self
+            .matches
+            .get(match_id)
+            .unwrap_or_else(|| panic!("No match exists with match id: {}", match_id));
+
+        require!(
+            score.is_some() || relevant_match.sub_markets.is_empty(),
+            "Matches with over/under or handicap markets must be finished with the final score"
+        );
+
+        let (mut total_bets, mut potential_winnings) = self.finish_match_internal(match_id, winner);
+
+        if let Some((team_1_score, team_2_score)) = score {
+            Event::FinalScore {
+                match_id: match_id.clone(),
+                team_1_score,
+                team_2_score,
+            }
+            .emit();
+
+            // Settle each market on the match and add its bets and winnings to the match's
+            // so the profit or loss is handled once
+            let sub_markets = self.matches.get(match_id).unwrap().sub_markets.clone();
+            for market_id in sub_markets {
+                let (market_bets, market_winnings) =
+                    self.finish_sub_market(&market_id, team_1_score, team_2_score);
+                total_bets += market_bets;
+                potential_winnings += market_winnings;
+            }
+        }
+
+        self.settle(total_bets, potential_winnings)
+    }
+
+    // Emits an event when the admin finishes a match instead of it being finalised
+    // from a result proposed by a resolver
+    fn emit_override_result(&self, match_id: &MatchId, winner: &Team) {
+        let proposed_winner = self
+            .matches
+            .get(match_id)
+            .and_then(|relevant_match| relevant_match.proposed_result.as_ref())
+            .map(|proposed_result| proposed_result.winner.clone());
+
+        Event::OverrideResult {
+            match_id: match_id.clone(),
+            winner: winner.clone(),
+            proposed_winner,
+        }
+        .emit();
+    }
+
+    // Finishes a match and returns the total bets made on it and the winnings to be paid out
+    fn finish_match_internal(&mut self, match_id: &MatchId, winner: Team) -> (u128, u128) {
+        let relevant_match = self
+            .matches
+            .get_mut(match_id)
+            .unwrap_or_else(|| panic!("No match exists with match id: {}", match_id));
+
+        require!(
+            matches!(
+                relevant_match.effective_state(),
+                MatchState::Current | MatchState::PendingResult | MatchState::Disputed
+            ),
+            "Match state must be Current, PendingResult or Disputed to finish the match"
+        );
+
+        require!(
+            relevant_match.outcome(&winner).is_some(),
+            "The winner must be a possible outcome of the match"
+        );
+
+        relevant_match.match_state = MatchState::Finished;
+        relevant_match.winner = Some(winner.clone());
+        relevant_match.proposed_result = None;
+        relevant_match.result_votes.clear();
+        relevant_match.disputed_by.clear();
+
+        Event::FinishMatch {
+            match_id: match_id.clone(),
+            winner: winner.clone(),
+        }
+        .emit(); // Change this to be emitted after the first callback down both paths
+
+        // Total bets made by users across all outcomes, excluding the initial pools
+        let total_bets: u128 = relevant_match
+            .outcomes
+            .iter()
+            .map(|outcome| outcome.total_bets.0 - outcome.initial_pool.0)
+            .sum();
+
+        let potential_winnings = relevant_match.outcomes[winner.index()].potential_winnings.0;
+
+        (total_bets, potential_winnings)
+    }
+
+    // Finishes an over/under or handicap market from the final score of its match
+    // and returns the total bets made on it and the winnings to be paid out
+    fn finish_sub_market(
+        &mut self,
+        market_id: &MarketId,
+        team_1_score: u32,
+        team_2_score: u32,
+    ) -> (u128, u128) {
+        let relevant_market = self.markets.get_mut(market_id).unwrap();
+
+        // Markets that were cancelled have no bets to settle
+        if !matches!(relevant_market.effective_state(), MatchState::Current) {
+            return (0, 0);
+        }
+
+        let line = relevant_market.line.as_ref().unwrap();
+
+        let Some(winner) = determine_line_outcome(line, team_1_score, team_2_score) else {
+            // The score landed exactly on the line so every bet is refunded
+            relevant_market.market_state = MatchState::Error;
+
+            Event::CancelMarket {
+                market_id: market_id.clone(),
+            }
+            .emit();
+
+            return (0, 0);
+        };
+
+        relevant_market.market_state = MatchState::Finished;
+        relevant_market.winner = Some(winner);
+
+        Event::FinishMarket {
+            market_id: market_id.clone(),
+            winner,
+        }
+        .emit();
+
+        // Total bets made by users across all outcomes, excluding the initial pools
+        let total_bets: u128 = relevant_market
+            .outcomes
+            .iter()
+            .map(|outcome| outcome.total_bets.0 - outcome.initial_pool.0)
+            .sum();
+
+        let potential_winnings = relevant_market.outcomes[winner as usize]
+            .potential_winnings
+            .0;
+
+        (total_bets, potential_winnings)
+    }
+
+    // Adds the winnings to the funds to pay out and sends the difference between
+    // the bets made and the winnings to be handled as a profit or loss
+    pub(crate) fn settle(
+        &mut self,
+        total_bets: u128,
+        potential_winnings: u128,
+    ) -> PromiseOrValue<()> {
+        self.funds_to_payout = U128(self.funds_to_payout.0 + potential_winnings);
+
+        // Calculate the difference between the total bets and the potential winnings
+        // and whether it is a profit or loss
+        let (difference, is_profit) = if total_bets > potential_winnings {
+            (total_bets - potential_winnings, true)
+        } else if total_bets == potential_winnings {
+            return PromiseOrValue::Value(()); // No profit or loss
+        } else {
+            (potential_winnings - total_bets, false)
+        };
+
+        // Send to relevant function to handle profit or loss scenario
+        match is_profit {
+            true => self.handle_profit(difference),
+            false => self.handle_loss(difference),
+        }
     }
 
     pub(crate) fn assert_admin(&self) {
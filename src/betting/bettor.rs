@@ -1,6 +1,7 @@
-use near_sdk::json_types::U128;
+use near_sdk::json_types::{U128, U64};
 use near_sdk::{env, near, require, Gas, NearToken, PromiseError};
 
+use crate::betting::pricing::{apply_market_margin, ln_fixed, other_outcomes_bets};
 use crate::events::Event;
 pub use crate::ext::*;
 use crate::*;
@@ -8,54 +9,40 @@ use crate::*;
 #[near]
 impl Contract {
     // Function to bet on a match with USDC
+    // Returns the amount of USDC to refund to the bettor
     pub(crate) fn bet(
         &mut self,
         sender_id: AccountId,
         amount: U128,
         match_id: MatchId,
         team: Team,
+        min_potential_winnings: Option<U128>,
+        deadline: Option<U64>,
     ) -> U128 {
-        require!(
-            env::predecessor_account_id() == self.usdc_token_contract,
-            "Bets can only be made in USDC"
-        );
-
-        require!(amount.0 >= ONE_USDC, "You must bet at least one USDC");
+        // Validate the bet, if it is rejected the full amount is refunded
+        let potential_winnings =
+            match self.validate_bet(amount, &match_id, &team, min_potential_winnings, deadline) {
+                Ok(potential_winnings) => potential_winnings,
+                Err(reason) => {
+                    Event::BetRejected {
+                        account_id: &sender_id,
+                        amount,
+                        match_id,
+                        reason,
+                    }
+                    .emit();
+
+                    return amount;
+                }
+            };
 
         // Get relevant match
-        let relevant_match = self
-            .matches
-            .get_mut(&match_id)
-            .unwrap_or_else(|| panic!("No match exists with match id: {}", &match_id));
-
-        require!(
-            matches!(relevant_match.match_state, MatchState::Future),
-            "Match state must be Future to bet on it"
-        );
-
-        // Determines potential winnings
-        let potential_winnings = determine_potential_winnings(
-            &team,
-            &relevant_match.team_1_total_bets,
-            &relevant_match.team_2_total_bets,
-            &amount,
-        );
+        let relevant_match = self.matches.get_mut(&match_id).unwrap();
 
         // Increment total bets for the team
-        match team {
-            Team::Team1 => {
-                relevant_match.team_1_total_bets =
-                    U128(relevant_match.team_1_total_bets.0 + amount.0);
-                relevant_match.team_1_potential_winnings =
-                    U128(relevant_match.team_1_potential_winnings.0 + potential_winnings.0)
-            }
-            Team::Team2 => {
-                relevant_match.team_2_total_bets =
-                    U128(relevant_match.team_2_total_bets.0 + amount.0);
-                relevant_match.team_2_potential_winnings =
-                    U128(relevant_match.team_2_potential_winnings.0 + potential_winnings.0)
-            }
-        }
+        let outcome = &mut relevant_match.outcomes[team.index()];
+        outcome.total_bets = U128(outcome.total_bets.0 + amount.0);
+        outcome.potential_winnings = U128(outcome.potential_winnings.0 + potential_winnings.0);
 
         // Creates a new bet
         let new_bet = Bet {
@@ -72,13 +59,13 @@ impl Contract {
 
         // Inserts the new bet, creates a new map if the user has not bet previously
         if self.bets_by_user.get(&sender_id).is_none() {
-            let new_map: IterableMap<BetId, Bet> = IterableMap::new(bet_id_string.as_bytes());
+            let new_map: IterableMap<BetId, UserBet> = IterableMap::new(bet_id_string.as_bytes());
             self.bets_by_user.insert(sender_id.clone(), new_map);
         };
 
         let bets_by_user = self.bets_by_user.get_mut(&sender_id).unwrap();
 
-        bets_by_user.insert(self.last_bet_id, new_bet);
+        bets_by_user.insert(self.last_bet_id, UserBet::Single(new_bet));
 
         Event::Bet {
             account_id: &sender_id,
@@ -87,14 +74,91 @@ impl Contract {
             match_id,
             team,
             potential_winnings,
-            new_team_1_pool_size: relevant_match.team_1_total_bets,
-            new_team_2_pool_size: relevant_match.team_2_total_bets,
+            new_team_1_pool_size: relevant_match.outcomes[Team::Team1.index()].total_bets,
+            new_team_2_pool_size: relevant_match.outcomes[Team::Team2.index()].total_bets,
+            new_draw_pool_size: relevant_match
+                .outcome(&Team::Draw)
+                .map(|outcome| outcome.total_bets),
         }
         .emit();
 
         U128(0)
     }
 
+    // Checks a bet can be placed and returns its potential winnings
+    // or the reason the bet is rejected
+    pub(crate) fn validate_bet(
+        &self,
+        amount: U128,
+        match_id: &MatchId,
+        team: &Team,
+        min_potential_winnings: Option<U128>,
+        deadline: Option<U64>,
+    ) -> Result<U128, RejectionReason> {
+        self.validate_bet_transfer(amount, deadline)?;
+
+        // Get relevant match
+        let relevant_match = self
+            .matches
+            .get(match_id)
+            .ok_or(RejectionReason::MatchNotFound)?;
+
+        // Betting closes at the start time even if the admin has not ended betting
+        if !matches!(relevant_match.effective_state(), MatchState::Future) {
+            return Err(RejectionReason::BettingClosed);
+        }
+
+        // Check the outcome is possible in the match, draws are only possible in three-way matches
+        if relevant_match.outcome(team).is_none() {
+            return Err(RejectionReason::InvalidOutcome);
+        }
+
+        // Determines potential winnings
+        let potential_winnings = determine_potential_winnings(
+            team.index(),
+            &relevant_match.total_bets(),
+            &amount,
+            self.config.market_margin_bps,
+        );
+
+        // Reject the bet if the odds moved against the bettor since they checked them
+        if let Some(min_potential_winnings) = min_potential_winnings {
+            if potential_winnings < min_potential_winnings {
+                return Err(RejectionReason::PotentialWinningsTooLow);
+            }
+        }
+
+        Ok(potential_winnings)
+    }
+
+    // Checks the token, amount and deadline of a transfer to bet on a match or market
+    pub(crate) fn validate_bet_transfer(
+        &self,
+        amount: U128,
+        deadline: Option<U64>,
+    ) -> Result<(), RejectionReason> {
+        if env::predecessor_account_id() != self.usdc_token_contract {
+            return Err(RejectionReason::WrongToken);
+        }
+
+        if self.pause_state.betting {
+            return Err(RejectionReason::Paused);
+        }
+
+        if amount.0 < ONE_USDC {
+            return Err(RejectionReason::BetTooSmall);
+        }
+
+        // Reject the bet if it arrived after the bettor's deadline
+        if let Some(deadline) = deadline {
+            if env::block_timestamp() > deadline.0 {
+                return Err(RejectionReason::DeadlinePassed);
+            }
+        }
+
+        Ok(())
+    }
+
     // Function to claim winnings or refund
     pub fn claim(&mut self, bet_id: BetId) {
         require!(
@@ -102,85 +166,92 @@ impl Contract {
             "You need to attach 300 TGas"
         );
 
+        require!(!self.pause_state.claiming, "Claiming is paused");
+
         let bettor = env::predecessor_account_id();
 
+        // Bets made before the state was versioned are moved on first access
+        self.migrate_legacy_bets(&bettor);
+
         // Get relevant user
         let relevant_user = self
             .bets_by_user
             .get_mut(&bettor)
             .unwrap_or_else(|| panic!("You have not made a bet"));
 
-        // Get relevant bet
-        let relevant_bet = relevant_user
+        // Get relevant bet and the amount to pay out
+        let (amount, pay_state) = match relevant_user
             .get_mut(&bet_id)
-            .unwrap_or_else(|| panic!("No bet exists with bet id: {:?}", bet_id));
+            .unwrap_or_else(|| panic!("No bet exists with bet id: {:?}", bet_id))
+        {
+            UserBet::Single(relevant_bet) => {
+                require!(
+                    relevant_bet.pay_state.is_none(),
+                    "You have already been paid out"
+                );
+
+                let match_id = &relevant_bet.match_id;
+
+                // Get match state of the match in the bet
+                let relevant_match = self.matches.get(match_id).unwrap_or_else(|| {
+                    panic!(
+                        "No match exists with match id: {} there must have been an error",
+                        match_id
+                    )
+                });
+
+                // Different flow depending on whether win or refund
+                let (amount, pay_state) = match relevant_match.match_state {
+                    MatchState::Finished => {
+                        // Checks they selected the winning team
+                        if let Some(winner) = &relevant_match.winner {
+                            require!(
+                                &relevant_bet.team == winner,
+                                "You did not select the winning team"
+                            );
+                        } else {
+                            panic!("There is an error")
+                        };
+
+                        (relevant_bet.potential_winnings, PayState::Paid)
+                    }
+                    MatchState::Error => (relevant_bet.bet_amount, PayState::RefundPaid),
+                    _ => panic!("Match state must be Finished or Error to claim funds"),
+                };
 
-        require!(
-            matches!(relevant_bet.pay_state, None),
-            "You have already been paid out"
-        );
+                relevant_bet.pay_state = Some(pay_state.clone());
 
-        let match_id = &relevant_bet.match_id;
-
-        // Get match state of the match in the bet
-        let relevant_match = self.matches.get(match_id).unwrap_or_else(|| {
-            panic!(
-                "No match exists with match id: {} there must have been an error",
-                match_id
-            )
-        });
-
-        // Different flow depending on whether win or refund
-        match relevant_match.match_state {
-            MatchState::Finished => {
-                // Checks they selected the winning team
-                if let Some(winner) = &relevant_match.winner {
-                    require!(
-                        &relevant_bet.team == winner,
-                        "You did not select the winning team"
-                    );
-                } else {
-                    panic!("There is an error")
+                (amount, pay_state)
+            }
+            UserBet::Parlay(relevant_parlay) => {
+                require!(
+                    relevant_parlay.pay_state.is_none(),
+                    "You have already been paid out"
+                );
+
+                let (amount, pay_state) = match &relevant_parlay.result {
+                    Some(ParlayResult::Won(winnings)) => (*winnings, PayState::Paid),
+                    Some(ParlayResult::Void) => (relevant_parlay.bet_amount, PayState::RefundPaid),
+                    Some(ParlayResult::Lost) => panic!("Not every leg of your parlay won"),
+                    None => panic!("The parlay must be settled with settle_parlay to claim funds"),
                 };
 
-                // Transfer USDC of amount potential_winnings to the bettor
-                ft_contract::ext(self.usdc_token_contract.clone())
-                    .with_attached_deposit(NearToken::from_yoctonear(1))
-                    .with_static_gas(Gas::from_tgas(30))
-                    .ft_transfer(bettor.clone(), relevant_bet.potential_winnings)
-                    .then(
-                        Self::ext(env::current_account_id())
-                            .with_static_gas(Gas::from_tgas(50))
-                            .claim_callback(
-                                bettor,
-                                bet_id,
-                                relevant_bet.potential_winnings,
-                                PayState::Paid,
-                            ),
-                    );
-
-                relevant_bet.pay_state = Some(PayState::Paid);
-            }
-            MatchState::Error => {
-                // Transfer USDC of amount bet_amount to the bettor
-                ft_contract::ext(self.usdc_token_contract.clone())
-                    .with_attached_deposit(NearToken::from_yoctonear(1))
-                    .with_static_gas(Gas::from_tgas(30))
-                    .ft_transfer(bettor.clone(), relevant_bet.bet_amount)
-                    .then(
-                        Self::ext(env::current_account_id())
-                            .with_static_gas(Gas::from_tgas(50))
-                            .claim_callback(
-                                bettor,
-                                bet_id,
-                                relevant_bet.bet_amount,
-                                PayState::RefundPaid,
-                            ),
-                    );
-                relevant_bet.pay_state = Some(PayState::RefundPaid);
+                relevant_parlay.pay_state = Some(pay_state.clone());
+
+                (amount, pay_state)
             }
-            _ => panic!("Match state must be Finished or Error to claim funds"),
-        }
+        };
+
+        // Transfer USDC of amount potential_winnings or bet_amount to the bettor
+        ft_contract::ext(self.usdc_token_contract.clone())
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .with_static_gas(Gas::from_tgas(30))
+            .ft_transfer(bettor.clone(), amount)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(Gas::from_tgas(50))
+                    .claim_callback(bettor, bet_id, amount, pay_state),
+            );
     }
 
     #[private]
@@ -204,7 +275,17 @@ impl Contract {
                 .get_mut(&bet_id)
                 .unwrap_or_else(|| panic!("No bet exists with bet id: {:?}", bet_id));
 
-            relevant_bet.pay_state = None;
+            *relevant_bet.pay_state_mut() = None;
+
+            // Put a bet that failed to be cashed out back into the pool
+            if let (PayState::CashedOut, UserBet::Single(relevant_bet)) = (&pay_state, relevant_bet)
+            {
+                let relevant_match = self.matches.get_mut(&relevant_bet.match_id).unwrap();
+                let outcome = &mut relevant_match.outcomes[relevant_bet.team.index()];
+                outcome.total_bets = U128(outcome.total_bets.0 + amount_received.0);
+                outcome.potential_winnings =
+                    U128(outcome.potential_winnings.0 + relevant_bet.potential_winnings.0);
+            }
 
             return "Failed transfer".to_string();
         }
@@ -226,6 +307,14 @@ impl Contract {
                 }
                 .emit();
             }
+            PayState::CashedOut => {
+                events::Event::CashOut {
+                    account_id: &bettor,
+                    bet_id,
+                    amount_received,
+                }
+                .emit();
+            }
         }
 
         return "Successful transfer".to_string();
@@ -233,23 +322,21 @@ impl Contract {
 }
 
 // Function to determine potential winnings
+// total_bets holds the total bets on every outcome of the match
 pub fn determine_potential_winnings(
-    team: &Team,
-    team_1_total_bets: &U128,
-    team_2_total_bets: &U128,
+    outcome: usize,
+    total_bets: &[U128],
     bet_amount: &U128,
+    market_margin_bps: u32,
 ) -> U128 {
-    let (betted_team_bets, other_team_bets) = match team {
-        Team::Team1 => (team_1_total_bets, team_2_total_bets),
-        Team::Team2 => (team_2_total_bets, team_1_total_bets),
-    };
-
-    let betted_team_bets = betted_team_bets.0 as f64;
-    let other_team_bets = other_team_bets.0 as f64;
-    let bet_amount = bet_amount.0 as f64;
+    let betted_outcome_bets = total_bets[outcome];
+    let other_outcomes_bets = other_outcomes_bets(outcome, total_bets);
 
-    let ln_target = (betted_team_bets + bet_amount) / betted_team_bets;
-    let val = (1.0 / 1.05) * (bet_amount + other_team_bets * ln_target.ln());
+    // winnings = (bet_amount + other_outcomes_bets * ln((betted_outcome_bets + bet_amount) / betted_outcome_bets)) / (1 + margin)
+    let ln_target = ln_fixed(betted_outcome_bets.0 + bet_amount.0, betted_outcome_bets.0);
+    let val =
+        U256::from(bet_amount.0) * U256::from(FIXED_POINT_ONE) + other_outcomes_bets * ln_target;
 
-    U128(val as u128)
+    // Only round down once at the end
+    U128((apply_market_margin(val, market_margin_bps) / U256::from(FIXED_POINT_ONE)).as_u128())
 }
@@ -1,29 +1,51 @@
 use near_sdk::json_types::U128;
 
-use crate::{betting::bettor::determine_potential_winnings, Team};
+use crate::betting::bettor::determine_potential_winnings;
+use crate::betting::cash_out::determine_cash_out_value;
+use crate::betting::pricing::{
+    determine_initial_pools, determine_parlay_winnings, ln_fixed, LN_MAX_ERROR,
+};
+use crate::betting::view_betting::determine_approx_odds;
+use crate::{Team, DEFAULT_MARKET_MARGIN_BPS, DEFAULT_WEIGHT_FACTOR, FIXED_POINT_ONE, ONE_USDC};
 
 #[test]
 fn test_determine_potential_winnings() {
-    determine_potential_winnings_base(500_000_000, 1000_000_000, 100_000_000, 268_877_673);
+    determine_potential_winnings_base(
+        Team::Team1,
+        vec![500_000_000, 1_000_000_000],
+        100_000_000,
+        268_877_673,
+    );
+}
+
+#[test]
+fn test_determine_potential_winnings_draw() {
+    determine_potential_winnings_base(
+        Team::Draw,
+        vec![400_000_000, 300_000_000, 300_000_000],
+        100_000_000,
+        287_026_143,
+    );
 }
 
 fn determine_potential_winnings_base(
-    team_1_total_bets: u128,
-    team_2_total_bets: u128,
+    team: Team,
+    total_bets: Vec<u128>,
     bet_amount: u128,
     expected_winnings: u128,
 ) {
-    let team_1 = Team::Team1;
-
-    let team_1_total_bets = U128(team_1_total_bets);
-    let team_2_total_bets = U128(team_2_total_bets);
+    let total_bets: Vec<U128> = total_bets.into_iter().map(U128).collect();
 
     let bet_amount = U128(bet_amount);
 
     let expected_potential_winnings = U128(expected_winnings);
 
-    let actual_potential_winnings =
-        determine_potential_winnings(&team_1, &team_1_total_bets, &team_2_total_bets, &bet_amount);
+    let actual_potential_winnings = determine_potential_winnings(
+        team.index(),
+        &total_bets,
+        &bet_amount,
+        DEFAULT_MARKET_MARGIN_BPS,
+    );
 
     assert_eq!(
         expected_potential_winnings, actual_potential_winnings,
@@ -31,3 +53,185 @@ fn determine_potential_winnings_base(
         actual_potential_winnings.0, expected_potential_winnings.0
     );
 }
+
+#[test]
+fn test_ln_fixed_is_rounded_down_within_bound() {
+    // Exact values of ln scaled by 10^18 and rounded down
+    ln_fixed_base(1, 1, 0);
+    ln_fixed_base(2, 1, 693_147_180_559_945_309);
+    ln_fixed_base(6, 5, 182_321_556_793_954_626);
+    ln_fixed_base(1_000_000, 1, 13_815_510_557_964_274_104);
+    ln_fixed_base(
+        571_000_000 + 10_000_000,
+        571_000_000,
+        17_361_547_195_901_011,
+    );
+}
+
+fn ln_fixed_base(numerator: u128, denominator: u128, expected: u128) {
+    let actual = ln_fixed(numerator, denominator).as_u128();
+
+    assert!(
+        actual <= expected && expected - actual <= LN_MAX_ERROR,
+        "ln calculation error. Actual: {} Expected: {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn test_approx_odds() {
+    let odds = determine_approx_odds(
+        &[U128(571_000_000), U128(429_000_000)],
+        DEFAULT_MARKET_MARGIN_BPS,
+    );
+
+    // 1000 / (571 * 1.05) and 1000 / (429 * 1.05) rounded down
+    assert_eq!(
+        odds,
+        vec![
+            U128(1_667_917_604_870_319_406),
+            U128(2_220_002_220_002_220_002)
+        ]
+    );
+
+    let odds = determine_approx_odds(
+        &[U128(400_000_000), U128(300_000_000), U128(300_000_000)],
+        DEFAULT_MARKET_MARGIN_BPS,
+    );
+
+    // 1000 / (400 * 1.05) and 1000 / (300 * 1.05) rounded down
+    assert_eq!(
+        odds,
+        vec![
+            U128(2_380_952_380_952_380_952),
+            U128(3_174_603_174_603_174_603),
+            U128(3_174_603_174_603_174_603)
+        ]
+    );
+}
+
+#[test]
+fn test_initial_pools() {
+    let initial_pools = determine_initial_pools(
+        &[
+            U128(1_200_000_000_000_000_000),
+            U128(1_600_000_000_000_000_000),
+        ],
+        DEFAULT_WEIGHT_FACTOR,
+    );
+
+    assert_eq!(
+        initial_pools,
+        vec![U128(571 * ONE_USDC), U128(429 * ONE_USDC)]
+    );
+
+    let initial_pools = determine_initial_pools(
+        &[
+            U128(2_500_000_000_000_000_000),
+            U128(3_200_000_000_000_000_000),
+            U128(3_500_000_000_000_000_000),
+        ],
+        DEFAULT_WEIGHT_FACTOR,
+    );
+
+    assert_eq!(
+        initial_pools,
+        vec![
+            U128(401 * ONE_USDC),
+            U128(313 * ONE_USDC),
+            U128(286 * ONE_USDC)
+        ]
+    );
+}
+
+#[test]
+fn test_initial_pools_for_market() {
+    // Eight outcomes with even odds
+    let initial_pools =
+        determine_initial_pools(&[U128(8 * FIXED_POINT_ONE); 8], DEFAULT_WEIGHT_FACTOR);
+
+    assert_eq!(initial_pools, vec![U128(125 * ONE_USDC); 8]);
+
+    let initial_pools = determine_initial_pools(
+        &[
+            U128(2 * FIXED_POINT_ONE),
+            U128(4 * FIXED_POINT_ONE),
+            U128(5 * FIXED_POINT_ONE),
+            U128(10 * FIXED_POINT_ONE),
+        ],
+        DEFAULT_WEIGHT_FACTOR,
+    );
+
+    assert_eq!(
+        initial_pools,
+        vec![
+            U128(476 * ONE_USDC),
+            U128(238 * ONE_USDC),
+            U128(190 * ONE_USDC),
+            U128(95 * ONE_USDC)
+        ]
+    );
+}
+
+#[test]
+fn test_parlay_winnings() {
+    let odds_1 = U128(1_667_917_604_870_319_406);
+    let odds_2 = U128(2_220_002_220_002_220_002);
+
+    // 10 * 1.6679... * 2.2200... rounded down
+    assert_eq!(
+        determine_parlay_winnings(U128(10 * ONE_USDC), &[odds_1, odds_2]),
+        U128(37_027_807)
+    );
+
+    // A parlay with one leg left after the others are void pays out like a single bet
+    assert_eq!(
+        determine_parlay_winnings(U128(10 * ONE_USDC), &[odds_1]),
+        U128(16_679_176)
+    );
+}
+
+#[test]
+fn test_cash_out_value() {
+    // A bet of 10 USDC on team 1 when the pools were 571 and 429 USDC
+    let potential_winnings = U128(16_617_241);
+    let initial_pool = U128(571 * ONE_USDC);
+
+    // Cashing out straight away returns the bet amount
+    cash_out_value_base(vec![581, 429], initial_pool, potential_winnings, 10_000_000);
+
+    // The value falls when others bet on team 2
+    cash_out_value_base(vec![581, 529], initial_pool, potential_winnings, 9_098_436);
+
+    // The value rises when others bet on team 1
+    cash_out_value_base(vec![681, 429], initial_pool, potential_winnings, 10_671_987);
+}
+
+fn cash_out_value_base(
+    total_bets: Vec<u128>,
+    initial_pool: U128,
+    potential_winnings: U128,
+    expected_value: u128,
+) {
+    let total_bets: Vec<U128> = total_bets
+        .into_iter()
+        .map(|bets| U128(bets * ONE_USDC))
+        .collect();
+
+    let actual_value = determine_cash_out_value(
+        Team::Team1.index(),
+        &total_bets,
+        initial_pool,
+        potential_winnings,
+        DEFAULT_MARKET_MARGIN_BPS,
+    );
+
+    assert_eq!(
+        actual_value,
+        U128(expected_value),
+        "Cash out value error. Actual: {} Expected: {}",
+        actual_value.0,
+        expected_value
+    );
+}
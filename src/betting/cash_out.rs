@@ -0,0 +1,147 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, near, require, Gas, NearToken};
+
+use crate::betting::bettor::determine_potential_winnings;
+pub use crate::ext::*;
+use crate::*;
+
+#[near]
+impl Contract {
+    // Sells a bet back to the pool while betting on its match is still open
+    pub fn cash_out(&mut self, bet_id: BetId) {
+        require!(
+            env::prepaid_gas() >= Gas::from_tgas(150),
+            "You need to attach 150 TGas"
+        );
+
+        require!(!self.pause_state.claiming, "Claiming is paused");
+
+        let bettor = env::predecessor_account_id();
+
+        // Bets made before the state was versioned are moved on first access
+        self.migrate_legacy_bets(&bettor);
+
+        // Get relevant bet
+        let relevant_bet = match self
+            .bets_by_user
+            .get_mut(&bettor)
+            .and_then(|relevant_user| relevant_user.get_mut(&bet_id))
+        {
+            Some(UserBet::Single(relevant_bet)) => relevant_bet,
+            Some(UserBet::Parlay(_)) => panic!("Parlays cannot be cashed out"),
+            None => panic!("No bet exists with bet id: {:?}", bet_id),
+        };
+
+        require!(
+            relevant_bet.pay_state.is_none(),
+            "You have already been paid out"
+        );
+
+        // Get relevant match
+        let relevant_match = self
+            .matches
+            .get_mut(&relevant_bet.match_id)
+            .unwrap_or_else(|| {
+                panic!(
+                    "No match exists with match id: {} there must have been an error",
+                    relevant_bet.match_id
+                )
+            });
+
+        require!(
+            matches!(relevant_match.effective_state(), MatchState::Future),
+            "Match state must be Future to cash out"
+        );
+
+        let outcome = relevant_bet.team.index();
+        let amount = determine_cash_out_value(
+            outcome,
+            &relevant_match.total_bets(),
+            relevant_match.outcomes[outcome].initial_pool,
+            relevant_bet.potential_winnings,
+            self.config.market_margin_bps,
+        );
+
+        require!(amount.0 > 0, "The bet has no cash out value");
+
+        // Remove the amount paid out and the bet's winnings from the pool
+        let outcome_pool = &mut relevant_match.outcomes[outcome];
+        outcome_pool.total_bets = U128(outcome_pool.total_bets.0 - amount.0);
+        outcome_pool.potential_winnings =
+            U128(outcome_pool.potential_winnings.0 - relevant_bet.potential_winnings.0);
+
+        relevant_bet.pay_state = Some(PayState::CashedOut);
+
+        // Transfer the cash out value to the bettor
+        // if the transfer fails the bet is put back into the pool
+        ft_contract::ext(self.usdc_token_contract.clone())
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .with_static_gas(Gas::from_tgas(30))
+            .ft_transfer(bettor.clone(), amount)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(Gas::from_tgas(50))
+                    .claim_callback(bettor, bet_id, amount, PayState::CashedOut),
+            );
+    }
+
+    // Returns the amount of USDC a bet can currently be cashed out for
+    pub fn get_cash_out_value(&self, bettor: &AccountId, bet_id: &BetId) -> U128 {
+        let relevant_bet = match self.get_bet(bettor, bet_id) {
+            UserBet::Single(relevant_bet) => relevant_bet,
+            UserBet::Parlay(_) => panic!("Parlays cannot be cashed out"),
+        };
+
+        require!(
+            relevant_bet.pay_state.is_none(),
+            "The bet has already been paid out"
+        );
+
+        // Get relevant match
+        let relevant_match = self
+            .matches
+            .get(&relevant_bet.match_id)
+            .unwrap_or_else(|| panic!("No match exists with match id: {}", relevant_bet.match_id));
+
+        let outcome = relevant_bet.team.index();
+        determine_cash_out_value(
+            outcome,
+            &relevant_match.total_bets(),
+            relevant_match.outcomes[outcome].initial_pool,
+            relevant_bet.potential_winnings,
+            self.config.market_margin_bps,
+        )
+    }
+}
+
+// Function to determine the cash out value of a bet using the inverse of the payout curve
+// the value is the largest amount that, if bet on the outcome after being taken out of its pool,
+// would win at most the bet's potential winnings
+// the pool never drops below its initial pool
+pub fn determine_cash_out_value(
+    outcome: usize,
+    total_bets: &[U128],
+    initial_pool: U128,
+    potential_winnings: U128,
+    market_margin_bps: u32,
+) -> U128 {
+    let mut pools = total_bets.to_vec();
+    let mut low = 0;
+    let mut high = total_bets[outcome].0 - initial_pool.0;
+
+    // The payout curve is increasing so binary search for the value, rounding down
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        pools[outcome] = U128(total_bets[outcome].0 - mid);
+
+        if determine_potential_winnings(outcome, &pools, &U128(mid), market_margin_bps)
+            <= potential_winnings
+        {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    U128(low)
+}
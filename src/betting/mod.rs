@@ -1,5 +1,10 @@
 pub mod bettor;
+pub mod cash_out;
+pub mod parlay;
+pub mod pricing;
 pub mod view_betting;
 
 #[cfg(test)]
 mod bettor_tests;
+#[cfg(test)]
+mod parlay_tests;
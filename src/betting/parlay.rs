@@ -0,0 +1,246 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{near, require, PromiseOrValue};
+
+use crate::betting::pricing::determine_parlay_winnings;
+use crate::betting::view_betting::determine_approx_odds;
+use crate::dex::gas::settle_gas;
+use crate::events::Event;
+use crate::*;
+
+#[near(serializers = [json])]
+pub struct ParlayLegInfo {
+    pub match_id: MatchId,
+    pub team: Team,
+}
+
+#[near]
+impl Contract {
+    // Function to bet on several matches at once with USDC
+    // Returns the amount of USDC to refund to the bettor
+    pub(crate) fn bet_parlay(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        legs: Vec<ParlayLegInfo>,
+        min_potential_winnings: Option<U128>,
+        deadline: Option<U64>,
+    ) -> U128 {
+        // Validate the parlay, if it is rejected the full amount is refunded
+        let (legs, potential_winnings) =
+            match self.validate_parlay(amount, legs, min_potential_winnings, deadline) {
+                Ok(parlay) => parlay,
+                Err(reason) => {
+                    Event::ParlayRejected {
+                        account_id: &sender_id,
+                        amount,
+                        reason,
+                    }
+                    .emit();
+
+                    return amount;
+                }
+            };
+
+        // The parlay does not change the pools of its matches
+        // so its potential winnings are tracked separately
+        self.parlay_exposure = U128(self.parlay_exposure.0 + potential_winnings.0);
+
+        // Creates a new parlay
+        let new_parlay = Parlay {
+            legs: legs.clone(),
+            bet_amount: amount,
+            potential_winnings,
+            result: None,
+            pay_state: None,
+        };
+
+        // Increments bet ID
+        self.last_bet_id.0 += 1;
+        let bet_id_string = self.last_bet_id.0.to_string();
+
+        // Inserts the new parlay, creates a new map if the user has not bet previously
+        if self.bets_by_user.get(&sender_id).is_none() {
+            let new_map: IterableMap<BetId, UserBet> = IterableMap::new(bet_id_string.as_bytes());
+            self.bets_by_user.insert(sender_id.clone(), new_map);
+        };
+
+        let bets_by_user = self.bets_by_user.get_mut(&sender_id).unwrap();
+
+        bets_by_user.insert(self.last_bet_id, UserBet::Parlay(new_parlay));
+
+        Event::Parlay {
+            account_id: &sender_id,
+            bet_id: self.last_bet_id,
+            amount,
+            legs,
+            potential_winnings,
+        }
+        .emit();
+
+        U128(0)
+    }
+
+    // Checks a parlay can be placed and returns its legs with their odds and its potential
+    // winnings or the reason the parlay is rejected
+    pub(crate) fn validate_parlay(
+        &self,
+        amount: U128,
+        legs: Vec<ParlayLegInfo>,
+        min_potential_winnings: Option<U128>,
+        deadline: Option<U64>,
+    ) -> Result<(Vec<ParlayLeg>, U128), RejectionReason> {
+        self.validate_bet_transfer(amount, deadline)?;
+
+        if !(MIN_PARLAY_LEGS..=MAX_PARLAY_LEGS).contains(&legs.len()) {
+            return Err(RejectionReason::InvalidParlay);
+        }
+
+        // Each leg must be on a different match
+        if legs
+            .iter()
+            .enumerate()
+            .any(|(index, leg)| legs[..index].iter().any(|l| l.match_id == leg.match_id))
+        {
+            return Err(RejectionReason::InvalidParlay);
+        }
+
+        let mut parlay_legs = Vec::with_capacity(legs.len());
+        for leg in legs {
+            // Get relevant match
+            let relevant_match = self
+                .matches
+                .get(&leg.match_id)
+                .ok_or(RejectionReason::MatchNotFound)?;
+
+            if !matches!(relevant_match.effective_state(), MatchState::Future) {
+                return Err(RejectionReason::BettingClosed);
+            }
+
+            if relevant_match.outcome(&leg.team).is_none() {
+                return Err(RejectionReason::InvalidOutcome);
+            }
+
+            // The leg is fixed at the current odds of the team
+            let odds =
+                determine_approx_odds(&relevant_match.total_bets(), self.config.market_margin_bps)
+                    [leg.team.index()];
+
+            parlay_legs.push(ParlayLeg {
+                match_id: leg.match_id,
+                team: leg.team,
+                odds,
+            });
+        }
+
+        let leg_odds: Vec<U128> = parlay_legs.iter().map(|leg| leg.odds).collect();
+        let potential_winnings = determine_parlay_winnings(amount, &leg_odds);
+
+        // Reject the parlay if the odds moved against the bettor since they checked them
+        if let Some(min_potential_winnings) = min_potential_winnings {
+            if potential_winnings < min_potential_winnings {
+                return Err(RejectionReason::PotentialWinningsTooLow);
+            }
+        }
+
+        // Parlays do not move the pools of their matches so the total that can be won on them is capped
+        if self.parlay_exposure.0 + potential_winnings.0 > self.config.max_parlay_exposure.0 {
+            return Err(RejectionReason::ParlayExposureTooHigh);
+        }
+
+        Ok((parlay_legs, potential_winnings))
+    }
+
+    // Settles a parlay once a leg has lost or every match in it is finished or cancelled
+    // the profit or loss is handled in the same way as for a match
+    // can be called by anyone
+    pub fn settle_parlay(&mut self, bettor: AccountId, bet_id: BetId) -> PromiseOrValue<()> {
+        self.require_dex_gas(settle_gas);
+
+        // Get relevant parlay
+        let relevant_parlay = match self
+            .bets_by_user
+            .get_mut(&bettor)
+            .and_then(|relevant_user| relevant_user.get_mut(&bet_id))
+        {
+            Some(UserBet::Parlay(relevant_parlay)) => relevant_parlay,
+            _ => panic!("No parlay exists with bet id: {:?}", bet_id),
+        };
+
+        require!(
+            relevant_parlay.result.is_none(),
+            "The parlay has already been settled"
+        );
+
+        let result = determine_parlay_result(relevant_parlay, &self.matches)
+            .unwrap_or_else(|| panic!("The parlay cannot be settled until its matches finish"));
+
+        relevant_parlay.result = Some(result.clone());
+
+        let bet_amount = relevant_parlay.bet_amount.0;
+        let winnings = match &result {
+            ParlayResult::Won(winnings) => winnings.0,
+            ParlayResult::Lost => 0,
+            ParlayResult::Void => bet_amount,
+        };
+
+        self.parlay_exposure = U128(self.parlay_exposure.0 - relevant_parlay.potential_winnings.0);
+
+        Event::SettleParlay {
+            account_id: &bettor,
+            bet_id,
+            result,
+        }
+        .emit();
+
+        self.settle(bet_amount, winnings)
+    }
+}
+
+// Determines the result of a parlay from the state of its matches
+// cancelled matches are void and drop out of the winnings
+// returns None if the parlay cannot be settled yet
+pub fn determine_parlay_result(
+    parlay: &Parlay,
+    matches: &IterableMap<MatchId, Match>,
+) -> Option<ParlayResult> {
+    let mut winning_odds = Vec::with_capacity(parlay.legs.len());
+    let mut is_settled = true;
+
+    for leg in parlay.legs.iter() {
+        let relevant_match = matches.get(&leg.match_id).unwrap_or_else(|| {
+            panic!(
+                "No match exists with match id: {} there must have been an error",
+                leg.match_id
+            )
+        });
+
+        match relevant_match.match_state {
+            MatchState::Finished => {
+                // One losing leg loses the whole parlay
+                if relevant_match.winner.as_ref() != Some(&leg.team) {
+                    return Some(ParlayResult::Lost);
+                }
+
+                winning_odds.push(leg.odds);
+            }
+            MatchState::Error => {}
+            MatchState::Future
+            | MatchState::Current
+            | MatchState::PendingResult
+            | MatchState::Disputed => is_settled = false,
+        }
+    }
+
+    if !is_settled {
+        return None;
+    }
+
+    if winning_odds.is_empty() {
+        return Some(ParlayResult::Void);
+    }
+
+    Some(ParlayResult::Won(determine_parlay_winnings(
+        parlay.bet_amount,
+        &winning_odds,
+    )))
+}
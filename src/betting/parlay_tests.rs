@@ -0,0 +1,69 @@
+use near_sdk::json_types::{U128, U64};
+
+use crate::betting::parlay::ParlayLegInfo;
+use crate::test_utils::*;
+use crate::*;
+
+const START_TIME: u64 = 1_000;
+
+// Sets up a contract with two matches at even odds
+fn setup() -> Contract {
+    let mut contract = init_contract(1_000);
+
+    for (team_1, team_2) in [("RUBY", "Nexus"), ("Spirit", "Navi")] {
+        contract.create_match(
+            "CSGO".to_string(),
+            team_1.to_string(),
+            team_2.to_string(),
+            U128(2 * FIXED_POINT_ONE),
+            U128(2 * FIXED_POINT_ONE),
+            None,
+            "17/08/2024".to_string(),
+            U64(START_TIME),
+            None,
+        );
+    }
+
+    contract
+}
+
+fn legs() -> Vec<ParlayLegInfo> {
+    vec![
+        ParlayLegInfo {
+            match_id: "RUBY-Nexus-17/08/2024".to_string(),
+            team: Team::Team1,
+        },
+        ParlayLegInfo {
+            match_id: "Spirit-Navi-17/08/2024".to_string(),
+            team: Team::Team2,
+        },
+    ]
+}
+
+#[test]
+fn test_parlay_over_max_exposure() {
+    let mut contract = setup();
+
+    // Alice makes a parlay of 10 USDC
+    new_call_from(usdc(), 1);
+    let refund = contract.bet_parlay(alice(), U128(10 * ONE_USDC), legs(), None, None);
+    assert_eq!(refund, U128(0));
+    let exposure = contract.parlay_exposure;
+    assert!(exposure.0 > 10 * ONE_USDC);
+
+    // The maximum exposure is lowered to just under what a second parlay would take it to
+    contract.config.max_parlay_exposure = U128(2 * exposure.0 - 1);
+
+    // Bob's parlay of the same amount is refunded and the exposure does not change
+    new_call_from(usdc(), 2);
+    let refund = contract.bet_parlay(bob(), U128(10 * ONE_USDC), legs(), None, None);
+    assert_eq!(refund, U128(10 * ONE_USDC));
+    assert_eq!(contract.parlay_exposure, exposure);
+    assert!(contract.bets_by_user.get(&bob()).is_none());
+
+    // A smaller parlay still fits under the maximum
+    new_call_from(usdc(), 3);
+    let refund = contract.bet_parlay(bob(), U128(5 * ONE_USDC), legs(), None, None);
+    assert_eq!(refund, U128(0));
+    assert!(contract.parlay_exposure.0 <= contract.config.max_parlay_exposure.0);
+}
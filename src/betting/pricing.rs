@@ -0,0 +1,125 @@
+use near_sdk::require;
+
+use crate::*;
+
+// Fixed-point implementation of the payout curve
+//
+// All values are scaled by FIXED_POINT_ONE (10^18) and every division rounds down,
+// so every result is at most the exact mathematical value. This means rounding always
+// goes in the house's favour and the same inputs give the same output off-chain.
+//
+// Precision: ln_fixed underestimates the exact natural log by at most LN_MAX_ERROR
+// units of 10^-18, so potential winnings are at most
+// (other_team_bets * LN_MAX_ERROR / 10^18) + 1 units below the exact value.
+// For pools below 5 billion USDC this is at most 1 unit of USDC's lowest denomination.
+
+// ln(2) scaled by FIXED_POINT_ONE, rounded down
+const LN_2: u128 = 693_147_180_559_945_309;
+
+// The maximum amount ln_fixed can underestimate the exact value by, scaled by FIXED_POINT_ONE
+pub const LN_MAX_ERROR: u128 = 200;
+
+// Calculates ln(numerator / denominator) scaled by FIXED_POINT_ONE and rounded down
+// numerator must be greater than or equal to denominator
+pub fn ln_fixed(numerator: u128, denominator: u128) -> U256 {
+    require!(denominator > 0, "Cannot take the log of a division by zero");
+    require!(
+        numerator >= denominator,
+        "Cannot take the log of a number less than one"
+    );
+
+    let one = U256::from(FIXED_POINT_ONE);
+    let numerator = U256::from(numerator);
+    let denominator = U256::from(denominator);
+
+    // Find k such that 2^k <= numerator / denominator < 2^(k + 1)
+    let mut k = numerator.bits() - denominator.bits();
+    if (denominator << k) > numerator {
+        k -= 1;
+    }
+
+    // y = numerator / (denominator * 2^k) is in the range [1, 2)
+    let y = numerator * one / (denominator << k);
+
+    // ln(y) = 2 * atanh(z) = 2 * (z + z^3 / 3 + z^5 / 5 + ...) where z = (y - 1) / (y + 1)
+    // z is in the range [0, 1/3) so each term is at most a ninth of the previous one
+    let z = (y - one) * one / (y + one);
+    let z_squared = z * z / one;
+
+    let mut series = U256::zero();
+    let mut term = z;
+    let mut divisor = U256::one();
+    while !term.is_zero() {
+        series += term / divisor;
+        term = term * z_squared / one;
+        divisor += U256::from(2);
+    }
+
+    U256::from(2) * series + U256::from(k) * U256::from(LN_2)
+}
+
+// Applies the market margin to an amount, rounded down
+// Divide the result rather than the input to avoid rounding twice
+pub fn apply_market_margin(amount: U256, market_margin_bps: u32) -> U256 {
+    amount * U256::from(BASIS_POINTS) / U256::from(BASIS_POINTS + u128::from(market_margin_bps))
+}
+
+// Calculates the initial pool sizes for each outcome from the decimal odds given by the admin
+// the pools are rounded to the nearest whole USDC
+pub fn determine_initial_pools(in_odds: &[U128], weight_factor: u32) -> Vec<U128> {
+    require!(
+        in_odds.iter().all(|odds| odds.0 >= FIXED_POINT_ONE),
+        "Odds must be at least 1"
+    );
+
+    let one = U256::from(FIXED_POINT_ONE);
+
+    // Implied probabilities
+    let in_probs: Vec<U256> = in_odds
+        .iter()
+        .map(|odds| one * one / U256::from(odds.0))
+        .collect();
+    let divider = in_probs
+        .iter()
+        .fold(U256::zero(), |sum, in_prob| sum + *in_prob);
+
+    // Normalise the probabilities and weight them, rounding half up
+    let initial_pools: Vec<U128> = in_probs
+        .iter()
+        .map(|in_prob| {
+            let whole_usdc = (U256::from(2) * U256::from(weight_factor) * *in_prob + divider)
+                / (U256::from(2) * divider);
+            U128(ONE_USDC * whole_usdc.as_u128())
+        })
+        .collect();
+
+    require!(
+        initial_pools.iter().all(|pool| pool.0 > 0),
+        "Odds are too extreme to create a match"
+    );
+
+    initial_pools
+}
+
+// Calculates the sum of the total bets on every outcome other than the given one
+pub fn other_outcomes_bets(outcome: usize, total_bets: &[U128]) -> U256 {
+    total_bets
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != outcome)
+        .fold(U256::zero(), |sum, (_, bets)| sum + U256::from(bets.0))
+}
+
+// Calculates the winnings of a parlay from the decimal odds of each leg, rounded down
+// the odds are multiplied one at a time so each step rounds down
+pub fn determine_parlay_winnings(bet_amount: U128, leg_odds: &[U128]) -> U128 {
+    let one = U256::from(FIXED_POINT_ONE);
+
+    let winnings = leg_odds
+        .iter()
+        .fold(U256::from(bet_amount.0) * one, |winnings, odds| {
+            winnings * U256::from(odds.0) / one
+        });
+
+    U128((winnings / one).as_u128())
+}
@@ -1,7 +1,8 @@
 use near_sdk::json_types::U128;
-use near_sdk::near;
+use near_sdk::{near, require};
 
 use crate::betting::bettor::determine_potential_winnings;
+use crate::betting::pricing::apply_market_margin;
 use crate::*;
 
 #[near(serializers = [json])]
@@ -10,8 +11,7 @@ pub struct ContractInfo {
     usdc_token_contract: AccountId,
     vex_token_contract: AccountId,
     treasury: AccountId,
-    ref_contract: AccountId,
-    ref_pool_id: U64,
+    dex_adapter: DexAdapter,
 }
 
 #[near(serializers = [json])]
@@ -20,12 +20,17 @@ pub struct DisplayMatch {
     pub game: String,
     pub team_1: String,
     pub team_2: String,
-    pub team_1_odds: f64,
-    pub team_2_odds: f64,
+    pub team_1_odds: U128,
+    pub team_2_odds: U128,
+    pub draw_odds: Option<U128>,
     pub team_1_real_bets: U128,
     pub team_2_real_bets: U128,
+    pub draw_real_bets: Option<U128>,
+    pub sub_markets: Vec<MarketId>,
+    pub start_time: U64,
     pub match_state: MatchState,
     pub winner: Option<Team>,
+    pub external_ref: Option<String>,
 }
 
 #[near]
@@ -37,11 +42,15 @@ impl Contract {
             usdc_token_contract: self.usdc_token_contract.clone(),
             vex_token_contract: self.vex_token_contract.clone(),
             treasury: self.treasury.clone(),
-            ref_contract: self.ref_contract.clone(),
-            ref_pool_id: U64(self.ref_pool_id),
+            dex_adapter: self.dex_adapter.clone(),
         }
     }
 
+    // Returns the account proposed to become the admin if it has not accepted yet
+    pub fn get_pending_admin(&self) -> Option<&AdminProposal> {
+        self.pending_admin.as_ref()
+    }
+
     // Returns a list of matches wihtin a range
     pub fn get_matches(&self, from_index: &Option<u32>, limit: &Option<u32>) -> Vec<DisplayMatch> {
         let from = from_index.unwrap_or(0);
@@ -52,7 +61,7 @@ impl Contract {
             .iter()
             .skip(from as usize)
             .take(limit as usize)
-            .map(|(match_id, m)| format_match(match_id, m))
+            .map(|(match_id, m)| format_match(match_id, m, self.config.market_margin_bps))
             .collect()
     }
 
@@ -65,7 +74,18 @@ impl Contract {
             .unwrap_or_else(|| panic!("No match exists with match id: {}", match_id));
 
         // Return formated match
-        format_match(match_id, relevant_match)
+        format_match(match_id, relevant_match, self.config.market_margin_bps)
+    }
+
+    // Returns a single match by the ID of the match at the external data provider
+    pub fn get_match_by_external_ref(&self, external_ref: String) -> DisplayMatch {
+        // Get relevant match ID
+        let match_id = self
+            .match_ids_by_external_ref
+            .get(&external_ref)
+            .unwrap_or_else(|| panic!("No match exists with external ref: {}", external_ref));
+
+        self.get_match(match_id)
     }
 
     // Returns the potential winnings you would get if you bet a certain
@@ -82,51 +102,77 @@ impl Contract {
             .get(match_id)
             .unwrap_or_else(|| panic!("No match exists with match id: {}", match_id));
 
+        require!(
+            relevant_match.outcome(team).is_some(),
+            "This outcome is not possible in this match"
+        );
+
         // Return potential winnings
         determine_potential_winnings(
-            team,
-            &relevant_match.team_1_total_bets,
-            &relevant_match.team_2_total_bets,
+            team.index(),
+            &relevant_match.total_bets(),
             bet_amount,
+            self.config.market_margin_bps,
         )
     }
 
-    // Returns a specific bet by its user and ID
-    pub fn get_bet(&self, bettor: &AccountId, bet_id: &BetId) -> &Bet {
-        // Get relevant user
-        let relevant_user = self
-            .bets_by_user
-            .get(bettor)
-            .unwrap_or_else(|| panic!("No user exists with Account ID: {:?}", bettor));
+    // Returns a specific bet or parlay by its user and ID
+    pub fn get_bet(&self, bettor: &AccountId, bet_id: &BetId) -> UserBet {
+        let relevant_user = self.bets_by_user.get(bettor);
+        let legacy_bets = self.legacy_bets_by_user.get(bettor);
 
-        // Return relevant bet
+        require!(
+            relevant_user.is_some() || legacy_bets.is_some(),
+            format!("No user exists with Account ID: {:?}", bettor)
+        );
+
+        // Return relevant bet, bets made before the state was versioned may not have been moved yet
         relevant_user
-            .get(bet_id)
+            .and_then(|relevant_user| relevant_user.get(bet_id).cloned())
+            .or_else(|| {
+                legacy_bets
+                    .and_then(|legacy_bets| legacy_bets.get(bet_id).cloned())
+                    .map(UserBet::Single)
+            })
             .unwrap_or_else(|| panic!("No bet exists with bet id: {:?}", bet_id))
     }
 
-    // Returns a list of bets made by a user within a range
+    // Returns a list of bets and parlays made by a user within a range
     pub fn get_users_bets(
         &self,
         bettor: &AccountId,
         from_index: &Option<u32>,
         limit: &Option<u32>,
-    ) -> Vec<(BetId, &Bet)> {
+    ) -> Vec<(BetId, UserBet)> {
         // Get relevant user's bets
-        let relevant_user_bets = self
-            .bets_by_user
-            .get(bettor)
-            .unwrap_or_else(|| panic!("{} is not a bettor", bettor));
+        let relevant_user_bets = self.bets_by_user.get(bettor);
+        let legacy_bets = self.legacy_bets_by_user.get(bettor);
+
+        require!(
+            relevant_user_bets.is_some() || legacy_bets.is_some(),
+            format!("{} is not a bettor", bettor)
+        );
 
         let from = from_index.unwrap_or(0);
         let limit = limit.unwrap_or(self.matches.len());
 
-        // Return bet IDs and their match IDs
-        relevant_user_bets
-            .iter()
+        // Return bet IDs and their bets, bets made before the state was versioned
+        // that have not been moved yet come first
+        legacy_bets
+            .into_iter()
+            .flat_map(|legacy_bets| {
+                legacy_bets
+                    .iter()
+                    .map(|(&key, value)| (key, UserBet::Single(value.clone())))
+            })
+            .chain(
+                relevant_user_bets
+                    .into_iter()
+                    .flat_map(|relevant_user_bets| relevant_user_bets.iter())
+                    .map(|(&key, value)| (key, value.clone())),
+            )
             .skip(from as usize)
             .take(limit as usize)
-            .map(|(&key, value)| (key, value))
             .collect()
     }
 
@@ -134,51 +180,64 @@ impl Contract {
     pub fn get_funds_to_payout(&self) -> U128 {
         self.funds_to_payout
     }
+
+    // Get the total potential winnings of parlays that have not been settled
+    pub fn get_parlay_exposure(&self) -> U128 {
+        self.parlay_exposure
+    }
 }
 
 // Helper function to format a match to be displayed
-pub fn format_match(match_id: &MatchId, match_struct: &Match) -> DisplayMatch {
-    let (team_1_odds, team_2_odds) = determine_approx_odds(
-        &match_struct.team_1_total_bets,
-        &match_struct.team_2_total_bets,
-    );
+pub fn format_match(
+    match_id: &MatchId,
+    match_struct: &Match,
+    market_margin_bps: u32,
+) -> DisplayMatch {
+    let odds = determine_approx_odds(&match_struct.total_bets(), market_margin_bps);
+
+    // Bets made by users, excluding the initial pool
+    let real_bets: Vec<U128> = match_struct
+        .outcomes
+        .iter()
+        .map(|outcome| U128(outcome.total_bets.0 - outcome.initial_pool.0))
+        .collect();
 
     DisplayMatch {
         match_id: match_id.clone(),
         game: match_struct.game.clone(),
         team_1: match_struct.team_1.clone(),
         team_2: match_struct.team_2.clone(),
-        team_1_odds,
-        team_2_odds,
-        team_1_real_bets: U128(
-            match_struct.team_1_total_bets.0 - match_struct.team_1_initial_pool.0,
-        ),
-        team_2_real_bets: U128(
-            match_struct.team_2_total_bets.0 - match_struct.team_2_initial_pool.0,
-        ),
-        match_state: match_struct.match_state.clone(),
+        team_1_odds: odds[Team::Team1.index()],
+        team_2_odds: odds[Team::Team2.index()],
+        draw_odds: odds.get(Team::Draw.index()).copied(),
+        team_1_real_bets: real_bets[Team::Team1.index()],
+        team_2_real_bets: real_bets[Team::Team2.index()],
+        draw_real_bets: real_bets.get(Team::Draw.index()).copied(),
+        sub_markets: match_struct.sub_markets.clone(),
+        start_time: match_struct.start_time,
+        match_state: match_struct.effective_state(),
         winner: match_struct.winner.clone(),
+        external_ref: match_struct.external_ref.clone(),
     }
 }
 
 // Helper function to determine approximate odds, odds for an infitesimal bet
-pub fn determine_approx_odds(team_1_total_bets: &U128, team_2_total_bets: &U128) -> (f64, f64) {
-    let team_1_bets: f64 = team_1_total_bets.0 as f64;
-    let team_2_bets: f64 = team_2_total_bets.0 as f64;
-
-    // Calculate total bets
-    let total_bets = team_1_bets + team_2_bets;
-
-    // Calculate the divider to make the implied probability sum to 1.05
-    let divider = total_bets / 1.05; // Market margin is set to 5%
-
-    // Calculate implied probabilities
-    let implied_prob_1 = team_1_bets / divider;
-    let implied_prob_2 = team_2_bets / divider;
-
-    // Calculate odds
-    let team_1_odds = 1.0 / implied_prob_1;
-    let team_2_odds = 1.0 / implied_prob_2;
-
-    (team_1_odds, team_2_odds)
+// Odds are decimal odds scaled by FIXED_POINT_ONE and rounded down
+pub fn determine_approx_odds(total_bets: &[U128], market_margin_bps: u32) -> Vec<U128> {
+    // Calculate total bets across all outcomes
+    let sum_bets = total_bets
+        .iter()
+        .fold(U256::zero(), |sum, bets| sum + U256::from(bets.0));
+
+    // Odds are sum_bets / outcome_bets with the market margin taken off
+    total_bets
+        .iter()
+        .map(|outcome_bets| {
+            U128(
+                (apply_market_margin(sum_bets * U256::from(FIXED_POINT_ONE), market_margin_bps)
+                    / U256::from(outcome_bets.0))
+                .as_u128(),
+            )
+        })
+        .collect()
 }
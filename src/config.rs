@@ -0,0 +1,137 @@
+use near_sdk::{env, near, require};
+
+use crate::events::Event;
+use crate::*;
+
+#[near]
+impl Contract {
+    // Replaces the protocol parameters after checking each of them
+    // Executed through the timelock with execute_action
+    pub(crate) fn update_config(&mut self, config: Config) {
+        validate_config(&config);
+        self.validate_resolver_threshold(&config);
+
+        self.config = config.clone();
+
+        Event::ConfigUpdated {
+            account_id: &env::predecessor_account_id(),
+            config,
+        }
+        .emit();
+    }
+
+    // Returns the protocol parameters
+    pub fn get_config(&self) -> &Config {
+        &self.config
+    }
+}
+
+impl Contract {
+    // Panics if a new resolver threshold could never be reached by the current resolvers
+    pub(crate) fn validate_resolver_threshold(&self, config: &Config) {
+        if config.resolver_threshold == self.config.resolver_threshold {
+            return;
+        }
+
+        require!(
+            config.resolver_threshold as usize <= self.get_role_members(Role::Resolver).len(),
+            "The resolver threshold must be at most the number of resolvers"
+        );
+    }
+}
+
+// Panics if any of the protocol parameters are outside of their allowed range
+pub fn validate_config(config: &Config) {
+    require!(
+        config.rewards_period.0 > 0 && config.rewards_period.0 <= MAX_CONFIG_PERIOD,
+        "The rewards period must be more than zero and at most one year"
+    );
+
+    require!(
+        config.unstake_time_buffer.0 <= MAX_CONFIG_PERIOD,
+        "The unstake time buffer must be at most one year"
+    );
+
+    require!(
+        config.min_swap_amount.0 >= ONE_USDC,
+        "The minimum swap amount must be at least one USDC"
+    );
+
+    // The fees fund receives whatever is left so the shares cannot add up to more than the profit
+    require!(
+        u128::from(config.staking_share_bps)
+            + u128::from(config.treasury_share_bps)
+            + u128::from(config.insurance_share_bps)
+            <= BASIS_POINTS,
+        "The profit shares must add up to at most 100%"
+    );
+
+    require!(
+        config.market_margin_bps <= MAX_MARKET_MARGIN_BPS,
+        "The market margin must be at most 20%"
+    );
+
+    require!(
+        config.weight_factor > 0,
+        "The weight factor must be more than zero"
+    );
+
+    require!(
+        config.min_stake.0 > 0,
+        "The minimum stake must be more than zero"
+    );
+
+    require!(
+        config.stake_swap_reward_bps <= MAX_STAKE_SWAP_REWARD_BPS,
+        "The stake swap reward must be at most 10%"
+    );
+
+    require!(
+        config.timelock_delay.0 >= MIN_TIMELOCK_DELAY
+            && config.timelock_delay.0 <= MAX_CONFIG_PERIOD,
+        "The timelock delay must be at least one day and at most one year"
+    );
+
+    require!(
+        config.max_slippage_bps <= MAX_SLIPPAGE_LIMIT_BPS,
+        "The max slippage must be at most 10%"
+    );
+
+    require!(
+        config.twap_window.0 > 0 && config.twap_window.0 <= MAX_TWAP_WINDOW,
+        "The TWAP window must be more than zero and at most one day"
+    );
+
+    require!(
+        config.max_price_deviation_bps <= MAX_PRICE_DEVIATION_LIMIT_BPS,
+        "The max price deviation must be at most 50%"
+    );
+
+    require!(
+        config.unstake_cooldown.0 <= MAX_CONFIG_PERIOD,
+        "The unstake cooldown must be at most one year"
+    );
+
+    require!(
+        config.resolver_threshold > 0,
+        "The resolver threshold must be at least one"
+    );
+
+    require!(
+        config.challenge_period.0 >= MIN_CHALLENGE_PERIOD
+            && config.challenge_period.0 <= MAX_CONFIG_PERIOD,
+        "The challenge period must be at least one hour and at most one year"
+    );
+
+    // A stake swap must be able to swap a full tranche
+    require!(
+        config.max_stake_swap_amount.0 == 0
+            || config.max_stake_swap_amount.0 > config.min_swap_amount.0,
+        "The max stake swap amount must be zero or more than the minimum swap amount"
+    );
+
+    require!(
+        config.min_stake_swap_interval.0 <= MAX_CONFIG_PERIOD,
+        "The minimum stake swap interval must be at most one year"
+    );
+}
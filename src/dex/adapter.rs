@@ -0,0 +1,231 @@
+use near_sdk::json_types::U128;
+use near_sdk::{env, log, near, require, AccountId, Gas, Promise, PromiseError};
+
+use crate::dex::gas::{max_dex_gas, quote_callback_gas};
+use crate::dex::ref_finance;
+use crate::events::Event;
+use crate::*;
+
+// A swap through a single pool
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapHop {
+    pub pool_id: u64,
+    pub token_in: AccountId,
+    pub token_out: AccountId,
+}
+
+// Which quote is being calculated, decides the direction of the route and the callback it finishes with
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[near(serializers = [json])]
+pub enum DexQuote {
+    // The VEX needed to receive the USDC to cover a loss, quoted backwards from the output
+    LossDeposit,
+    // The USDC received for the VEX deposited to cover a loss
+    LossSwap,
+    // The VEX received for the USDC deposited in a stake swap
+    StakeSwap,
+    // The USDC received for a fixed amount of VEX when a keeper pings the price
+    PricePing,
+}
+
+// Calculates the minimum output of a swap from a quote, allowing for the max slippage in basis points
+pub fn min_amount_out(quote: U128, max_slippage_bps: u32) -> U128 {
+    let allowed_bps = BASIS_POINTS - u128::from(max_slippage_bps);
+    U128((U256::from(quote.0) * U256::from(allowed_bps) / U256::from(BASIS_POINTS)).as_u128())
+}
+
+#[near]
+impl Contract {
+    // Callback after quoting a pool that is not the last in the route, quotes the next pool
+    #[private]
+    pub fn dex_quote_callback(
+        &mut self,
+        #[callback_result] call_result: Result<U128, PromiseError>,
+        quote: DexQuote,
+        hop: u8,
+    ) {
+        let amount = match call_result {
+            Ok(amount) if amount.0 > 0 => amount,
+            _ => {
+                // Nothing has been swapped so the step can be resumed
+                match quote {
+                    DexQuote::LossDeposit | DexQuote::LossSwap => {
+                        self.loss_coverage_failed("Quote from the DEX failed")
+                    }
+                    DexQuote::StakeSwap => {
+                        self.stake_swap_quote_failed("Quote from the DEX failed")
+                    }
+                    DexQuote::PricePing => {
+                        log!("Quote from the DEX failed, no price was recorded")
+                    }
+                }
+                return;
+            }
+        };
+
+        self.dex_quote(quote, usize::from(hop), amount);
+    }
+}
+
+impl Contract {
+    // Replaces the exchange the swaps go through
+    // Executed through the timelock with execute_action
+    pub(crate) fn set_dex_adapter(&mut self, dex_adapter: DexAdapter) {
+        self.validate_dex_adapter(&dex_adapter);
+
+        // The tokens of a swap in progress are deposited in the current exchange
+        require!(
+            self.loss_coverage.is_none() && self.stake_swap.is_none(),
+            "The DEX cannot be changed while a swap is in progress"
+        );
+
+        self.dex_adapter = dex_adapter.clone();
+
+        Event::SetDexAdapter {
+            account_id: &env::predecessor_account_id(),
+            dex_adapter,
+        }
+        .emit();
+    }
+
+    // Panics if the route cannot swap between VEX and USDC
+    pub(crate) fn validate_dex_adapter(&self, dex_adapter: &DexAdapter) {
+        match dex_adapter {
+            DexAdapter::RefFinance { route, .. } => {
+                require!(
+                    !route.is_empty() && route.len() <= MAX_ROUTE_POOLS,
+                    "The route must have one or two pools"
+                );
+
+                let (last_pool, other_pools) = route.split_last().unwrap();
+
+                require!(
+                    last_pool.token_out == self.usdc_token_contract,
+                    "The route must end in USDC"
+                );
+
+                require!(
+                    other_pools
+                        .iter()
+                        .all(|pool| pool.token_out != self.usdc_token_contract
+                            && pool.token_out != self.vex_token_contract),
+                    "The route can only pass through VEX and USDC at its ends"
+                );
+
+                require!(
+                    max_dex_gas(route.len() as u64) <= MAX_PREPAID_GAS,
+                    "The route needs more gas than can be attached"
+                );
+            }
+        }
+    }
+
+    // The pools to swap through in order, from VEX to USDC or in reverse from USDC to VEX
+    pub(crate) fn swap_hops(&self, token_in: &AccountId) -> Vec<SwapHop> {
+        let route = match &self.dex_adapter {
+            DexAdapter::RefFinance { route, .. } => route,
+        };
+
+        let mut hops = Vec::new();
+        let mut hop_token_in = self.vex_token_contract.clone();
+        for pool in route {
+            hops.push(SwapHop {
+                pool_id: pool.pool_id.0,
+                token_in: hop_token_in,
+                token_out: pool.token_out.clone(),
+            });
+            hop_token_in = pool.token_out.clone();
+        }
+
+        if *token_in == self.usdc_token_contract {
+            hops.reverse();
+            for hop in hops.iter_mut() {
+                std::mem::swap(&mut hop.token_in, &mut hop.token_out);
+            }
+        }
+
+        hops
+    }
+
+    // Deposits tokens in the DEX so they can be swapped
+    pub(crate) fn dex_deposit(&self, token: AccountId, amount: U128) -> Promise {
+        match &self.dex_adapter {
+            DexAdapter::RefFinance { contract_id, .. } => {
+                ref_finance::deposit(contract_id.clone(), token, amount)
+            }
+        }
+    }
+
+    // Quotes a pool of the route then quotes the next one or finishes with the callback for the quote
+    // Quotes from the output start at the last pool of the route
+    pub(crate) fn dex_quote(&self, quote: DexQuote, hop: usize, amount: U128) -> Promise {
+        let by_output = quote == DexQuote::LossDeposit;
+
+        let mut hops = match quote {
+            DexQuote::LossDeposit | DexQuote::LossSwap | DexQuote::PricePing => {
+                self.swap_hops(&self.vex_token_contract)
+            }
+            DexQuote::StakeSwap => self.swap_hops(&self.usdc_token_contract),
+        };
+        if by_output {
+            hops.reverse();
+        }
+
+        let quote_pool = match &self.dex_adapter {
+            DexAdapter::RefFinance { contract_id, .. } => {
+                ref_finance::quote(contract_id.clone(), &hops[hop], amount, by_output)
+            }
+        };
+
+        // The callback needs gas to quote each pool left and for every call after the quote
+        let pools_left = (hops.len() - hop - 1) as u64;
+        let callback = Self::ext(env::current_account_id()).with_static_gas(Gas::from_tgas(
+            quote_callback_gas(quote, pools_left, hops.len() as u64),
+        ));
+
+        if pools_left > 0 {
+            return quote_pool.then(callback.dex_quote_callback(quote, (hop + 1) as u8));
+        }
+
+        match quote {
+            DexQuote::LossDeposit => quote_pool.then(callback.ref_loss_view_callback()),
+            DexQuote::LossSwap => quote_pool.then(callback.ref_loss_quote_callback()),
+            DexQuote::StakeSwap => quote_pool.then(callback.ref_profit_quote_callback()),
+            DexQuote::PricePing => quote_pool.then(callback.price_ping_callback()),
+        }
+    }
+
+    // Swaps deposited tokens through the route, the swap fails if the output is below the minimum
+    pub(crate) fn dex_swap(
+        &self,
+        token_in: &AccountId,
+        amount_in: U128,
+        min_amount_out: U128,
+    ) -> Promise {
+        let hops = self.swap_hops(token_in);
+
+        match &self.dex_adapter {
+            DexAdapter::RefFinance { contract_id, .. } => {
+                ref_finance::swap(contract_id.clone(), hops, amount_in, min_amount_out)
+            }
+        }
+    }
+
+    // Withdraws deposited tokens from the DEX back to the contract
+    pub(crate) fn dex_withdraw(&self, token: AccountId, amount: U128) -> Promise {
+        match &self.dex_adapter {
+            DexAdapter::RefFinance { contract_id, .. } => {
+                ref_finance::withdraw(contract_id.clone(), token, amount)
+            }
+        }
+    }
+
+    // Gets the contract's tokens deposited in the DEX
+    pub(crate) fn dex_get_deposits(&self) -> Promise {
+        match &self.dex_adapter {
+            DexAdapter::RefFinance { contract_id, .. } => {
+                ref_finance::get_deposits(contract_id.clone(), env::current_account_id())
+            }
+        }
+    }
+}
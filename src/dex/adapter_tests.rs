@@ -0,0 +1,130 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde_json::json;
+use near_sdk::{serde_json, AccountId, PromiseError};
+
+use crate::dex::adapter::{DexQuote, SwapHop};
+use crate::dex::ref_finance::create_swap_args;
+use crate::test_utils::*;
+use crate::*;
+
+fn wnear() -> AccountId {
+    "wnear.testnet".parse().unwrap()
+}
+
+// A route from VEX to wNEAR in pool 1 then from wNEAR to USDC in pool 2
+fn two_pool_adapter() -> DexAdapter {
+    DexAdapter::RefFinance {
+        contract_id: "ref.testnet".parse().unwrap(),
+        route: vec![
+            RoutePool {
+                pool_id: U64(1),
+                token_out: wnear(),
+            },
+            RoutePool {
+                pool_id: U64(2),
+                token_out: usdc(),
+            },
+        ],
+    }
+}
+
+fn hop(pool_id: u64, token_in: AccountId, token_out: AccountId) -> SwapHop {
+    SwapHop {
+        pool_id,
+        token_in,
+        token_out,
+    }
+}
+
+#[test]
+fn test_swap_hops() {
+    let mut contract = init_contract(100);
+
+    // The pool given at init is a route of one pool
+    assert_eq!(contract.swap_hops(&vex()), vec![hop(0, vex(), usdc())]);
+
+    new_call(0);
+    contract.set_dex_adapter(two_pool_adapter());
+
+    assert_eq!(
+        contract.swap_hops(&vex()),
+        vec![hop(1, vex(), wnear()), hop(2, wnear(), usdc())]
+    );
+
+    // Swaps from USDC go through the route in reverse
+    assert_eq!(
+        contract.swap_hops(&usdc()),
+        vec![hop(2, usdc(), wnear()), hop(1, wnear(), vex())]
+    );
+}
+
+#[test]
+fn test_create_swap_args() {
+    let actions = create_swap_args(
+        vec![hop(1, vex(), wnear()), hop(2, wnear(), usdc())],
+        U128(1_000),
+        U128(990),
+    );
+
+    // Only the first pool has an amount in and only the last has a minimum out
+    assert_eq!(
+        serde_json::to_value(actions).unwrap(),
+        json!([
+            {"pool_id": 1, "token_in": vex(), "token_out": wnear(), "amount_in": "1000", "min_amount_out": "0"},
+            {"pool_id": 2, "token_in": wnear(), "token_out": usdc(), "amount_in": null, "min_amount_out": "990"},
+        ])
+    );
+}
+
+#[test]
+#[should_panic(expected = "The route must end in USDC")]
+fn test_route_not_ending_in_usdc() {
+    let mut contract = init_contract(100);
+
+    new_call(0);
+    contract.set_dex_adapter(DexAdapter::RefFinance {
+        contract_id: "ref.testnet".parse().unwrap(),
+        route: vec![RoutePool {
+            pool_id: U64(1),
+            token_out: wnear(),
+        }],
+    });
+}
+
+#[test]
+#[should_panic(expected = "The DEX cannot be changed while a swap is in progress")]
+fn test_set_dex_adapter_during_swap() {
+    let mut contract = init_contract(100);
+    contract.insurance_fund = U128(ONE_USDC);
+
+    new_call(0);
+    let _ = contract.handle_loss(10 * ONE_USDC);
+
+    new_call(0);
+    contract.set_dex_adapter(two_pool_adapter());
+}
+
+#[test]
+fn test_route_quote_fails() {
+    let mut contract = init_contract(100);
+    contract.insurance_fund = U128(ONE_USDC);
+
+    new_call(0);
+    contract.set_dex_adapter(two_pool_adapter());
+
+    new_call(0);
+    let _ = contract.handle_loss(10 * ONE_USDC);
+    assert!(contract.loss_coverage.as_ref().unwrap().in_flight);
+
+    // The second pool of the route is quoted after the first
+    new_call(0);
+    contract.dex_quote_callback(Ok(U128(5 * ONE_USDC)), DexQuote::LossDeposit, 1);
+    assert!(contract.loss_coverage.as_ref().unwrap().in_flight);
+
+    // A failed quote leaves the deposit to be resumed
+    new_call(0);
+    contract.dex_quote_callback(Err(PromiseError::Failed), DexQuote::LossDeposit, 1);
+    let loss_coverage = contract.loss_coverage.as_ref().unwrap();
+    assert_eq!(loss_coverage.step, LossCoverageStep::Deposit);
+    assert!(!loss_coverage.in_flight);
+}
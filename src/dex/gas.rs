@@ -0,0 +1,155 @@
+use near_sdk::{env, require, Gas};
+
+use crate::dex::adapter::DexQuote;
+use crate::*;
+
+// A callback in the pipelines with the DEX, decides the static gas it is given
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DexCallback {
+    // ref_loss_view_callback, deposits the VEX quoted to cover a loss
+    LossView,
+    // ref_loss_deposit_callback, swaps the VEX deposited
+    LossDeposit,
+    // ref_loss_quote_callback, swaps the VEX deposited when a swap is resumed
+    LossQuote,
+    // ref_loss_swap_callback, withdraws the USDC swapped into
+    LossSwap,
+    // ref_loss_withdraw_callback, finishes covering the loss
+    LossWithdraw,
+    // ref_loss_coverage_deposits_callback, continues from the deposits in the DEX
+    LossDeposits,
+    // ref_profit_quote_callback, deposits or swaps the USDC rewards
+    ProfitQuote,
+    // ref_profit_deposit_callback, swaps the USDC deposited
+    ProfitDeposit,
+    // ref_profit_swap_callback, withdraws the VEX swapped into
+    ProfitSwap,
+    // ref_profit_withdraw_callback, rewards the caller and stakes the VEX
+    ProfitWithdraw,
+    // ref_stake_swap_deposits_callback, continues from the deposits in the DEX
+    StakeSwapDeposits,
+    // price_ping_callback, records the price
+    PricePing,
+}
+
+// The gas in TGas a callback needs for itself and every call still to come after it
+// on a route through a number of pools
+pub fn callback_gas(callback: DexCallback, pools: u64) -> u64 {
+    let calls_after = match callback {
+        DexCallback::LossView => DEX_CALL_GAS + callback_gas(DexCallback::LossDeposit, pools),
+        DexCallback::LossDeposit | DexCallback::LossQuote => {
+            DEX_CALL_GAS + callback_gas(DexCallback::LossSwap, pools)
+        }
+        DexCallback::LossSwap => DEX_CALL_GAS + callback_gas(DexCallback::LossWithdraw, pools),
+        DexCallback::LossWithdraw => 0,
+        // A swap is quoted again, a deposit is swapped or the USDC is withdrawn
+        DexCallback::LossDeposits => quote_gas(DexQuote::LossSwap, pools, pools)
+            .max(DEX_CALL_GAS + callback_gas(DexCallback::LossSwap, pools)),
+        // The USDC is deposited or, when a swap is resumed, swapped
+        DexCallback::ProfitQuote => DEX_CALL_GAS + callback_gas(DexCallback::ProfitDeposit, pools),
+        DexCallback::ProfitDeposit => DEX_CALL_GAS + callback_gas(DexCallback::ProfitSwap, pools),
+        DexCallback::ProfitSwap => DEX_CALL_GAS + callback_gas(DexCallback::ProfitWithdraw, pools),
+        // The caller's reward is transferred
+        DexCallback::ProfitWithdraw => DEX_CALL_GAS,
+        // A swap is quoted again, a deposit is swapped or the VEX is withdrawn
+        DexCallback::StakeSwapDeposits => quote_gas(DexQuote::StakeSwap, pools, pools)
+            .max(DEX_CALL_GAS + callback_gas(DexCallback::ProfitSwap, pools)),
+        DexCallback::PricePing => 0,
+    };
+
+    DEX_STEP_GAS + calls_after
+}
+
+// The gas in TGas to quote the pools left in a route one by one and run the callback for the quote
+pub fn quote_gas(quote: DexQuote, pools_left: u64, pools: u64) -> u64 {
+    DEX_QUOTE_GAS + quote_callback_gas(quote, pools_left - 1, pools)
+}
+
+// The static gas in TGas of the callback after quoting a pool, it quotes the pools left
+// after that pool or finishes with the callback for the quote
+pub fn quote_callback_gas(quote: DexQuote, pools_left: u64, pools: u64) -> u64 {
+    if pools_left > 0 {
+        return DEX_STEP_GAS + quote_gas(quote, pools_left, pools);
+    }
+
+    let last_callback = match quote {
+        DexQuote::LossDeposit => DexCallback::LossView,
+        DexQuote::LossSwap => DexCallback::LossQuote,
+        DexQuote::StakeSwap => DexCallback::ProfitQuote,
+        DexQuote::PricePing => DexCallback::PricePing,
+    };
+
+    callback_gas(last_callback, pools)
+}
+
+// The gas in TGas to start covering a loss
+pub fn loss_coverage_gas(pools: u64) -> u64 {
+    quote_gas(DexQuote::LossDeposit, pools, pools)
+}
+
+// The gas in TGas to resume covering a loss from any step
+pub fn resume_loss_coverage_gas(pools: u64) -> u64 {
+    (DEX_CALL_GAS + callback_gas(DexCallback::LossDeposits, pools))
+        .max(loss_coverage_gas(pools))
+        .max(quote_gas(DexQuote::LossSwap, pools, pools))
+}
+
+// The gas in TGas to start a stake swap
+pub fn stake_swap_gas(pools: u64) -> u64 {
+    quote_gas(DexQuote::StakeSwap, pools, pools)
+}
+
+// The gas in TGas to resume a stake swap from the deposits in the DEX
+pub fn resume_stake_swap_gas(pools: u64) -> u64 {
+    DEX_CALL_GAS + callback_gas(DexCallback::StakeSwapDeposits, pools)
+}
+
+// The gas in TGas to settle a profit or loss, a profit sends the treasury its share then starts a stake swap
+pub fn settle_gas(pools: u64) -> u64 {
+    loss_coverage_gas(pools).max(DEX_CALL_GAS + stake_swap_gas(pools))
+}
+
+// The gas in TGas to ping the price
+pub fn price_ping_gas(pools: u64) -> u64 {
+    quote_gas(DexQuote::PricePing, pools, pools)
+}
+
+// The gas in TGas the method that needs the most gas for the DEX needs on a route through a number of pools
+pub fn max_dex_gas(pools: u64) -> u64 {
+    DEX_STEP_GAS
+        + [
+            resume_loss_coverage_gas,
+            resume_stake_swap_gas,
+            settle_gas,
+            price_ping_gas,
+        ]
+        .iter()
+        .map(|pipeline_gas| pipeline_gas(pools))
+        .max()
+        .unwrap()
+}
+
+impl Contract {
+    // The number of pools in the route the swaps go through
+    pub(crate) fn route_pools(&self) -> u64 {
+        match &self.dex_adapter {
+            DexAdapter::RefFinance { route, .. } => route.len() as u64,
+        }
+    }
+
+    // The static gas of a callback in a pipeline with the DEX on the current route
+    pub(crate) fn dex_callback_gas(&self, callback: DexCallback) -> Gas {
+        Gas::from_tgas(callback_gas(callback, self.route_pools()))
+    }
+
+    // Panics if less gas is attached than the method needs for itself and a pipeline with the DEX
+    // on the current route
+    pub(crate) fn require_dex_gas(&self, pipeline_gas: fn(u64) -> u64) {
+        let required = DEX_STEP_GAS + pipeline_gas(self.route_pools());
+
+        require!(
+            env::prepaid_gas() >= Gas::from_tgas(required),
+            format!("You need to attach {} TGas", required)
+        );
+    }
+}
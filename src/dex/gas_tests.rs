@@ -0,0 +1,77 @@
+use near_sdk::json_types::U64;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, Gas};
+
+use crate::dex::adapter::DexQuote;
+use crate::dex::gas::*;
+use crate::test_utils::*;
+use crate::*;
+
+// Sets up a contract that swaps from VEX to wNEAR in pool 1 then from wNEAR to USDC in pool 2
+fn setup_two_pools() -> Contract {
+    let mut contract = init_contract(100);
+
+    new_call(0);
+    contract.set_dex_adapter(DexAdapter::RefFinance {
+        contract_id: "ref.testnet".parse().unwrap(),
+        route: vec![
+            RoutePool {
+                pool_id: U64(1),
+                token_out: "wnear.testnet".parse().unwrap(),
+            },
+            RoutePool {
+                pool_id: U64(2),
+                token_out: usdc(),
+            },
+        ],
+    });
+
+    contract
+}
+
+#[test]
+fn test_gas_grows_with_route() {
+    // The callback after the first pool of two quotes the second pool then runs the callback for the quote
+    assert_eq!(
+        quote_callback_gas(DexQuote::StakeSwap, 1, 2),
+        DEX_STEP_GAS + DEX_QUOTE_GAS + callback_gas(DexCallback::ProfitQuote, 2)
+    );
+
+    // A stake swap needs enough gas to deposit, swap, withdraw and reward the caller after the quote
+    assert_eq!(
+        callback_gas(DexCallback::ProfitQuote, 1),
+        4 * DEX_STEP_GAS + 4 * DEX_CALL_GAS
+    );
+
+    // A second pool needs gas for its quote and the callback that quotes it
+    for pipeline_gas in [
+        loss_coverage_gas,
+        resume_loss_coverage_gas,
+        stake_swap_gas,
+        resume_stake_swap_gas,
+        settle_gas,
+        price_ping_gas,
+    ] {
+        assert_eq!(
+            pipeline_gas(2),
+            pipeline_gas(1) + DEX_QUOTE_GAS + DEX_STEP_GAS
+        );
+    }
+
+    // Every route that can be set fits in the gas that can be attached
+    assert!(max_dex_gas(MAX_ROUTE_POOLS as u64) <= MAX_PREPAID_GAS);
+}
+
+#[test]
+#[should_panic(expected = "You need to attach 65 TGas")]
+fn test_not_enough_gas_for_route() {
+    let mut contract = setup_two_pools();
+
+    // The gas to ping the price through one pool is not enough for two
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(admin())
+        .block_timestamp(100)
+        .prepaid_gas(Gas::from_tgas(DEX_STEP_GAS + price_ping_gas(1)))
+        .build());
+    let _ = contract.ping_price();
+}
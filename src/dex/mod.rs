@@ -0,0 +1,8 @@
+pub mod adapter;
+pub mod gas;
+pub mod ref_finance;
+
+#[cfg(test)]
+mod adapter_tests;
+#[cfg(test)]
+mod gas_tests;
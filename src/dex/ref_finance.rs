@@ -0,0 +1,96 @@
+use near_sdk::json_types::U128;
+use near_sdk::{near, AccountId, Gas, NearToken, Promise};
+
+use crate::dex::adapter::SwapHop;
+use crate::ext::{ft_contract, ref_contract};
+use crate::{DEX_CALL_GAS, DEX_QUOTE_GAS};
+
+#[near(serializers = [json])]
+pub struct Action {
+    pool_id: u64,
+    token_in: AccountId,
+    token_out: AccountId,
+    amount_in: Option<U128>,
+    min_amount_out: U128,
+}
+
+// Creates an action for each pool, after the first pool each action swaps what the previous one returned
+// and the minimum output is only checked on the last pool
+pub fn create_swap_args(hops: Vec<SwapHop>, amount_in: U128, min_amount_out: U128) -> Vec<Action> {
+    let last_hop = hops.len() - 1;
+
+    hops.into_iter()
+        .enumerate()
+        .map(|(index, hop)| Action {
+            pool_id: hop.pool_id,
+            token_in: hop.token_in,
+            token_out: hop.token_out,
+            amount_in: if index == 0 { Some(amount_in) } else { None },
+            min_amount_out: if index == last_hop {
+                min_amount_out
+            } else {
+                U128(0)
+            },
+        })
+        .collect()
+}
+
+// Transfers tokens to Ref Finance so they are deposited for the contract
+pub fn deposit(contract_id: AccountId, token: AccountId, amount: U128) -> Promise {
+    ft_contract::ext(token)
+        .with_attached_deposit(NearToken::from_yoctonear(1))
+        .with_static_gas(Gas::from_tgas(DEX_CALL_GAS))
+        .ft_transfer_call(contract_id, amount, "".to_string())
+}
+
+// Gets the amount out of a pool for an amount in, or the amount in needed for an amount out
+pub fn quote(contract_id: AccountId, hop: &SwapHop, amount: U128, by_output: bool) -> Promise {
+    if by_output {
+        ref_contract::ext(contract_id)
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .with_static_gas(Gas::from_tgas(DEX_QUOTE_GAS))
+            .get_return_by_output(
+                hop.pool_id,
+                hop.token_in.clone(),
+                amount,
+                hop.token_out.clone(),
+            )
+    } else {
+        ref_contract::ext(contract_id)
+            .with_static_gas(Gas::from_tgas(DEX_QUOTE_GAS))
+            .get_return(
+                hop.pool_id,
+                hop.token_in.clone(),
+                amount,
+                hop.token_out.clone(),
+            )
+    }
+}
+
+// Swaps deposited tokens through the pools, Ref Finance panics if the output is below the minimum
+pub fn swap(
+    contract_id: AccountId,
+    hops: Vec<SwapHop>,
+    amount_in: U128,
+    min_amount_out: U128,
+) -> Promise {
+    ref_contract::ext(contract_id)
+        .with_attached_deposit(NearToken::from_yoctonear(1))
+        .with_static_gas(Gas::from_tgas(DEX_CALL_GAS))
+        .swap(create_swap_args(hops, amount_in, min_amount_out))
+}
+
+// Withdraws deposited tokens back to the contract
+pub fn withdraw(contract_id: AccountId, token: AccountId, amount: U128) -> Promise {
+    ref_contract::ext(contract_id)
+        .with_attached_deposit(NearToken::from_yoctonear(1))
+        .with_static_gas(Gas::from_tgas(DEX_CALL_GAS))
+        .withdraw(token, amount)
+}
+
+// Gets the tokens deposited by an account
+pub fn get_deposits(contract_id: AccountId, account_id: AccountId) -> Promise {
+    ref_contract::ext(contract_id)
+        .with_static_gas(Gas::from_tgas(DEX_CALL_GAS))
+        .get_deposits(account_id)
+}
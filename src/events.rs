@@ -21,6 +21,9 @@ pub enum Event<'a> {
         team_2: String,
         team_1_initial_pool: U128,
         team_2_initial_pool: U128,
+        draw_initial_pool: Option<U128>,
+        start_time: U64,
+        external_ref: Option<String>,
     },
     EndBetting {
         match_id: MatchId,
@@ -32,6 +35,106 @@ pub enum Event<'a> {
         match_id: MatchId,
         winner: Team,
     },
+    FinalScore {
+        match_id: MatchId,
+        team_1_score: u32,
+        team_2_score: u32,
+    },
+    ProposeResult {
+        account_id: &'a AccountId,
+        match_id: MatchId,
+        winner: Team,
+        score: Option<(u32, u32)>,
+        finalise_after: U64,
+    },
+    DisputeResult {
+        account_id: &'a AccountId,
+        match_id: MatchId,
+        proposed_winner: Team,
+    },
+    ConfirmResult {
+        account_id: &'a AccountId,
+        match_id: MatchId,
+        confirmations: u32,
+    },
+    SubmitResult {
+        account_id: &'a AccountId,
+        match_id: MatchId,
+        winner: Team,
+        votes: u32,
+    },
+    ConflictingResults {
+        match_id: MatchId,
+    },
+    ProposeAdmin {
+        new_admin: &'a AccountId,
+        expires_at: Option<U64>,
+    },
+    AcceptAdmin {
+        old_admin: &'a AccountId,
+        new_admin: &'a AccountId,
+    },
+    CancelAdminProposal {
+        new_admin: &'a AccountId,
+    },
+    GrantRole {
+        account_id: &'a AccountId,
+        role: Role,
+    },
+    RevokeRole {
+        account_id: &'a AccountId,
+        role: Role,
+    },
+    ScheduleAction {
+        account_id: &'a AccountId,
+        action_id: ActionId,
+        action: TimelockAction,
+        eta: U64,
+    },
+    ExecuteAction {
+        account_id: &'a AccountId,
+        action_id: ActionId,
+    },
+    CancelAction {
+        account_id: &'a AccountId,
+        action_id: ActionId,
+    },
+    ConfigUpdated {
+        account_id: &'a AccountId,
+        config: Config,
+    },
+    SetDexAdapter {
+        account_id: &'a AccountId,
+        dex_adapter: DexAdapter,
+    },
+    StageCode {
+        account_id: &'a AccountId,
+        code_hash: Base58CryptoHash,
+    },
+    Upgrade {
+        code_hash: Base58CryptoHash,
+    },
+    Migrate {
+        old_version: u32,
+        new_version: u32,
+    },
+    ResumeLossCoverage {
+        account_id: &'a AccountId,
+        step: LossCoverageStep,
+    },
+    ResumeStakeSwap {
+        account_id: &'a AccountId,
+        step: StakeSwapStep,
+    },
+    SetPauseState {
+        account_id: &'a AccountId,
+        pause_state: PauseState,
+    },
+    OverrideResult {
+        match_id: MatchId,
+        winner: Team,
+        proposed_winner: Option<Team>,
+    },
     Bet {
         account_id: &'a AccountId,
         bet_id: BetId,
@@ -41,6 +144,72 @@ pub enum Event<'a> {
         potential_winnings: U128,
         new_team_1_pool_size: U128,
         new_team_2_pool_size: U128,
+        new_draw_pool_size: Option<U128>,
+    },
+    BetRejected {
+        account_id: &'a AccountId,
+        amount: U128,
+        match_id: MatchId,
+        reason: RejectionReason,
+    },
+    NewMarket {
+        market_id: MarketId,
+        game: String,
+        name: String,
+        date: String,
+        outcome_labels: Vec<String>,
+        initial_pools: Vec<U128>,
+        start_time: U64,
+    },
+    NewSubMarket {
+        market_id: MarketId,
+        match_id: MatchId,
+        kind: LineKind,
+        line: i64,
+        outcome_labels: Vec<String>,
+        initial_pools: Vec<U128>,
+    },
+    EndMarketBetting {
+        market_id: MarketId,
+    },
+    CancelMarket {
+        market_id: MarketId,
+    },
+    FinishMarket {
+        market_id: MarketId,
+        winner: u32,
+    },
+    MarketBet {
+        account_id: &'a AccountId,
+        bet_id: BetId,
+        amount: U128,
+        market_id: MarketId,
+        outcome: u32,
+        potential_winnings: U128,
+        new_pool_size: U128,
+    },
+    MarketBetRejected {
+        account_id: &'a AccountId,
+        amount: U128,
+        market_id: MarketId,
+        reason: RejectionReason,
+    },
+    Parlay {
+        account_id: &'a AccountId,
+        bet_id: BetId,
+        amount: U128,
+        legs: Vec<ParlayLeg>,
+        potential_winnings: U128,
+    },
+    ParlayRejected {
+        account_id: &'a AccountId,
+        amount: U128,
+        reason: RejectionReason,
+    },
+    SettleParlay {
+        account_id: &'a AccountId,
+        bet_id: BetId,
+        result: ParlayResult,
     },
     ClaimWinnings {
         account_id: &'a AccountId,
@@ -52,16 +221,37 @@ pub enum Event<'a> {
         bet_id: BetId,
         amount_received: U128,
     },
+    CashOut {
+        account_id: &'a AccountId,
+        bet_id: BetId,
+        amount_received: U128,
+    },
     StakeVex {
         account_id: &'a AccountId,
         amount: U128,
         new_total_staked: U128,
     },
+    StakeRejected {
+        account_id: &'a AccountId,
+        amount: U128,
+        reason: RejectionReason,
+    },
     UnstakeVex {
         account_id: &'a AccountId,
         amount: U128,
         new_total_staked: U128,
     },
+    WithdrawUnstaked {
+        account_id: &'a AccountId,
+        ticket_id: TicketId,
+        amount: U128,
+    },
+    TransferRejected {
+        account_id: &'a AccountId,
+        token_id: &'a AccountId,
+        amount: U128,
+        reason: RejectionReason,
+    },
 }
 
 impl Event<'_> {
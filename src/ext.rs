@@ -1,32 +1,8 @@
 use near_sdk::json_types::U128;
-use near_sdk::{ext_contract, near, AccountId, PromiseOrValue};
-
-#[near(serializers = [json])]
-pub struct Action {
-    pool_id: u64,
-    token_in: AccountId,
-    token_out: AccountId,
-    amount_in: U128,
-    min_amount_out: U128,
-}
+use near_sdk::{ext_contract, AccountId, PromiseOrValue};
+use std::collections::HashMap;
 
-pub fn create_swap_args(
-    pool_id: u64,
-    token_in: AccountId,
-    token_out: AccountId,
-    amount_in: U128,
-    min_amount_out: U128,
-) -> Vec<Action> {
-    let action = Action {
-        pool_id,
-        token_in,
-        token_out,
-        amount_in: amount_in,
-        min_amount_out: min_amount_out,
-    };
-
-    vec![action]
-}
+use crate::dex::ref_finance::Action;
 
 // FT transfer interface
 #[allow(dead_code)]
@@ -49,6 +25,14 @@ trait Ref {
 
     fn withdraw(&mut self, token_id: AccountId, amount: U128) -> U128;
 
+    fn get_return(
+        &self,
+        pool_id: u64,
+        token_in: AccountId,
+        amount_in: U128,
+        token_out: AccountId,
+    ) -> U128;
+
     fn get_return_by_output(
         &self,
         pool_id: u64,
@@ -56,4 +40,6 @@ trait Ref {
         amount_out: U128,
         token_out: AccountId,
     ) -> U128;
+
+    fn get_deposits(&self, account_id: AccountId) -> HashMap<AccountId, U128>;
 }
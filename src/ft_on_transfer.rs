@@ -1,6 +1,8 @@
-use near_sdk::json_types::U128;
-use near_sdk::{near, serde_json};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, log, near, serde_json};
 
+use crate::betting::parlay::ParlayLegInfo;
+use crate::events::Event;
 pub use crate::ext::*;
 use crate::*;
 
@@ -8,6 +10,30 @@ use crate::*;
 pub struct BetInfo {
     match_id: MatchId,
     team: Team,
+    // The bet is refunded if the potential winnings are less than this
+    min_potential_winnings: Option<U128>,
+    // The bet is refunded if it is placed after this timestamp in nanoseconds
+    deadline: Option<U64>,
+}
+
+#[near(serializers = [json])]
+pub struct MarketBetInfo {
+    market_id: MarketId,
+    // The index of the outcome being bet on
+    outcome: u32,
+    // The bet is refunded if the potential winnings are less than this
+    min_potential_winnings: Option<U128>,
+    // The bet is refunded if it is placed after this timestamp in nanoseconds
+    deadline: Option<U64>,
+}
+
+#[near(serializers = [json])]
+pub struct ParlayInfo {
+    legs: Vec<ParlayLegInfo>,
+    // The parlay is refunded if the potential winnings are less than this
+    min_potential_winnings: Option<U128>,
+    // The parlay is refunded if it is placed after this timestamp in nanoseconds
+    deadline: Option<U64>,
 }
 
 #[near(serializers = [json])]
@@ -15,29 +41,56 @@ pub enum FtTransferAction {
     Stake,
     AddUSDC,
     Bet(BetInfo),
+    MarketBet(MarketBetInfo),
+    Parlay(ParlayInfo),
 }
 
 #[near]
 impl Contract {
     pub fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> U128 {
         // Send to relevant function based on msg
+        // returns the amount of tokens to refund to the sender
         match serde_json::from_str(&msg) {
-            Ok(FtTransferAction::Stake) => {
-                self.stake(sender_id, amount);
-            }
-            Ok(FtTransferAction::AddUSDC) => {
-                self.add_usdc(amount);
-            }
-            Ok(FtTransferAction::Bet(bet_info)) => {
-                self.bet(sender_id, amount, bet_info.match_id, bet_info.team);
-            }
+            Ok(FtTransferAction::Stake) => self.stake(sender_id, amount),
+            Ok(FtTransferAction::AddUSDC) => self.add_usdc(sender_id, amount),
+            Ok(FtTransferAction::Bet(bet_info)) => self.bet(
+                sender_id,
+                amount,
+                bet_info.match_id,
+                bet_info.team,
+                bet_info.min_potential_winnings,
+                bet_info.deadline,
+            ),
+            Ok(FtTransferAction::Parlay(parlay_info)) => self.bet_parlay(
+                sender_id,
+                amount,
+                parlay_info.legs,
+                parlay_info.min_potential_winnings,
+                parlay_info.deadline,
+            ),
+            Ok(FtTransferAction::MarketBet(bet_info)) => self.bet_on_market(
+                sender_id,
+                amount,
+                bet_info.market_id,
+                bet_info.outcome,
+                bet_info.min_potential_winnings,
+                bet_info.deadline,
+            ),
 
-            // add option to fill up the difference
+            // Refund the tokens if the msg is invalid
             Err(err) => {
-                panic!("Invalid call {}", err);
+                log!("Invalid call {}", err);
+
+                Event::TransferRejected {
+                    account_id: &sender_id,
+                    token_id: &env::predecessor_account_id(),
+                    amount,
+                    reason: RejectionReason::InvalidMessage,
+                }
+                .emit();
+
+                amount
             }
         }
-
-        U128(0)
     }
 }
@@ -1,15 +1,30 @@
-use near_sdk::json_types::{U128, U64};
+use config::validate_config;
+use migration::state_v0::MatchV0;
+use migration::upgrade::write_state_version;
+use near_sdk::json_types::{Base58CryptoHash, U128, U64};
 use near_sdk::store::{IterableMap, LookupMap};
-use near_sdk::{near, AccountId, BorshStorageKey, PanicOnDefault};
+use near_sdk::{env, near, AccountId, BorshStorageKey, PanicOnDefault};
 use std::collections::VecDeque;
 use uint::construct_uint;
 
 pub mod admin;
 pub mod betting;
+pub mod config;
+pub mod dex;
 pub mod events;
 pub mod ext;
 pub mod ft_on_transfer;
+pub mod markets;
+pub mod migration;
+pub mod pause;
+pub mod resolution;
+pub mod roles;
 pub mod staking;
+pub mod timelock;
+pub mod twap;
+
+#[cfg(test)]
+mod test_utils;
 
 #[near(contract_state)]
 #[derive(PanicOnDefault)]
@@ -17,6 +32,12 @@ pub struct Contract {
     // The account ID of this contract's admin
     pub admin: AccountId,
 
+    // The account proposed to become the admin, it must accept before it becomes the admin
+    pub pending_admin: Option<AdminProposal>,
+
+    // Map of the roles granted to each account by the admin
+    pub roles_by_account: IterableMap<AccountId, Vec<Role>>,
+
     // The USDC contract account ID
     pub usdc_token_contract: AccountId,
 
@@ -26,24 +47,43 @@ pub struct Contract {
     // The treasury account ID
     pub treasury: AccountId,
 
-    // The account ID of the Ref Finance contract
-    pub ref_contract: AccountId,
-
-    // The Ref Finance pool ID between USDC and VEX
-    pub ref_pool_id: u64,
+    // The exchange and route the VEX/USDC swaps go through, changed by the admin with the timelock
+    pub dex_adapter: DexAdapter,
 
     // Map of all matches
     pub matches: IterableMap<MatchId, Match>,
 
-    // Map of all bets ordered by user
-    pub bets_by_user: LookupMap<AccountId, IterableMap<BetId, Bet>>,
+    // Matches created before the state was versioned, they are moved to matches by migrate_matches
+    pub legacy_matches: IterableMap<MatchId, MatchV0>,
+
+    // Map of match IDs by the ID of the match at the external data provider
+    pub match_ids_by_external_ref: LookupMap<String, MatchId>,
 
-    // The bet ID of the previous bet
+    // Map of all bets and parlays ordered by user
+    pub bets_by_user: LookupMap<AccountId, IterableMap<BetId, UserBet>>,
+
+    // Bets made before the state was versioned, they are moved to bets_by_user by migrate_bets
+    // or the first time their bettor claims or cashes out
+    pub legacy_bets_by_user: LookupMap<AccountId, IterableMap<BetId, Bet>>,
+
+    // Map of all multi-outcome markets
+    pub markets: IterableMap<MarketId, Market>,
+
+    // Map of all bets on markets ordered by user
+    pub market_bets_by_user: LookupMap<AccountId, IterableMap<BetId, MarketBet>>,
+
+    // The bet ID of the previous bet, shared by bets on matches and markets
     pub last_bet_id: BetId,
 
     // A map of balances related to staking for each user
     pub users_stake: LookupMap<AccountId, UserStake>,
 
+    // A map of the withdrawal tickets of VEX that has been unstaked but is still cooling down for each user
+    pub withdrawal_tickets: LookupMap<AccountId, Vec<WithdrawalTicket>>,
+
+    // The ID of the last withdrawal ticket
+    pub last_ticket_id: TicketId,
+
     // A FIFO queue of matches that still have staking rewards to be distributed
     pub staking_rewards_queue: VecDeque<MatchStakeInfo>,
 
@@ -59,6 +99,12 @@ pub struct Contract {
     // The total number of VEX stake shares
     pub total_stake_shares: U128,
 
+    // The total VEX in withdrawal tickets, it bears losses but does not earn staking rewards
+    pub total_unstaking_balance: U128,
+
+    // The total number of shares of the VEX in withdrawal tickets
+    pub total_unstaking_shares: U128,
+
     // The total amount of USDC in the fees fund
     pub fees_fund: U128,
 
@@ -68,17 +114,36 @@ pub struct Contract {
     // The total amount of USDC that needs to be paid out
     pub funds_to_payout: U128,
 
+    // The total potential winnings of parlays that have not been settled
+    pub parlay_exposure: U128,
+
     // The amount of USDC that needs to be added to be added to the contract because an error occurred
     pub funds_to_add: U128,
 
-    // The time that rewards for staking are distributed over in nanoseconds, default is one month - 2_628_000_000_000_000
-    pub rewards_period: u64,
+    // The protocol parameters that can be changed by the admin or a config manager
+    pub config: Config,
 
-    // The buffer time in nanoseconds before a user unstake since last staking, default is one week - 604_800_000_000_000
-    pub unstake_time_buffer: u64,
+    // Sensitive admin actions waiting for the timelock delay to pass before they can be executed
+    pub pending_actions: IterableMap<ActionId, PendingAction>,
 
-    // The minimum amount of rewards required to be able to swap, default is 100 USDC - 100_000_000
-    pub min_swap_amount: u128,
+    // The ID of the last action scheduled with the timelock
+    pub last_action_id: ActionId,
+
+    // Which parts of the contract are paused, everything is unpaused by default
+    pub pause_state: PauseState,
+
+    // The swap of staked VEX for USDC on the DEX covering a loss the insurance fund could not
+    pub loss_coverage: Option<LossCoverage>,
+
+    // The swap of USDC staking rewards for VEX on the DEX, only one can be in progress at a time
+    pub stake_swap: Option<StakeSwap>,
+
+    // The USDC staking rewards already distributed that were over the max stake swap amount
+    // and are carried over to the next stake swap
+    pub pending_stake_swap_usdc: U128,
+
+    // A ring buffer of the latest VEX prices seen on the DEX, used to calculate the TWAP
+    pub price_observations: VecDeque<PriceObservation>,
 }
 
 #[near(serializers = [borsh])]
@@ -92,31 +157,133 @@ pub struct Match {
     // Team 2's name
     pub team_2: String,
 
-    // The total bets made on team 1 in USDC
-    pub team_1_total_bets: U128,
-
-    // The total bets made on team 2 in USDC
-    pub team_2_total_bets: U128,
-
-    // The initial pool of USDC for team 1
-    pub team_1_initial_pool: U128,
+    // The pools for each outcome in the order Team1, Team2, Draw
+    // matches where a draw is not possible only have two outcomes
+    pub outcomes: Vec<OutcomePool>,
 
-    // The initial pool of USDC for team 2
-    pub team_2_initial_pool: U128,
+    // The IDs of the over/under and handicap markets attached to the match
+    pub sub_markets: Vec<MarketId>,
 
-    // USDC to be paid out if team 1 wins
-    pub team_1_potential_winnings: U128,
-
-    // USDC to be paid out if team 2 wins
-    pub team_2_potential_winnings: U128,
+    // The timestamp in nanoseconds of when the match starts, bets are rejected after this
+    pub start_time: U64,
 
     // Whether the match is in the future, current, finished, or had an error
     pub match_state: MatchState,
 
     // The winning team
     pub winner: Option<Team>,
+
+    // The result proposed by a resolver while it can be disputed
+    pub proposed_result: Option<ProposedResult>,
+
+    // The results submitted by resolvers voting on the winner
+    pub result_votes: Vec<ResultVote>,
+
+    // The resolvers that have disputed a proposed result, each can only dispute once
+    pub disputed_by: Vec<AccountId>,
+
+    // The ID of the match at the external data provider
+    pub external_ref: Option<String>,
 }
 
+#[near(serializers = [borsh])]
+pub struct OutcomePool {
+    // The total bets made on the outcome in USDC, this includes the initial pool
+    pub total_bets: U128,
+
+    // The initial pool of USDC for the outcome
+    pub initial_pool: U128,
+
+    // USDC to be paid out if the outcome wins
+    pub potential_winnings: U128,
+}
+
+impl Match {
+    // Returns the pool for an outcome or None if the outcome is not possible in this match
+    pub fn outcome(&self, team: &Team) -> Option<&OutcomePool> {
+        self.outcomes.get(team.index())
+    }
+
+    // Returns the total bets made on each outcome
+    pub fn total_bets(&self) -> Vec<U128> {
+        self.outcomes
+            .iter()
+            .map(|outcome| outcome.total_bets)
+            .collect()
+    }
+
+    // Returns the state of the match taking its start time into account
+    pub fn effective_state(&self) -> MatchState {
+        effective_state(&self.match_state, self.start_time)
+    }
+}
+
+#[near(serializers = [borsh])]
+pub struct Market {
+    // What game the market is for
+    pub game: String,
+
+    // What is being bet on, e.g. the winner of a tournament
+    pub name: String,
+
+    // The name of each outcome, in the same order as outcomes
+    pub outcome_labels: Vec<String>,
+
+    // The pools for each outcome
+    pub outcomes: Vec<OutcomePool>,
+
+    // Whether the market is in the future, current, finished, or had an error
+    pub market_state: MatchState,
+
+    // The index of the winning outcome
+    pub winner: Option<u32>,
+
+    // The line if the market is an over/under or handicap market attached to a match
+    pub line: Option<MarketLine>,
+
+    // The timestamp in nanoseconds of when the market's event starts, bets are rejected after this
+    pub start_time: U64,
+}
+
+#[near(serializers = [json, borsh])]
+#[derive(Clone)]
+pub struct MarketLine {
+    // The match the market is attached to
+    pub match_id: MatchId,
+
+    // Whether the market is an over/under or handicap market
+    pub kind: LineKind,
+
+    // The line scaled by LINE_SCALE, e.g. 25 is 2.5
+    // for handicap markets the line is added to team 1's score
+    pub value: i64,
+}
+
+impl Market {
+    // Returns the total bets made on each outcome
+    pub fn total_bets(&self) -> Vec<U128> {
+        self.outcomes
+            .iter()
+            .map(|outcome| outcome.total_bets)
+            .collect()
+    }
+
+    // Returns the state of the market taking its start time into account
+    pub fn effective_state(&self) -> MatchState {
+        effective_state(&self.market_state, self.start_time)
+    }
+}
+
+// A match or market in the Future state is Current once its start time has passed
+// even if betting has not been ended by the admin
+pub fn effective_state(state: &MatchState, start_time: U64) -> MatchState {
+    match state {
+        MatchState::Future if env::block_timestamp() >= start_time.0 => MatchState::Current,
+        _ => state.clone(),
+    }
+}
+
+#[derive(Clone)]
 #[near(serializers = [json, borsh])]
 pub struct Bet {
     // The match that is being bet on
@@ -135,6 +302,85 @@ pub struct Bet {
     pub pay_state: Option<PayState>,
 }
 
+// A bet or parlay made by a user
+#[derive(Clone)]
+#[near(serializers = [json, borsh])]
+pub enum UserBet {
+    Single(Bet),
+    Parlay(Parlay),
+}
+
+impl UserBet {
+    // Returns the pay state of the bet or parlay so it can be updated
+    pub fn pay_state_mut(&mut self) -> &mut Option<PayState> {
+        match self {
+            UserBet::Single(bet) => &mut bet.pay_state,
+            UserBet::Parlay(parlay) => &mut parlay.pay_state,
+        }
+    }
+}
+
+#[derive(Clone)]
+#[near(serializers = [json, borsh])]
+pub struct Parlay {
+    // The matches and teams that are being bet on
+    pub legs: Vec<ParlayLeg>,
+
+    // The amount of USDC being bet
+    pub bet_amount: U128,
+
+    // The winnings in USDC if every leg is successful
+    pub potential_winnings: U128,
+
+    // The result of the parlay once it has been settled
+    pub result: Option<ParlayResult>,
+
+    // Whether the parlay has been paid out
+    pub pay_state: Option<PayState>,
+}
+
+#[derive(Clone, Debug)]
+#[near(serializers = [json, borsh])]
+pub struct ParlayLeg {
+    // The match that is being bet on
+    pub match_id: MatchId,
+
+    // The team that is being bet on
+    pub team: Team,
+
+    // The decimal odds of the team when the parlay was made, scaled by FIXED_POINT_ONE
+    pub odds: U128,
+}
+
+#[derive(Clone, Debug)]
+#[near(serializers = [json, borsh])]
+pub enum ParlayResult {
+    // Every leg that was not void won, holds the winnings
+    Won(U128),
+    // At least one leg lost
+    Lost,
+    // Every leg was void so the bet is refunded
+    Void,
+}
+
+#[near(serializers = [json, borsh])]
+pub struct MarketBet {
+    // The market that is being bet on
+    pub market_id: MarketId,
+
+    // The index of the outcome that is being bet on
+    pub outcome: u32,
+
+    // The amount of USDC being bet
+    pub bet_amount: U128,
+
+    // The winnings in USDC if the bet is successful
+    pub potential_winnings: U128,
+
+    // Whether the bet has been paid out
+    pub pay_state: Option<PayState>,
+}
+
 #[near(serializers = [json, borsh])]
 pub struct UserStake {
     // The number of stake shares the user has
@@ -144,6 +390,20 @@ pub struct UserStake {
     pub unstake_timestamp: U64,
 }
 
+// VEX that has been unstaked and can be withdrawn once the cooldown has passed
+#[derive(Clone)]
+#[near(serializers = [json, borsh])]
+pub struct WithdrawalTicket {
+    // The ID of the ticket
+    pub ticket_id: TicketId,
+
+    // The number of shares of the VEX in withdrawal tickets the ticket has
+    pub unstaking_shares: U128,
+
+    // The timestamp of when the VEX can be withdrawn
+    pub withdrawable_timestamp: U64,
+}
+
 impl Default for UserStake {
     fn default() -> Self {
         Self {
@@ -153,6 +413,20 @@ impl Default for UserStake {
     }
 }
 
+// A price of VEX seen on the DEX from a swap or a keeper's ping
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers = [json, borsh])]
+pub struct PriceObservation {
+    // The timestamp in nanoseconds of the observation
+    pub timestamp: U64,
+
+    // The price of one VEX in USDC as a fixed-point number, FIXED_POINT_ONE is one USDC
+    pub price: U128,
+
+    // The sum of each previous price multiplied by the nanoseconds it lasted, wraps on overflow
+    pub price_cumulative: U128,
+}
+
 #[near(serializers = [json, borsh])]
 pub struct MatchStakeInfo {
     // The USDC profit from the match that is to be distributed
@@ -167,12 +441,349 @@ pub struct MatchStakeInfo {
 pub enum Team {
     Team1,
     Team2,
+    Draw,
+}
+
+impl Team {
+    // The index of the team's pool in Match outcomes
+    pub fn index(&self) -> usize {
+        match self {
+            Team::Team1 => 0,
+            Team::Team2 => 1,
+            Team::Draw => 2,
+        }
+    }
 }
 
+#[derive(Clone)]
 #[near(serializers = [json, borsh])]
 pub enum PayState {
     Paid,
     RefundPaid,
+    CashedOut,
+}
+
+// The type of market attached to a match
+// over/under outcomes are Over then Under, handicap outcomes are Team1 then Team2
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers = [json, borsh])]
+pub enum LineKind {
+    OverUnder,
+    Handicap,
+}
+
+#[near(serializers = [json, borsh])]
+pub struct AdminProposal {
+    // The account proposed to become the admin
+    pub new_admin: AccountId,
+
+    // The timestamp in nanoseconds after which the proposal can no longer be accepted
+    pub expires_at: Option<U64>,
+}
+
+// Protocol parameters that can be changed without redeploying the contract
+#[derive(Clone, Debug)]
+#[near(serializers = [json, borsh])]
+pub struct Config {
+    // The time that rewards for staking are distributed over in nanoseconds, default is one month - 2_628_000_000_000_000
+    pub rewards_period: U64,
+
+    // The buffer time in nanoseconds before a user unstake since last staking, default is one week - 604_800_000_000_000
+    pub unstake_time_buffer: U64,
+
+    // The minimum amount of rewards required to be able to swap, default is 100 USDC - 100_000_000
+    pub min_swap_amount: U128,
+
+    // The shares of a profit in basis points sent to staking rewards, the treasury and the insurance fund
+    // the rest of the profit goes to the fees fund, defaults are 60%, 30% and 5%
+    pub staking_share_bps: u32,
+    pub treasury_share_bps: u32,
+    pub insurance_share_bps: u32,
+
+    // The market margin in basis points, default is 5%
+    pub market_margin_bps: u32,
+
+    // The weight factor used to determine the inital pool sizes, default is 1000
+    pub weight_factor: u32,
+
+    // The minimum amount of VEX a user must keep staked, default is 50 VEX
+    pub min_stake: U128,
+
+    // The share in basis points of the VEX bought in a stake swap that is given to the caller, default is 1%
+    pub stake_swap_reward_bps: u32,
+
+    // The time in nanoseconds a scheduled action must wait before it can be executed, default is two days
+    pub timelock_delay: U64,
+
+    // The maximum slippage in basis points below the quoted output a swap on the DEX accepts, default is 1%
+    pub max_slippage_bps: u32,
+
+    // The time in nanoseconds the TWAP that quotes are checked against is taken over, default is 30 minutes
+    pub twap_window: U64,
+
+    // The maximum difference in basis points between the price of a quote and the TWAP, default is 5%
+    pub max_price_deviation_bps: u32,
+
+    // The maximum amount of USDC swapped in one stake swap, the rest is carried over to the next stake swap
+    // zero means there is no maximum, default is zero
+    pub max_stake_swap_amount: U128,
+
+    // The minimum time in nanoseconds between the start of two stake swaps, default is zero
+    pub min_stake_swap_interval: U64,
+
+    // The time in nanoseconds unstaked VEX keeps bearing losses before it can be withdrawn, default is three days
+    pub unstake_cooldown: U64,
+
+    // The number of resolvers that must submit the same result to finish a match, default is 2
+    // at most the number of resolvers when it is changed
+    pub resolver_threshold: u32,
+
+    // The time in nanoseconds a proposed result can be disputed before it is final, default is two hours
+    pub challenge_period: U64,
+
+    // The maximum total potential winnings of parlays that have not been settled, default is 10,000 USDC
+    pub max_parlay_exposure: U128,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            rewards_period: U64(DEFAULT_REWARDS_PERIOD),
+            unstake_time_buffer: U64(DEFAULT_UNSTAKE_TIME_BUFFER),
+            min_swap_amount: U128(DEFAULT_MIN_SWAP_AMOUNT),
+            staking_share_bps: DEFAULT_STAKING_SHARE_BPS,
+            treasury_share_bps: DEFAULT_TREASURY_SHARE_BPS,
+            insurance_share_bps: DEFAULT_INSURANCE_SHARE_BPS,
+            market_margin_bps: DEFAULT_MARKET_MARGIN_BPS,
+            weight_factor: DEFAULT_WEIGHT_FACTOR,
+            min_stake: U128(FIFTY_VEX),
+            stake_swap_reward_bps: DEFAULT_STAKE_SWAP_REWARD_BPS,
+            timelock_delay: U64(DEFAULT_TIMELOCK_DELAY),
+            max_slippage_bps: DEFAULT_MAX_SLIPPAGE_BPS,
+            twap_window: U64(DEFAULT_TWAP_WINDOW),
+            max_price_deviation_bps: DEFAULT_MAX_PRICE_DEVIATION_BPS,
+            max_stake_swap_amount: U128(0),
+            min_stake_swap_interval: U64(0),
+            unstake_cooldown: U64(DEFAULT_UNSTAKE_COOLDOWN),
+            resolver_threshold: DEFAULT_RESOLVER_THRESHOLD,
+            challenge_period: U64(DEFAULT_CHALLENGE_PERIOD),
+            max_parlay_exposure: U128(DEFAULT_MAX_PARLAY_EXPOSURE),
+        }
+    }
+}
+
+// A sensitive admin action that must wait for the timelock delay before it can be executed
+#[derive(Clone, Debug)]
+#[near(serializers = [json, borsh])]
+pub enum TimelockAction {
+    // Removes an amount of USDC from the fees fund and sends it to the receiver
+    TakeFromFeesFund {
+        amount: U128,
+        receiver: AccountId,
+    },
+    // Removes an amount of USDC from the insurance fund and sends it to the receiver
+    TakeFromInsuranceFund {
+        amount: U128,
+        receiver: AccountId,
+    },
+    // Replaces the protocol parameters
+    UpdateConfig {
+        config: Config,
+    },
+    // Proposes a new admin which the new admin must accept
+    ProposeAdmin {
+        new_admin: AccountId,
+        expires_at: Option<U64>,
+    },
+    // Routes the VEX/USDC swaps through a different exchange or pools
+    SetDexAdapter {
+        dex_adapter: DexAdapter,
+    },
+    // Deploys the code staged with stage_code if it has this hash and migrates the state
+    UpgradeContract {
+        code_hash: Base58CryptoHash,
+    },
+}
+
+impl TimelockAction {
+    // The role that can schedule, execute and cancel the action, None if only the admin can
+    pub fn role(&self) -> Option<Role> {
+        match self {
+            TimelockAction::TakeFromFeesFund { .. }
+            | TimelockAction::TakeFromInsuranceFund { .. } => Some(Role::Treasurer),
+            TimelockAction::UpdateConfig { .. } => Some(Role::ConfigManager),
+            TimelockAction::ProposeAdmin { .. }
+            | TimelockAction::SetDexAdapter { .. }
+            | TimelockAction::UpgradeContract { .. } => None,
+        }
+    }
+}
+
+// An action scheduled with the timelock
+#[near(serializers = [json, borsh])]
+pub struct PendingAction {
+    // The action to execute
+    pub action: TimelockAction,
+
+    // The account that scheduled the action
+    pub scheduled_by: AccountId,
+
+    // The timestamp in nanoseconds after which the action can be executed
+    pub eta: U64,
+}
+
+// Flags to halt parts of the contract in an emergency, toggled by the admin or a pauser
+#[derive(Clone, Debug, Default)]
+#[near(serializers = [json, borsh])]
+pub struct PauseState {
+    // Bets on matches and markets and parlays are refunded
+    pub betting: bool,
+
+    // Stakes are refunded and unstaking is blocked
+    pub staking: bool,
+
+    // Claims and cash outs are blocked
+    pub claiming: bool,
+
+    // Stake swaps are blocked
+    pub stake_swaps: bool,
+}
+
+// A swap of staked VEX for USDC on the DEX to cover a loss the insurance fund could not
+#[derive(Clone, Debug)]
+#[near(serializers = [json, borsh])]
+pub struct LossCoverage {
+    // The step that is in progress or failed and needs to be resumed
+    pub step: LossCoverageStep,
+
+    // The USDC needed to cover the loss after the insurance fund was used
+    pub difference: U128,
+
+    // The VEX deposited in the DEX waiting to be swapped, it is removed from the stakers once it is sold
+    pub vex_deposited: U128,
+
+    // The minimum USDC the deposited VEX must be swapped for, set from the quote before the VEX is deposited
+    pub min_usdc_out: U128,
+
+    // The USDC received from the swap waiting to be withdrawn from the DEX
+    pub usdc_to_withdraw: U128,
+
+    // Whether a call to the DEX is waiting for its callback
+    pub in_flight: bool,
+
+    // The timestamp in nanoseconds of the last call to the DEX, if its callback has not run
+    // after DEX_CALL_TIMEOUT the loss coverage can be resumed from the deposits in the DEX
+    pub last_call_timestamp: U64,
+
+    // Losses made while this loss was being covered, they are covered once it completes
+    pub queued_loss: U128,
+}
+
+// The steps of covering a loss, each step's callback moves on to the next one
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers = [json, borsh])]
+pub enum LossCoverageStep {
+    // Get the amount of VEX needed and deposit it in the DEX
+    Deposit,
+    // Swap the deposited VEX for USDC
+    Swap,
+    // Withdraw the USDC from the DEX
+    Withdraw,
+}
+
+// A swap of USDC staking rewards for VEX on the DEX
+#[derive(Clone, Debug)]
+#[near(serializers = [json, borsh])]
+pub struct StakeSwap {
+    // The step that is in progress or failed and needs to be resumed
+    pub step: StakeSwapStep,
+
+    // The USDC rewards to deposit in the DEX, they are quoted before they are deposited
+    pub usdc_to_deposit: U128,
+
+    // The USDC deposited in the DEX waiting to be swapped
+    pub usdc_deposited: U128,
+
+    // The minimum VEX the deposited USDC must be swapped for, set from the quote before the USDC is deposited
+    pub min_vex_out: U128,
+
+    // The VEX received from the swap waiting to be withdrawn from the DEX
+    pub vex_to_withdraw: U128,
+
+    // The account that started the stake swap, it is rewarded once the VEX is withdrawn
+    pub caller: AccountId,
+
+    // Whether a call to the DEX is waiting for its callback
+    pub in_flight: bool,
+
+    // The timestamp in nanoseconds of the last call to the DEX, if its callback has not run
+    // after DEX_CALL_TIMEOUT the stake swap can be resumed from the deposits in the DEX
+    pub last_call_timestamp: U64,
+
+    // The timestamp of the last stake swap before this one, restored if the deposit fails
+    pub previous_timestamp: U64,
+
+    // The number of finished matches removed from the queue once the deposit succeeds
+    pub num_to_pop: u16,
+
+    // The rewards of the finished matches removed from the total once the deposit succeeds
+    pub expired_rewards: U128,
+
+    // The USDC over the max stake swap amount carried over once the deposit succeeds
+    pub usdc_carried_over: U128,
+}
+
+// The steps of a stake swap, a failed quote or deposit is reverted so only the swap and withdraw are resumed
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers = [json, borsh])]
+pub enum StakeSwapStep {
+    // Quote the USDC rewards and deposit them in the DEX
+    Deposit,
+    // Swap the deposited USDC for VEX
+    Swap,
+    // Withdraw the VEX from the DEX
+    Withdraw,
+}
+
+// The exchange the VEX/USDC swaps go through, each variant is one implementation in the dex module
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers = [json, borsh])]
+pub enum DexAdapter {
+    // Ref Finance v1, swaps from VEX to USDC go through the pools of the route in order
+    // and swaps from USDC to VEX go through them in reverse
+    RefFinance {
+        contract_id: AccountId,
+        route: Vec<RoutePool>,
+    },
+}
+
+// A pool in a swap route and the token it swaps into on the way from VEX to USDC
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers = [json, borsh])]
+pub struct RoutePool {
+    // The ID of the pool in the exchange
+    pub pool_id: U64,
+
+    // The token received from this pool, the last pool must swap into USDC
+    pub token_out: AccountId,
+}
+
+// The roles the admin can grant to other accounts, the admin can call every method
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers = [json, borsh])]
+pub enum Role {
+    // Can create, close and cancel matches and markets
+    MatchCreator,
+    // Can propose, vote on and dispute the results of matches, this can be an oracle contract
+    Resolver,
+    // Can take funds from the fees fund and the insurance fund
+    Treasurer,
+    // Can change the contract's configuration
+    ConfigManager,
+    // Can pause and unpause the contract
+    Pauser,
+    // Can resume loss coverages and stake swaps that failed part way through
+    Keeper,
 }
 
 #[derive(Clone)]
@@ -180,18 +791,90 @@ pub enum PayState {
 pub enum MatchState {
     Future,
     Current,
+    PendingResult,
+    Disputed,
     Finished,
     Error,
 }
 
+#[derive(Clone, Debug)]
+#[near(serializers = [json, borsh])]
+pub struct ProposedResult {
+    // The resolver that proposed the result
+    pub proposer: AccountId,
+
+    // The proposed winner
+    pub winner: Team,
+
+    // The proposed final score if the match has over/under or handicap markets
+    pub score: Option<(u32, u32)>,
+
+    // The timestamp in nanoseconds after which the result can be finalised
+    pub finalise_after: U64,
+
+    // The resolvers that agree with the result, including the proposer
+    pub confirmed_by: Vec<AccountId>,
+}
+
+#[derive(Clone, Debug)]
+#[near(serializers = [json, borsh])]
+pub struct ResultVote {
+    // The resolver that submitted the result
+    pub resolver: AccountId,
+
+    // The winner submitted by the resolver
+    pub winner: Team,
+}
+
+// The reason a transfer to the contract was rejected and refunded
+#[derive(Clone, Debug, PartialEq)]
+#[near(serializers = [json])]
+pub enum RejectionReason {
+    // The msg could not be parsed into an FtTransferAction
+    InvalidMessage,
+    // The token sent is not the token required for the action
+    WrongToken,
+    // The bet is less than one USDC
+    BetTooSmall,
+    // The bet arrived after the deadline set by the bettor
+    DeadlinePassed,
+    // No match exists with the given match ID
+    MatchNotFound,
+    // No market exists with the given market ID
+    MarketNotFound,
+    // The outcome being bet on is not possible in the match
+    InvalidOutcome,
+    // The parlay has too few or too many legs or more than one leg on a match
+    InvalidParlay,
+    // The match is not in the Future state so bets are closed
+    BettingClosed,
+    // The potential winnings are less than the minimum set by the bettor
+    PotentialWinningsTooLow,
+    // The parlay would take the parlay exposure over the maximum in the config
+    ParlayExposureTooHigh,
+    // The stake is worth no stake shares or would leave the user with less than 50 VEX staked
+    StakeTooSmall,
+    // The action is paused
+    Paused,
+}
+
+// The index of each variant is its storage prefix so new keys must only be appended
 #[derive(BorshStorageKey)]
 #[near]
 pub enum StorageKey {
-    Matches,
-    BetsByUser,
+    LegacyMatches,
+    LegacyBetsByUser,
     UsersStake,
     StakingRewards,
     Funds,
+    Markets,
+    MarketBetsByUser,
+    MatchIdsByExternalRef,
+    RolesByAccount,
+    PendingActions,
+    BetsByUser,
+    WithdrawalTickets,
+    Matches,
 }
 
 // Construct a 256-bit unsigned integer
@@ -202,15 +885,153 @@ construct_uint! {
 // A unique identifier for a match of the form "team_1-team_2-date"
 pub type MatchId = String;
 
+// A unique identifier for a market of the form "name-date"
+// or "match_id-kind-line" for over/under and handicap markets
+pub type MarketId = String;
+
 // A unique identifier for a bet
 pub type BetId = U64;
 
-// The weight factor used to determine the inital pool sizes
-pub const WEIGHT_FACTOR: f64 = 1000.0;
+// A unique identifier for an action scheduled with the timelock
+pub type ActionId = U64;
+
+// A unique identifier for a withdrawal ticket
+pub type TicketId = U64;
+
+// The default weight factor used to determine the inital pool sizes
+pub const DEFAULT_WEIGHT_FACTOR: u32 = 1000;
+
+// One in the fixed-point representation used for odds and the payout curve
+pub const FIXED_POINT_ONE: u128 = 1_000_000_000_000_000_000;
+
+// The denominator for values given in basis points
+pub const BASIS_POINTS: u128 = 10_000;
+
+// The default market margin in basis points, 5%
+pub const DEFAULT_MARKET_MARGIN_BPS: u32 = 500;
+
+// The maximum market margin in basis points, 20%
+pub const MAX_MARKET_MARGIN_BPS: u32 = 2_000;
+
+// The default shares of a profit in basis points sent to staking rewards, the treasury and the insurance fund
+pub const DEFAULT_STAKING_SHARE_BPS: u32 = 6_000;
+pub const DEFAULT_TREASURY_SHARE_BPS: u32 = 3_000;
+pub const DEFAULT_INSURANCE_SHARE_BPS: u32 = 500;
+
+// The default share in basis points of the VEX bought in a stake swap that is given to the caller, 1%
+pub const DEFAULT_STAKE_SWAP_REWARD_BPS: u32 = 100;
+
+// The maximum share in basis points of the VEX bought in a stake swap that is given to the caller, 10%
+pub const MAX_STAKE_SWAP_REWARD_BPS: u32 = 1_000;
+
+// The default maximum slippage in basis points below the quoted output of a swap, 1%
+pub const DEFAULT_MAX_SLIPPAGE_BPS: u32 = 100;
+
+// The maximum slippage in basis points that can be configured, 10%
+pub const MAX_SLIPPAGE_LIMIT_BPS: u32 = 1_000;
+
+// The default time in nanoseconds the TWAP that quotes are checked against is taken over, 30 minutes
+pub const DEFAULT_TWAP_WINDOW: u64 = 1_800_000_000_000;
+
+// The maximum TWAP window in nanoseconds, one day
+pub const MAX_TWAP_WINDOW: u64 = 86_400_000_000_000;
+
+// The default maximum difference in basis points between the price of a quote and the TWAP, 5%
+pub const DEFAULT_MAX_PRICE_DEVIATION_BPS: u32 = 500;
+
+// The maximum price deviation in basis points that can be configured, 50%
+pub const MAX_PRICE_DEVIATION_LIMIT_BPS: u32 = 5_000;
+
+// The number of price observations kept, the oldest is removed when a new one is added
+pub const MAX_PRICE_OBSERVATIONS: usize = 64;
+
+// The maximum number of price observations recorded per TWAP window, half of the observations
+// kept so the ring buffer always covers twice the TWAP window
+pub const PRICE_OBSERVATIONS_PER_WINDOW: u64 = 32;
+
+// The amount of VEX quoted on the DEX when a keeper pings the price
+pub const PRICE_PING_AMOUNT: u128 = FIFTY_VEX;
+
+// The default time that rewards for staking are distributed over in nanoseconds, one month
+pub const DEFAULT_REWARDS_PERIOD: u64 = 2_628_000_000_000_000;
+
+// The default buffer time in nanoseconds before a user can unstake since last staking, one week
+pub const DEFAULT_UNSTAKE_TIME_BUFFER: u64 = 604_800_000_000_000;
+
+// The default time in nanoseconds unstaked VEX keeps bearing losses before it can be withdrawn, three days
+pub const DEFAULT_UNSTAKE_COOLDOWN: u64 = 259_200_000_000_000;
+
+// The maximum number of withdrawal tickets a user can have at once
+pub const MAX_WITHDRAWAL_TICKETS: usize = 10;
+
+// The default minimum amount of rewards required to be able to swap, 100 USDC
+pub const DEFAULT_MIN_SWAP_AMOUNT: u128 = 100 * ONE_USDC;
+
+// The default time in nanoseconds a scheduled action must wait before it can be executed, two days
+pub const DEFAULT_TIMELOCK_DELAY: u64 = 172_800_000_000_000;
+
+// The minimum time in nanoseconds a scheduled action must wait before it can be executed, one day
+pub const MIN_TIMELOCK_DELAY: u64 = 86_400_000_000_000;
+
+// The maximum rewards period, unstake time buffer and timelock delay in nanoseconds, one year
+pub const MAX_CONFIG_PERIOD: u64 = 31_536_000_000_000_000;
+
+// The maximum number of pools in a swap route
+pub const MAX_ROUTE_POOLS: usize = 2;
+
+// The static gas in TGas of a quote of one pool in the DEX
+pub const DEX_QUOTE_GAS: u64 = 10;
+
+// The static gas in TGas of a deposit, swap or withdraw in the DEX and of a transfer of tokens
+pub const DEX_CALL_GAS: u64 = 30;
+
+// The gas in TGas a method in a pipeline with the DEX uses itself on top of the gas it attaches
+pub const DEX_STEP_GAS: u64 = 15;
+
+// The most gas in TGas that can be attached to a call
+pub const MAX_PREPAID_GAS: u64 = 300;
+
+// The minimum and maximum number of outcomes in a market
+pub const MIN_MARKET_OUTCOMES: usize = 2;
+pub const MAX_MARKET_OUTCOMES: usize = 64;
+
+// The minimum and maximum number of legs in a parlay
+pub const MIN_PARLAY_LEGS: usize = 2;
+pub const MAX_PARLAY_LEGS: usize = 10;
+
+// The default maximum total potential winnings of parlays that have not been settled, 10,000 USDC
+pub const DEFAULT_MAX_PARLAY_EXPOSURE: u128 = 10_000 * ONE_USDC;
+
+// The scale of the line in over/under and handicap markets, lines are given in tenths
+pub const LINE_SCALE: i64 = 10;
+
+// The time in nanoseconds after which a call to the DEX whose callback has not run can be resumed, one hour
+pub const DEX_CALL_TIMEOUT: u64 = 3_600_000_000_000;
+
+// The default time in nanoseconds a proposed result can be disputed, two hours
+pub const DEFAULT_CHALLENGE_PERIOD: u64 = 7_200_000_000_000;
+
+// The minimum time in nanoseconds a proposed result can be disputed, one hour
+pub const MIN_CHALLENGE_PERIOD: u64 = 3_600_000_000_000;
+
+// The default number of resolvers that must submit the same result to finish a match
+pub const DEFAULT_RESOLVER_THRESHOLD: u32 = 2;
+
+// The version of the contract's state layout, increase this when a migration is added
+pub const STATE_VERSION: u32 = 1;
+
+// The storage key the state version is kept under, deployments without it use the version 0 layout
+pub const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+
+// The storage key code is staged under until an upgrade is executed with the timelock
+pub const STAGED_CODE_KEY: &[u8] = b"STAGED_CODE";
 
 // One USDC in its lowest denomination
 pub const ONE_USDC: u128 = 1_000_000; // Note that this will have to change if USDC decimals are not 6
 
+// One VEX in its lowest denomination
+pub const ONE_VEX: u128 = 1_000_000_000_000_000_000;
+
 // Fifty VEX in its lowest denomination
 pub const FIFTY_VEX: u128 = 50_000_000_000_000_000_000; // Note that this will have to change if VEX decimals are not 18
 
@@ -229,37 +1050,58 @@ impl Contract {
         usdc_token_contract: AccountId,
         vex_token_contract: AccountId,
         treasury: AccountId,
-        ref_contract: AccountId,
-        ref_pool_id: U64,
-        rewards_period: U64,
-        unstake_time_buffer: U64,
-        min_swap_amount: U128,
+        dex_adapter: DexAdapter,
+        config: Config,
     ) -> Self {
+        validate_config(&config);
+
         let total_staked_balance = U128(INITIAL_ACCOUNT_BALANCE - STAKE_SHARE_PRICE_GUARANTEE_FUND);
 
-        Self {
+        write_state_version();
+
+        let contract = Self {
             admin,
+            pending_admin: None,
+            roles_by_account: IterableMap::new(StorageKey::RolesByAccount),
             usdc_token_contract,
             vex_token_contract,
             treasury,
-            ref_contract,
-            ref_pool_id: ref_pool_id.0,
+            dex_adapter,
             matches: IterableMap::new(StorageKey::Matches),
+            legacy_matches: IterableMap::new(StorageKey::LegacyMatches),
+            match_ids_by_external_ref: LookupMap::new(StorageKey::MatchIdsByExternalRef),
             bets_by_user: LookupMap::new(StorageKey::BetsByUser),
+            legacy_bets_by_user: LookupMap::new(StorageKey::LegacyBetsByUser),
+            markets: IterableMap::new(StorageKey::Markets),
+            market_bets_by_user: LookupMap::new(StorageKey::MarketBetsByUser),
             last_bet_id: U64(0),
             users_stake: LookupMap::new(StorageKey::UsersStake),
+            withdrawal_tickets: LookupMap::new(StorageKey::WithdrawalTickets),
+            last_ticket_id: U64(0),
             staking_rewards_queue: VecDeque::new(),
             usdc_staking_rewards: U128(0),
             last_stake_swap_timestamp: U64(0),
             total_staked_balance,
             total_stake_shares: total_staked_balance,
+            total_unstaking_balance: U128(0),
+            total_unstaking_shares: U128(0),
             fees_fund: U128(0),
             insurance_fund: U128(0),
             funds_to_payout: U128(0),
+            parlay_exposure: U128(0),
             funds_to_add: U128(0),
-            rewards_period: rewards_period.0,
-            unstake_time_buffer: unstake_time_buffer.0,
-            min_swap_amount: min_swap_amount.0,
-        }
+            config,
+            pending_actions: IterableMap::new(StorageKey::PendingActions),
+            last_action_id: U64(0),
+            pause_state: PauseState::default(),
+            loss_coverage: None,
+            stake_swap: None,
+            pending_stake_swap_usdc: U128(0),
+            price_observations: VecDeque::new(),
+        };
+
+        contract.validate_dex_adapter(&contract.dex_adapter);
+
+        contract
     }
 }
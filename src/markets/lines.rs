@@ -0,0 +1,35 @@
+use std::cmp::Ordering;
+
+use crate::*;
+
+// Determines the winner of a match from its final score, a tied score is a draw
+pub fn determine_winner(team_1_score: u32, team_2_score: u32) -> Team {
+    match team_1_score.cmp(&team_2_score) {
+        Ordering::Greater => Team::Team1,
+        Ordering::Less => Team::Team2,
+        Ordering::Equal => Team::Draw,
+    }
+}
+
+// Determines the index of the winning outcome of an over/under or handicap market from the
+// final score of its match, returns None if the score lands exactly on the line
+pub fn determine_line_outcome(
+    line: &MarketLine,
+    team_1_score: u32,
+    team_2_score: u32,
+) -> Option<u32> {
+    let team_1_score = i64::from(team_1_score) * LINE_SCALE;
+    let team_2_score = i64::from(team_2_score) * LINE_SCALE;
+
+    // Over and team 1 are the first outcome, under and team 2 are the second
+    let (first, second) = match line.kind {
+        LineKind::OverUnder => (team_1_score + team_2_score, line.value),
+        LineKind::Handicap => (team_1_score + line.value, team_2_score),
+    };
+
+    match first.cmp(&second) {
+        Ordering::Greater => Some(0),
+        Ordering::Less => Some(1),
+        Ordering::Equal => None,
+    }
+}
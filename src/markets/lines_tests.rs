@@ -0,0 +1,48 @@
+use crate::markets::lines::{determine_line_outcome, determine_winner};
+use crate::{LineKind, MarketLine, Team};
+
+fn line(kind: LineKind, value: i64) -> MarketLine {
+    MarketLine {
+        match_id: "RUBY-Nexus-17/08/2024".to_string(),
+        kind,
+        value,
+    }
+}
+
+#[test]
+fn test_over_under_outcome() {
+    // Over/under 2.5 maps
+    let over_under = line(LineKind::OverUnder, 25);
+
+    assert_eq!(determine_line_outcome(&over_under, 2, 1), Some(0));
+    assert_eq!(determine_line_outcome(&over_under, 2, 0), Some(1));
+
+    // The bets are refunded if the score lands on the line
+    let over_under = line(LineKind::OverUnder, 30);
+
+    assert_eq!(determine_line_outcome(&over_under, 2, 1), None);
+}
+
+#[test]
+fn test_handicap_outcome() {
+    // Team 1 -1.5 maps
+    let handicap = line(LineKind::Handicap, -15);
+
+    assert_eq!(determine_line_outcome(&handicap, 2, 0), Some(0));
+    assert_eq!(determine_line_outcome(&handicap, 2, 1), Some(1));
+    assert_eq!(determine_line_outcome(&handicap, 0, 2), Some(1));
+
+    // Team 1 +1 map
+    let handicap = line(LineKind::Handicap, 10);
+
+    assert_eq!(determine_line_outcome(&handicap, 1, 1), Some(0));
+    assert_eq!(determine_line_outcome(&handicap, 1, 2), None);
+    assert_eq!(determine_line_outcome(&handicap, 0, 2), Some(1));
+}
+
+#[test]
+fn test_winner_from_score() {
+    assert_eq!(determine_winner(2, 1), Team::Team1);
+    assert_eq!(determine_winner(0, 2), Team::Team2);
+    assert_eq!(determine_winner(1, 1), Team::Draw);
+}
@@ -0,0 +1,252 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, near, require, Gas, NearToken, PromiseError};
+
+use crate::betting::bettor::determine_potential_winnings;
+use crate::events::Event;
+pub use crate::ext::*;
+use crate::*;
+
+#[near]
+impl Contract {
+    // Function to bet on an outcome of a market with USDC
+    // Returns the amount of USDC to refund to the bettor
+    pub(crate) fn bet_on_market(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        market_id: MarketId,
+        outcome: u32,
+        min_potential_winnings: Option<U128>,
+        deadline: Option<U64>,
+    ) -> U128 {
+        // Validate the bet, if it is rejected the full amount is refunded
+        let potential_winnings = match self.validate_market_bet(
+            amount,
+            &market_id,
+            outcome,
+            min_potential_winnings,
+            deadline,
+        ) {
+            Ok(potential_winnings) => potential_winnings,
+            Err(reason) => {
+                Event::MarketBetRejected {
+                    account_id: &sender_id,
+                    amount,
+                    market_id,
+                    reason,
+                }
+                .emit();
+
+                return amount;
+            }
+        };
+
+        // Get relevant market
+        let relevant_market = self.markets.get_mut(&market_id).unwrap();
+
+        // Increment total bets for the outcome
+        let outcome_pool = &mut relevant_market.outcomes[outcome as usize];
+        outcome_pool.total_bets = U128(outcome_pool.total_bets.0 + amount.0);
+        outcome_pool.potential_winnings =
+            U128(outcome_pool.potential_winnings.0 + potential_winnings.0);
+        let new_pool_size = outcome_pool.total_bets;
+
+        // Creates a new bet
+        let new_bet = MarketBet {
+            market_id: market_id.clone(),
+            outcome,
+            bet_amount: amount,
+            potential_winnings,
+            pay_state: None,
+        };
+
+        // Increments bet ID
+        self.last_bet_id.0 += 1;
+        let bet_id_string = self.last_bet_id.0.to_string();
+
+        // Inserts the new bet, creates a new map if the user has not bet on a market previously
+        if self.market_bets_by_user.get(&sender_id).is_none() {
+            let new_map: IterableMap<BetId, MarketBet> = IterableMap::new(bet_id_string.as_bytes());
+            self.market_bets_by_user.insert(sender_id.clone(), new_map);
+        };
+
+        let market_bets_by_user = self.market_bets_by_user.get_mut(&sender_id).unwrap();
+
+        market_bets_by_user.insert(self.last_bet_id, new_bet);
+
+        Event::MarketBet {
+            account_id: &sender_id,
+            bet_id: self.last_bet_id,
+            amount,
+            market_id,
+            outcome,
+            potential_winnings,
+            new_pool_size,
+        }
+        .emit();
+
+        U128(0)
+    }
+
+    // Checks a bet on a market can be placed and returns its potential winnings
+    // or the reason the bet is rejected
+    pub(crate) fn validate_market_bet(
+        &self,
+        amount: U128,
+        market_id: &MarketId,
+        outcome: u32,
+        min_potential_winnings: Option<U128>,
+        deadline: Option<U64>,
+    ) -> Result<U128, RejectionReason> {
+        self.validate_bet_transfer(amount, deadline)?;
+
+        // Get relevant market
+        let relevant_market = self
+            .markets
+            .get(market_id)
+            .ok_or(RejectionReason::MarketNotFound)?;
+
+        if !matches!(relevant_market.effective_state(), MatchState::Future) {
+            return Err(RejectionReason::BettingClosed);
+        }
+
+        if outcome as usize >= relevant_market.outcomes.len() {
+            return Err(RejectionReason::InvalidOutcome);
+        }
+
+        // Determines potential winnings
+        let potential_winnings = determine_potential_winnings(
+            outcome as usize,
+            &relevant_market.total_bets(),
+            &amount,
+            self.config.market_margin_bps,
+        );
+
+        // Reject the bet if the odds moved against the bettor since they checked them
+        if let Some(min_potential_winnings) = min_potential_winnings {
+            if potential_winnings < min_potential_winnings {
+                return Err(RejectionReason::PotentialWinningsTooLow);
+            }
+        }
+
+        Ok(potential_winnings)
+    }
+
+    // Function to claim winnings or refund for a bet on a market
+    pub fn claim_market_bet(&mut self, bet_id: BetId) {
+        require!(
+            env::prepaid_gas() >= Gas::from_tgas(150),
+            "You need to attach 300 TGas"
+        );
+
+        require!(!self.pause_state.claiming, "Claiming is paused");
+
+        let bettor = env::predecessor_account_id();
+
+        // Get relevant user
+        let relevant_user = self
+            .market_bets_by_user
+            .get_mut(&bettor)
+            .unwrap_or_else(|| panic!("You have not made a bet on a market"));
+
+        // Get relevant bet
+        let relevant_bet = relevant_user
+            .get_mut(&bet_id)
+            .unwrap_or_else(|| panic!("No bet exists with bet id: {:?}", bet_id));
+
+        require!(
+            relevant_bet.pay_state.is_none(),
+            "You have already been paid out"
+        );
+
+        let market_id = &relevant_bet.market_id;
+
+        // Get market state of the market in the bet
+        let relevant_market = self.markets.get(market_id).unwrap_or_else(|| {
+            panic!(
+                "No market exists with market id: {} there must have been an error",
+                market_id
+            )
+        });
+
+        // Different flow depending on whether win or refund
+        let (amount, pay_state) = match relevant_market.market_state {
+            MatchState::Finished => {
+                // Checks they selected the winning outcome
+                require!(
+                    relevant_market.winner == Some(relevant_bet.outcome),
+                    "You did not select the winning outcome"
+                );
+
+                (relevant_bet.potential_winnings, PayState::Paid)
+            }
+            MatchState::Error => (relevant_bet.bet_amount, PayState::RefundPaid),
+            _ => panic!("Market state must be Finished or Error to claim funds"),
+        };
+
+        // Transfer the USDC to the bettor
+        ft_contract::ext(self.usdc_token_contract.clone())
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .with_static_gas(Gas::from_tgas(30))
+            .ft_transfer(bettor.clone(), amount)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(Gas::from_tgas(50))
+                    .claim_market_bet_callback(bettor, bet_id, amount, pay_state.clone()),
+            );
+
+        relevant_bet.pay_state = Some(pay_state);
+    }
+
+    #[private]
+    pub fn claim_market_bet_callback(
+        &mut self,
+        #[callback_result] call_result: Result<(), PromiseError>,
+        bettor: AccountId,
+        bet_id: BetId,
+        amount_received: U128,
+        pay_state: PayState,
+    ) -> String {
+        if call_result.is_err() {
+            // Get relevant bet and reset it so it can be claimed again
+            let relevant_bet = self
+                .market_bets_by_user
+                .get_mut(&bettor)
+                .and_then(|relevant_user| relevant_user.get_mut(&bet_id))
+                .unwrap_or_else(|| panic!("No bet exists with bet id: {:?}", bet_id));
+
+            relevant_bet.pay_state = None;
+
+            return "Failed transfer".to_string();
+        }
+
+        match pay_state {
+            PayState::Paid => {
+                Event::ClaimWinnings {
+                    account_id: &bettor,
+                    bet_id,
+                    amount_received,
+                }
+                .emit();
+            }
+            PayState::RefundPaid => {
+                Event::ClaimRefund {
+                    account_id: &bettor,
+                    bet_id,
+                    amount_received,
+                }
+                .emit();
+            }
+            PayState::CashedOut => {
+                Event::CashOut {
+                    account_id: &bettor,
+                    bet_id,
+                    amount_received,
+                }
+                .emit();
+            }
+        }
+
+        "Successful transfer".to_string()
+    }
+}
@@ -0,0 +1,6 @@
+pub mod lines;
+pub mod market_bettor;
+pub mod view_markets;
+
+#[cfg(test)]
+mod lines_tests;
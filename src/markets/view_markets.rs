@@ -0,0 +1,143 @@
+use near_sdk::json_types::U128;
+use near_sdk::{near, require};
+
+use crate::betting::bettor::determine_potential_winnings;
+use crate::betting::view_betting::determine_approx_odds;
+use crate::*;
+
+#[near(serializers = [json])]
+pub struct DisplayMarket {
+    pub market_id: MarketId,
+    pub game: String,
+    pub name: String,
+    pub outcome_labels: Vec<String>,
+    pub odds: Vec<U128>,
+    pub real_bets: Vec<U128>,
+    pub market_state: MatchState,
+    pub winner: Option<u32>,
+    pub line: Option<MarketLine>,
+    pub start_time: U64,
+}
+
+#[near]
+impl Contract {
+    // Returns a list of markets within a range
+    pub fn get_markets(&self, from_index: &Option<u32>, limit: &Option<u32>) -> Vec<DisplayMarket> {
+        let from = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(self.markets.len());
+
+        // Iterates over markets, formats them, and outputs them
+        self.markets
+            .iter()
+            .skip(from as usize)
+            .take(limit as usize)
+            .map(|(market_id, m)| format_market(market_id, m, self.config.market_margin_bps))
+            .collect()
+    }
+
+    // Returns a specific market by its ID
+    pub fn get_market(&self, market_id: &MarketId) -> DisplayMarket {
+        // Get relevant market
+        let relevant_market = self
+            .markets
+            .get(market_id)
+            .unwrap_or_else(|| panic!("No market exists with market id: {}", market_id));
+
+        // Return formated market
+        format_market(market_id, relevant_market, self.config.market_margin_bps)
+    }
+
+    // Returns the potential winnings you would get if you bet a certain
+    // amount on a certain outcome of a market
+    pub fn get_market_potential_winnings(
+        &self,
+        market_id: &MarketId,
+        outcome: u32,
+        bet_amount: &U128,
+    ) -> U128 {
+        // Get relevant market
+        let relevant_market = self
+            .markets
+            .get(market_id)
+            .unwrap_or_else(|| panic!("No market exists with market id: {}", market_id));
+
+        require!(
+            (outcome as usize) < relevant_market.outcomes.len(),
+            "This outcome is not possible in this market"
+        );
+
+        // Return potential winnings
+        determine_potential_winnings(
+            outcome as usize,
+            &relevant_market.total_bets(),
+            bet_amount,
+            self.config.market_margin_bps,
+        )
+    }
+
+    // Returns a specific bet on a market by its user and ID
+    pub fn get_market_bet(&self, bettor: &AccountId, bet_id: &BetId) -> &MarketBet {
+        // Get relevant user
+        let relevant_user = self
+            .market_bets_by_user
+            .get(bettor)
+            .unwrap_or_else(|| panic!("No user exists with Account ID: {:?}", bettor));
+
+        // Return relevant bet
+        relevant_user
+            .get(bet_id)
+            .unwrap_or_else(|| panic!("No bet exists with bet id: {:?}", bet_id))
+    }
+
+    // Returns a list of bets on markets made by a user within a range
+    pub fn get_users_market_bets(
+        &self,
+        bettor: &AccountId,
+        from_index: &Option<u32>,
+        limit: &Option<u32>,
+    ) -> Vec<(BetId, &MarketBet)> {
+        // Get relevant user's bets
+        let relevant_user_bets = self
+            .market_bets_by_user
+            .get(bettor)
+            .unwrap_or_else(|| panic!("{} has not bet on a market", bettor));
+
+        let from = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(relevant_user_bets.len());
+
+        // Return bet IDs and their bets
+        relevant_user_bets
+            .iter()
+            .skip(from as usize)
+            .take(limit as usize)
+            .map(|(&key, value)| (key, value))
+            .collect()
+    }
+}
+
+// Helper function to format a market to be displayed
+pub fn format_market(
+    market_id: &MarketId,
+    market: &Market,
+    market_margin_bps: u32,
+) -> DisplayMarket {
+    // Bets made by users, excluding the initial pool
+    let real_bets = market
+        .outcomes
+        .iter()
+        .map(|outcome| U128(outcome.total_bets.0 - outcome.initial_pool.0))
+        .collect();
+
+    DisplayMarket {
+        market_id: market_id.clone(),
+        game: market.game.clone(),
+        name: market.name.clone(),
+        outcome_labels: market.outcome_labels.clone(),
+        odds: determine_approx_odds(&market.total_bets(), market_margin_bps),
+        real_bets,
+        market_state: market.effective_state(),
+        winner: market.winner,
+        line: market.line.clone(),
+        start_time: market.start_time,
+    }
+}
@@ -0,0 +1,158 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::store::{IterableMap, LookupMap};
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{env, testing_env, AccountId};
+use std::collections::VecDeque;
+
+use crate::migration::state_v0::{ContractV0, MatchStateV0, MatchV0};
+use crate::migration::upgrade::read_state_version;
+use crate::test_utils::*;
+use crate::*;
+
+// Writes the state of a contract deployed before the state was versioned
+// with one match, one bet from Alice and a stake from Bob
+fn write_v0_state() {
+    let admin: AccountId = "admin.testnet".parse().unwrap();
+
+    let mut matches = IterableMap::new(StorageKey::LegacyMatches);
+    matches.insert(
+        "RUBY-Nexus-17/08/2024".to_string(),
+        MatchV0 {
+            game: "CSGO".to_string(),
+            team_1: "RUBY".to_string(),
+            team_2: "Nexus".to_string(),
+            team_1_total_bets: U128(610 * ONE_USDC),
+            team_2_total_bets: U128(400 * ONE_USDC),
+            team_1_initial_pool: U128(600 * ONE_USDC),
+            team_2_initial_pool: U128(400 * ONE_USDC),
+            team_1_potential_winnings: U128(16 * ONE_USDC),
+            team_2_potential_winnings: U128(0),
+            match_state: MatchStateV0::Current,
+            winner: None,
+        },
+    );
+    matches.flush();
+
+    let mut alices_bets = IterableMap::new(b"1");
+    alices_bets.insert(
+        U64(1),
+        Bet {
+            match_id: "RUBY-Nexus-17/08/2024".to_string(),
+            team: Team::Team1,
+            bet_amount: U128(10 * ONE_USDC),
+            potential_winnings: U128(16 * ONE_USDC),
+            pay_state: None,
+        },
+    );
+    let mut bets_by_user = LookupMap::new(StorageKey::LegacyBetsByUser);
+    bets_by_user.insert(alice(), alices_bets);
+    bets_by_user.flush();
+
+    let mut users_stake = LookupMap::new(StorageKey::UsersStake);
+    users_stake.insert(
+        bob(),
+        UserStake {
+            stake_shares: U128(60 * FIFTY_VEX),
+            unstake_timestamp: U64(5),
+        },
+    );
+    users_stake.flush();
+
+    let contract = ContractV0 {
+        admin: admin.clone(),
+        usdc_token_contract: "usdc.testnet".parse().unwrap(),
+        vex_token_contract: "vex.testnet".parse().unwrap(),
+        treasury: admin,
+        ref_contract: "ref.testnet".parse().unwrap(),
+        ref_pool_id: 0,
+        matches,
+        bets_by_user,
+        last_bet_id: U64(1),
+        users_stake,
+        staking_rewards_queue: VecDeque::new(),
+        usdc_staking_rewards: U128(0),
+        last_stake_swap_timestamp: U64(0),
+        total_staked_balance: U128(99 * FIFTY_VEX),
+        total_stake_shares: U128(99 * FIFTY_VEX),
+        fees_fund: U128(3 * ONE_USDC),
+        insurance_fund: U128(ONE_USDC),
+        funds_to_payout: U128(16 * ONE_USDC),
+        funds_to_add: U128(0),
+        rewards_period: 100,
+        unstake_time_buffer: 10,
+        min_swap_amount: 500,
+    };
+    env::state_write(&contract);
+}
+
+#[test]
+fn test_migrate_from_v0() {
+    testing_env!(VMContextBuilder::new().build());
+    write_v0_state();
+    assert_eq!(read_state_version(), 0);
+
+    let mut contract = Contract::migrate();
+    assert_eq!(read_state_version(), STATE_VERSION);
+
+    // Matches are only moved once migrate_matches is called
+    assert!(contract.matches.get("RUBY-Nexus-17/08/2024").is_none());
+    assert_eq!(contract.get_legacy_match_count(), 1);
+
+    contract.migrate_matches(10);
+    assert_eq!(contract.get_legacy_match_count(), 0);
+
+    // Matches are converted to the current layout
+    let migrated_match = contract.matches.get("RUBY-Nexus-17/08/2024").unwrap();
+    assert_eq!(
+        migrated_match.total_bets(),
+        vec![U128(610 * ONE_USDC), U128(400 * ONE_USDC)]
+    );
+    assert_eq!(
+        migrated_match.outcomes[0].potential_winnings,
+        U128(16 * ONE_USDC)
+    );
+    assert!(matches!(migrated_match.match_state, MatchState::Current));
+    assert_eq!(migrated_match.start_time, U64(u64::MAX));
+
+    // Stakes, funds and parameters are kept
+    assert_eq!(
+        contract.users_stake.get(&bob()).unwrap().stake_shares,
+        U128(60 * FIFTY_VEX)
+    );
+    assert_eq!(contract.total_staked_balance, U128(99 * FIFTY_VEX));
+    assert_eq!(contract.funds_to_payout, U128(16 * ONE_USDC));
+    assert_eq!(contract.last_bet_id, U64(1));
+    assert_eq!(contract.config.rewards_period, U64(100));
+    assert_eq!(contract.config.min_swap_amount, U128(500));
+
+    // Bets are only moved once migrate_bets is called but can be viewed before
+    assert!(contract.bets_by_user.get(&alice()).is_none());
+    match contract.get_bet(&alice(), &U64(1)) {
+        UserBet::Single(bet) => assert_eq!(bet.bet_amount, U128(10 * ONE_USDC)),
+        UserBet::Parlay(_) => panic!("Alice's legacy bet is a parlay"),
+    }
+    assert_eq!(contract.get_users_bets(&alice(), &None, &Some(10)).len(), 1);
+
+    contract.migrate_bets(vec![alice(), bob()]);
+
+    assert!(contract.legacy_bets_by_user.get(&alice()).is_none());
+    match contract.bets_by_user.get(&alice()).unwrap().get(&U64(1)) {
+        Some(UserBet::Single(bet)) => {
+            assert_eq!(bet.bet_amount, U128(10 * ONE_USDC));
+            assert_eq!(bet.potential_winnings, U128(16 * ONE_USDC));
+        }
+        _ => panic!("Alice's bet was not migrated"),
+    }
+    assert!(contract.bets_by_user.get(&bob()).is_none());
+}
+
+#[test]
+fn test_migrate_current_version() {
+    let contract = init_contract(100);
+    assert_eq!(read_state_version(), STATE_VERSION);
+    env::state_write(&contract);
+
+    let contract = Contract::migrate();
+    assert_eq!(contract.config.rewards_period, U64(100));
+    assert_eq!(read_state_version(), STATE_VERSION);
+}
@@ -0,0 +1,5 @@
+pub mod state_v0;
+pub mod upgrade;
+
+#[cfg(test)]
+mod migration_tests;
@@ -0,0 +1,152 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::near;
+use near_sdk::store::{IterableMap, LookupMap};
+use std::collections::VecDeque;
+
+use crate::*;
+
+// The layout of the contract's state before it was versioned
+#[near(serializers = [borsh])]
+pub struct ContractV0 {
+    pub admin: AccountId,
+    pub usdc_token_contract: AccountId,
+    pub vex_token_contract: AccountId,
+    pub treasury: AccountId,
+    pub ref_contract: AccountId,
+    pub ref_pool_id: u64,
+    pub matches: IterableMap<MatchId, MatchV0>,
+    pub bets_by_user: LookupMap<AccountId, IterableMap<BetId, Bet>>,
+    pub last_bet_id: BetId,
+    pub users_stake: LookupMap<AccountId, UserStake>,
+    pub staking_rewards_queue: VecDeque<MatchStakeInfo>,
+    pub usdc_staking_rewards: U128,
+    pub last_stake_swap_timestamp: U64,
+    pub total_staked_balance: U128,
+    pub total_stake_shares: U128,
+    pub fees_fund: U128,
+    pub insurance_fund: U128,
+    pub funds_to_payout: U128,
+    pub funds_to_add: U128,
+    pub rewards_period: u64,
+    pub unstake_time_buffer: u64,
+    pub min_swap_amount: u128,
+}
+
+// The layout of a match before matches could have a draw, a start time or sub markets
+#[near(serializers = [borsh])]
+pub struct MatchV0 {
+    pub game: String,
+    pub team_1: String,
+    pub team_2: String,
+    pub team_1_total_bets: U128,
+    pub team_2_total_bets: U128,
+    pub team_1_initial_pool: U128,
+    pub team_2_initial_pool: U128,
+    pub team_1_potential_winnings: U128,
+    pub team_2_potential_winnings: U128,
+    pub match_state: MatchStateV0,
+    pub winner: Option<Team>,
+}
+
+// The match states before results could be proposed and disputed
+#[near(serializers = [borsh])]
+pub enum MatchStateV0 {
+    Future,
+    Current,
+    Finished,
+    Error,
+}
+
+impl From<MatchV0> for Match {
+    fn from(old: MatchV0) -> Self {
+        Match {
+            game: old.game,
+            team_1: old.team_1,
+            team_2: old.team_2,
+            outcomes: vec![
+                OutcomePool {
+                    total_bets: old.team_1_total_bets,
+                    initial_pool: old.team_1_initial_pool,
+                    potential_winnings: old.team_1_potential_winnings,
+                },
+                OutcomePool {
+                    total_bets: old.team_2_total_bets,
+                    initial_pool: old.team_2_initial_pool,
+                    potential_winnings: old.team_2_potential_winnings,
+                },
+            ],
+            sub_markets: vec![],
+            // Old matches have no start time so betting stays open until the admin ends it
+            start_time: U64(u64::MAX),
+            match_state: match old.match_state {
+                MatchStateV0::Future => MatchState::Future,
+                MatchStateV0::Current => MatchState::Current,
+                MatchStateV0::Finished => MatchState::Finished,
+                MatchStateV0::Error => MatchState::Error,
+            },
+            winner: old.winner,
+            proposed_result: None,
+            result_votes: vec![],
+            disputed_by: vec![],
+            external_ref: None,
+        }
+    }
+}
+
+impl ContractV0 {
+    // Converts the version 0 state into the current layout, matches and bets are kept
+    // as legacy matches and bets until migrate_matches and migrate_bets are called
+    pub fn migrate(self) -> Contract {
+        Contract {
+            admin: self.admin,
+            pending_admin: None,
+            roles_by_account: IterableMap::new(StorageKey::RolesByAccount),
+            usdc_token_contract: self.usdc_token_contract.clone(),
+            vex_token_contract: self.vex_token_contract,
+            treasury: self.treasury,
+            dex_adapter: DexAdapter::RefFinance {
+                contract_id: self.ref_contract,
+                route: vec![RoutePool {
+                    pool_id: U64(self.ref_pool_id),
+                    token_out: self.usdc_token_contract.clone(),
+                }],
+            },
+            matches: IterableMap::new(StorageKey::Matches),
+            legacy_matches: self.matches,
+            match_ids_by_external_ref: LookupMap::new(StorageKey::MatchIdsByExternalRef),
+            bets_by_user: LookupMap::new(StorageKey::BetsByUser),
+            legacy_bets_by_user: self.bets_by_user,
+            markets: IterableMap::new(StorageKey::Markets),
+            market_bets_by_user: LookupMap::new(StorageKey::MarketBetsByUser),
+            last_bet_id: self.last_bet_id,
+            users_stake: self.users_stake,
+            withdrawal_tickets: LookupMap::new(StorageKey::WithdrawalTickets),
+            last_ticket_id: U64(0),
+            staking_rewards_queue: self.staking_rewards_queue,
+            usdc_staking_rewards: self.usdc_staking_rewards,
+            last_stake_swap_timestamp: self.last_stake_swap_timestamp,
+            total_staked_balance: self.total_staked_balance,
+            total_stake_shares: self.total_stake_shares,
+            total_unstaking_balance: U128(0),
+            total_unstaking_shares: U128(0),
+            fees_fund: self.fees_fund,
+            insurance_fund: self.insurance_fund,
+            funds_to_payout: self.funds_to_payout,
+            parlay_exposure: U128(0),
+            funds_to_add: self.funds_to_add,
+            config: Config {
+                rewards_period: U64(self.rewards_period),
+                unstake_time_buffer: U64(self.unstake_time_buffer),
+                min_swap_amount: U128(self.min_swap_amount),
+                ..Config::default()
+            },
+            pending_actions: IterableMap::new(StorageKey::PendingActions),
+            last_action_id: U64(0),
+            pause_state: PauseState::default(),
+            loss_coverage: None,
+            stake_swap: None,
+            pending_stake_swap_usdc: U128(0),
+            price_observations: VecDeque::new(),
+        }
+    }
+}
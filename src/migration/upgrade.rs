@@ -0,0 +1,165 @@
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::{borsh, env, near, require, Gas, NearToken, Promise};
+
+use crate::events::Event;
+use crate::migration::state_v0::ContractV0;
+use crate::*;
+
+#[near]
+impl Contract {
+    // Stages new code to be deployed by an UpgradeContract action once the timelock delay
+    // has passed, the code is given as the raw input of the call rather than as JSON
+    // Returns the hash of the code to schedule the upgrade with
+    pub fn stage_code(&mut self) -> Base58CryptoHash {
+        self.assert_admin();
+
+        let code = env::input().unwrap_or_else(|| panic!("No code was given"));
+        require!(!code.is_empty(), "No code was given");
+
+        let code_hash = Base58CryptoHash::from(env::sha256_array(&code));
+        env::storage_write(STAGED_CODE_KEY, &code);
+
+        Event::StageCode {
+            account_id: &env::predecessor_account_id(),
+            code_hash,
+        }
+        .emit();
+
+        code_hash
+    }
+
+    // Returns the hash of the code waiting to be deployed if any has been staged
+    pub fn get_staged_code_hash(&self) -> Option<Base58CryptoHash> {
+        env::storage_read(STAGED_CODE_KEY)
+            .map(|code| Base58CryptoHash::from(env::sha256_array(&code)))
+    }
+
+    // Moves up to limit matches created before the state was versioned into matches
+    // can be called by anyone until every match has been moved
+    pub fn migrate_matches(&mut self, limit: u32) {
+        let match_ids: Vec<MatchId> = self
+            .legacy_matches
+            .keys()
+            .take(limit as usize)
+            .cloned()
+            .collect();
+
+        for match_id in match_ids {
+            let old_match = self.legacy_matches.remove(&match_id).unwrap();
+            self.matches.insert(match_id, Match::from(old_match));
+        }
+    }
+
+    // Returns the number of matches still waiting to be moved by migrate_matches
+    pub fn get_legacy_match_count(&self) -> u32 {
+        self.legacy_matches.len()
+    }
+
+    // Converts the state from the version it was written in to the current version
+    // can only be called by the contract itself, usually as part of an upgrade
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old_version = read_state_version();
+
+        let contract = match old_version {
+            0 => env::state_read::<ContractV0>()
+                .unwrap_or_else(|| panic!("No state to migrate"))
+                .migrate(),
+            STATE_VERSION => {
+                env::state_read::<Contract>().unwrap_or_else(|| panic!("No state to migrate"))
+            }
+            _ => panic!("Unknown state version: {}", old_version),
+        };
+
+        write_state_version();
+
+        Event::Migrate {
+            old_version,
+            new_version: STATE_VERSION,
+        }
+        .emit();
+
+        contract
+    }
+
+    // Moves the bets of the given bettors made before the state was versioned into bets_by_user
+    // bettors without legacy bets are skipped so this can be called by anyone
+    // bets are also moved the first time their bettor claims or cashes out
+    pub fn migrate_bets(&mut self, bettors: Vec<AccountId>) {
+        for bettor in bettors {
+            self.migrate_legacy_bets(&bettor);
+        }
+    }
+
+    // Returns the version of the contract's state layout
+    pub fn get_state_version(&self) -> u32 {
+        read_state_version()
+    }
+}
+
+impl Contract {
+    // Moves the bets a bettor made before the state was versioned into bets_by_user
+    // does nothing if they have no legacy bets
+    pub(crate) fn migrate_legacy_bets(&mut self, bettor: &AccountId) {
+        let Some(mut legacy_bets) = self.legacy_bets_by_user.remove(bettor) else {
+            return;
+        };
+
+        let bets_to_migrate: Vec<(BetId, Bet)> = legacy_bets.drain().collect();
+        legacy_bets.flush();
+
+        // Creates a new map if the user has not bet since the upgrade
+        if self.bets_by_user.get(bettor).is_none() {
+            let prefix = format!("legacy-{}", bettor);
+            let new_map: IterableMap<BetId, UserBet> = IterableMap::new(prefix.as_bytes());
+            self.bets_by_user.insert(bettor.clone(), new_map);
+        }
+
+        let bets_by_user = self.bets_by_user.get_mut(bettor).unwrap();
+
+        for (bet_id, bet) in bets_to_migrate {
+            bets_by_user.insert(bet_id, UserBet::Single(bet));
+        }
+    }
+
+    // Deploys the staged code if it matches the scheduled hash and calls migrate on it
+    // in the same promise chain, called by execute_action for an UpgradeContract action
+    pub(crate) fn deploy_staged_code(&self, code_hash: Base58CryptoHash) -> Promise {
+        require!(
+            env::prepaid_gas() >= Gas::from_tgas(300),
+            "You need to attach 300 TGas"
+        );
+
+        let code =
+            env::storage_read(STAGED_CODE_KEY).unwrap_or_else(|| panic!("No code is staged"));
+        require!(
+            Base58CryptoHash::from(env::sha256_array(&code)) == code_hash,
+            "The staged code does not match the scheduled code hash"
+        );
+        env::storage_remove(STAGED_CODE_KEY);
+
+        Event::Upgrade { code_hash }.emit();
+
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(
+                "migrate".to_string(),
+                vec![],
+                NearToken::from_yoctonear(0),
+                Gas::from_tgas(200),
+            )
+    }
+}
+
+// Reads the state version, state written before the state was versioned is version 0
+pub fn read_state_version() -> u32 {
+    env::storage_read(STATE_VERSION_KEY)
+        .map(|bytes| borsh::from_slice(&bytes).unwrap_or_else(|_| panic!("Invalid state version")))
+        .unwrap_or(0)
+}
+
+// Marks the state as being in the current layout
+pub fn write_state_version() {
+    env::storage_write(STATE_VERSION_KEY, &borsh::to_vec(&STATE_VERSION).unwrap());
+}
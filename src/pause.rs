@@ -0,0 +1,45 @@
+use near_sdk::{env, near};
+
+use crate::events::Event;
+use crate::*;
+
+#[near]
+impl Contract {
+    // Pauses or unpauses parts of the contract, flags that are not given are left unchanged
+    pub fn set_pause_state(
+        &mut self,
+        betting: Option<bool>,
+        staking: Option<bool>,
+        claiming: Option<bool>,
+        stake_swaps: Option<bool>,
+    ) {
+        self.assert_role(Role::Pauser);
+
+        if let Some(betting) = betting {
+            self.pause_state.betting = betting;
+        }
+
+        if let Some(staking) = staking {
+            self.pause_state.staking = staking;
+        }
+
+        if let Some(claiming) = claiming {
+            self.pause_state.claiming = claiming;
+        }
+
+        if let Some(stake_swaps) = stake_swaps {
+            self.pause_state.stake_swaps = stake_swaps;
+        }
+
+        Event::SetPauseState {
+            account_id: &env::predecessor_account_id(),
+            pause_state: self.pause_state.clone(),
+        }
+        .emit();
+    }
+
+    // Returns which parts of the contract are paused
+    pub fn get_pause_state(&self) -> &PauseState {
+        &self.pause_state
+    }
+}
@@ -0,0 +1,264 @@
+use near_sdk::json_types::U64;
+use near_sdk::{env, near, require, PromiseOrValue};
+
+use crate::dex::gas::settle_gas;
+use crate::events::Event;
+use crate::markets::lines::determine_winner;
+use crate::*;
+
+#[near]
+impl Contract {
+    // Proposes the winner of a match that has no over/under or handicap markets
+    // the result can be disputed until the challenge period has passed
+    pub fn propose_result(&mut self, match_id: &MatchId, winner: Team) {
+        self.propose_result_internal(match_id, winner, None);
+    }
+
+    // Proposes the final score of a match, the winner and the result of every
+    // over/under and handicap market on the match are determined from it
+    pub fn propose_score(&mut self, match_id: &MatchId, team_1_score: u32, team_2_score: u32) {
+        let winner = determine_winner(team_1_score, team_2_score);
+
+        self.propose_result_internal(match_id, winner, Some((team_1_score, team_2_score)));
+    }
+
+    // Submits a resolver's vote on the winner of a match that has no over/under or handicap
+    // markets, the match is finished once enough resolvers agree and is disputed if any disagree
+    pub fn submit_result(&mut self, match_id: &MatchId, winner: Team) -> PromiseOrValue<()> {
+        self.require_dex_gas(settle_gas);
+
+        self.assert_resolver();
+
+        let resolver = env::predecessor_account_id();
+        let resolver_threshold = self.config.resolver_threshold;
+
+        let relevant_match = self
+            .matches
+            .get_mut(match_id)
+            .unwrap_or_else(|| panic!("No match exists with match id: {}", match_id));
+
+        require!(
+            matches!(relevant_match.effective_state(), MatchState::Current),
+            "Match state must be Current to submit a result"
+        );
+
+        require!(
+            relevant_match.outcome(&winner).is_some(),
+            "The winner must be a possible outcome of the match"
+        );
+
+        require!(
+            relevant_match.sub_markets.is_empty(),
+            "Matches with over/under or handicap markets must be proposed with the final score"
+        );
+
+        require!(
+            !relevant_match
+                .result_votes
+                .iter()
+                .any(|vote| vote.resolver == resolver),
+            "You have already submitted a result for this match"
+        );
+
+        relevant_match.result_votes.push(ResultVote {
+            resolver: resolver.clone(),
+            winner: winner.clone(),
+        });
+        let votes = relevant_match.result_votes.len() as u32;
+
+        Event::SubmitResult {
+            account_id: &resolver,
+            match_id: match_id.clone(),
+            winner: winner.clone(),
+            votes,
+        }
+        .emit();
+
+        // Conflicting votes block the match from being finished until the admin resolves it
+        if relevant_match
+            .result_votes
+            .iter()
+            .any(|vote| vote.winner != winner)
+        {
+            relevant_match.match_state = MatchState::Disputed;
+
+            Event::ConflictingResults {
+                match_id: match_id.clone(),
+            }
+            .emit();
+
+            return PromiseOrValue::Value(());
+        }
+
+        if votes >= resolver_threshold {
+            return self.resolve_match(match_id, winner, None);
+        }
+
+        PromiseOrValue::Value(())
+    }
+
+    // Confirms a proposed result, it can only be finalised once
+    // resolver_threshold resolvers including the proposer agree with it
+    pub fn confirm_result(&mut self, match_id: &MatchId) {
+        self.assert_resolver();
+
+        let account_id = env::predecessor_account_id();
+
+        let relevant_match = self
+            .matches
+            .get_mut(match_id)
+            .unwrap_or_else(|| panic!("No match exists with match id: {}", match_id));
+
+        require!(
+            matches!(relevant_match.match_state, MatchState::PendingResult),
+            "Match state must be PendingResult to confirm the result"
+        );
+
+        let proposed_result = relevant_match.proposed_result.as_mut().unwrap();
+
+        require!(
+            !proposed_result.confirmed_by.contains(&account_id),
+            "You have already confirmed this result"
+        );
+
+        proposed_result.confirmed_by.push(account_id.clone());
+
+        Event::ConfirmResult {
+            account_id: &account_id,
+            match_id: match_id.clone(),
+            confirmations: proposed_result.confirmed_by.len() as u32,
+        }
+        .emit();
+    }
+
+    // Disputes a proposed result before the challenge period has passed
+    // the match goes back to Current so a new result can be proposed
+    pub fn dispute_result(&mut self, match_id: &MatchId) {
+        self.assert_resolver();
+
+        let account_id = env::predecessor_account_id();
+
+        let relevant_match = self
+            .matches
+            .get_mut(match_id)
+            .unwrap_or_else(|| panic!("No match exists with match id: {}", match_id));
+
+        require!(
+            matches!(relevant_match.match_state, MatchState::PendingResult),
+            "Match state must be PendingResult to dispute the result"
+        );
+
+        // Each resolver can only dispute once so a single resolver cannot block a match forever
+        require!(
+            !relevant_match.disputed_by.contains(&account_id),
+            "You have already disputed a result for this match"
+        );
+
+        let proposed_result = relevant_match.proposed_result.take().unwrap();
+
+        require!(
+            env::block_timestamp() < proposed_result.finalise_after.0,
+            "The challenge period has passed"
+        );
+
+        relevant_match.match_state = MatchState::Current;
+        relevant_match.disputed_by.push(account_id.clone());
+
+        Event::DisputeResult {
+            account_id: &account_id,
+            match_id: match_id.clone(),
+            proposed_winner: proposed_result.winner,
+        }
+        .emit();
+    }
+
+    // Finalises a proposed result once the challenge period has passed and enough
+    // resolvers have confirmed it, handles the profit or loss, can be called by anyone
+    pub fn finalise_result(&mut self, match_id: &MatchId) -> PromiseOrValue<()> {
+        self.require_dex_gas(settle_gas);
+
+        let relevant_match = self
+            .matches
+            .get(match_id)
+            .unwrap_or_else(|| panic!("No match exists with match id: {}", match_id));
+
+        require!(
+            matches!(relevant_match.match_state, MatchState::PendingResult),
+            "Match state must be PendingResult to finalise the result"
+        );
+
+        let proposed_result = relevant_match.proposed_result.clone().unwrap();
+
+        require!(
+            env::block_timestamp() >= proposed_result.finalise_after.0,
+            "The result cannot be finalised until the challenge period has passed"
+        );
+
+        require!(
+            proposed_result.confirmed_by.len() as u32 >= self.config.resolver_threshold,
+            "The result cannot be finalised until enough resolvers have confirmed it"
+        );
+
+        self.resolve_match(match_id, proposed_result.winner, proposed_result.score)
+    }
+
+    // Puts a match in the PendingResult state with the proposed result
+    fn propose_result_internal(
+        &mut self,
+        match_id: &MatchId,
+        winner: Team,
+        score: Option<(u32, u32)>,
+    ) {
+        self.assert_resolver();
+
+        let proposer = env::predecessor_account_id();
+        let finalise_after = U64(env::block_timestamp() + self.config.challenge_period.0);
+
+        let relevant_match = self
+            .matches
+            .get_mut(match_id)
+            .unwrap_or_else(|| panic!("No match exists with match id: {}", match_id));
+
+        require!(
+            matches!(relevant_match.effective_state(), MatchState::Current),
+            "Match state must be Current to propose a result"
+        );
+
+        require!(
+            relevant_match.outcome(&winner).is_some(),
+            "The winner must be a possible outcome of the match"
+        );
+
+        require!(
+            score.is_some() || relevant_match.sub_markets.is_empty(),
+            "Matches with over/under or handicap markets must be proposed with the final score"
+        );
+
+        relevant_match.match_state = MatchState::PendingResult;
+        relevant_match.proposed_result = Some(ProposedResult {
+            proposer: proposer.clone(),
+            winner: winner.clone(),
+            score,
+            finalise_after,
+            confirmed_by: vec![proposer.clone()],
+        });
+
+        Event::ProposeResult {
+            account_id: &proposer,
+            match_id: match_id.clone(),
+            winner,
+            score,
+            finalise_after,
+        }
+        .emit();
+    }
+
+    // Checks the caller has been granted the Resolver role, unlike other roles
+    // the admin does not have it unless it is granted
+    pub(crate) fn assert_resolver(&self) {
+        require!(
+            self.has_role(&env::predecessor_account_id(), &Role::Resolver),
+            "Only a resolver can call this method"
+        );
+    }
+}
@@ -0,0 +1,35 @@
+use near_sdk::json_types::U64;
+use near_sdk::near;
+
+use crate::*;
+
+#[near]
+impl Contract {
+    // Returns the number of resolvers that must submit the same result to finish a match
+    pub fn get_resolver_threshold(&self) -> u32 {
+        self.config.resolver_threshold
+    }
+
+    // Returns the results submitted by resolvers for a match that has not been finished
+    pub fn get_result_votes(&self, match_id: &MatchId) -> &Vec<ResultVote> {
+        &self
+            .matches
+            .get(match_id)
+            .unwrap_or_else(|| panic!("No match exists with match id: {}", match_id))
+            .result_votes
+    }
+
+    // Returns the time in nanoseconds a proposed result can be disputed
+    pub fn get_challenge_period(&self) -> U64 {
+        self.config.challenge_period
+    }
+
+    // Returns the result proposed for a match if it has not been finalised
+    pub fn get_proposed_result(&self, match_id: &MatchId) -> Option<&ProposedResult> {
+        self.matches
+            .get(match_id)
+            .unwrap_or_else(|| panic!("No match exists with match id: {}", match_id))
+            .proposed_result
+            .as_ref()
+    }
+}
@@ -0,0 +1,87 @@
+use near_sdk::{env, near, require};
+
+use crate::events::Event;
+use crate::*;
+
+#[near]
+impl Contract {
+    // Grants a role to an account, only callable by the admin
+    pub fn grant_role(&mut self, account_id: AccountId, role: Role) {
+        self.assert_admin();
+
+        let roles = self.roles_by_account.entry(account_id.clone()).or_default();
+
+        require!(
+            !roles.contains(&role),
+            format!("The account already has the {:?} role", role)
+        );
+
+        roles.push(role.clone());
+
+        Event::GrantRole {
+            account_id: &account_id,
+            role,
+        }
+        .emit();
+    }
+
+    // Revokes a role from an account, only callable by the admin
+    pub fn revoke_role(&mut self, account_id: AccountId, role: Role) {
+        self.assert_admin();
+
+        let roles = self
+            .roles_by_account
+            .get_mut(&account_id)
+            .filter(|roles| roles.contains(&role))
+            .unwrap_or_else(|| panic!("The account does not have the {:?} role", role));
+
+        roles.retain(|account_role| account_role != &role);
+
+        if roles.is_empty() {
+            self.roles_by_account.remove(&account_id);
+        }
+
+        Event::RevokeRole {
+            account_id: &account_id,
+            role,
+        }
+        .emit();
+    }
+
+    // Returns the roles granted to an account
+    pub fn get_roles(&self, account_id: AccountId) -> Vec<Role> {
+        self.roles_by_account
+            .get(&account_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // Returns the accounts that have been granted a role
+    pub fn get_role_members(&self, role: Role) -> Vec<&AccountId> {
+        self.roles_by_account
+            .iter()
+            .filter(|(_, roles)| roles.contains(&role))
+            .map(|(account_id, _)| account_id)
+            .collect()
+    }
+
+    // Checks the caller has been granted a role
+    pub(crate) fn has_role(&self, account_id: &AccountId, role: &Role) -> bool {
+        self.roles_by_account
+            .get(account_id)
+            .is_some_and(|roles| roles.contains(role))
+    }
+
+    // Checks the caller is the admin or has been granted a role
+    pub(crate) fn assert_role(&self, role: Role) {
+        let account_id = env::predecessor_account_id();
+
+        require!(
+            account_id == self.admin || self.has_role(&account_id, &role),
+            format!(
+                "Only the admin or an account with the {:?} role can call this method",
+                role
+            )
+        );
+    }
+}
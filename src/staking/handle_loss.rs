@@ -1,12 +1,32 @@
-use near_sdk::{env, log, near, Gas, NearToken, PromiseError, PromiseOrValue};
+use near_sdk::{env, log, near, require, AccountId, Promise, PromiseError, PromiseOrValue};
+use std::collections::HashMap;
 
-pub use crate::ext::*;
+use crate::dex::adapter::{min_amount_out, DexQuote};
+use crate::dex::gas::{resume_loss_coverage_gas, DexCallback};
+use crate::events::Event;
 use crate::*;
 
 #[near]
 impl Contract {
     // Handles the case when a match finishes and there is a loss
     pub(crate) fn handle_loss(&mut self, loss: u128) -> PromiseOrValue<()> {
+        if let Some(loss_coverage) = self.loss_coverage.as_mut() {
+            // If the loss being covered has not been deposited in the DEX yet, such as a loss
+            // queued by the last loss coverage, then it is started now together with this loss
+            if loss_coverage.step == LossCoverageStep::Deposit && !loss_coverage.in_flight {
+                let from_insurance_fund = loss.min(self.insurance_fund.0);
+                self.insurance_fund = U128(self.insurance_fund.0 - from_insurance_fund);
+                loss_coverage.difference =
+                    U128(loss_coverage.difference.0 + loss - from_insurance_fund);
+
+                return PromiseOrValue::Promise(self.deposit_vex_to_cover_loss());
+            }
+
+            // Otherwise this loss is covered once the loss being covered completes
+            loss_coverage.queued_loss = U128(loss_coverage.queued_loss.0 + loss);
+            return PromiseOrValue::Value(());
+        }
+
         // If the loss can be covered by the insurance fund then do so
         if loss < self.insurance_fund.0 {
             self.insurance_fund = U128(self.insurance_fund.0 - loss);
@@ -14,56 +34,105 @@ impl Contract {
         }
 
         // Calculate how much more USDC is needed to cover the loss
+        // the insurance fund is all used
         let difference = U128(loss - self.insurance_fund.0);
+        self.insurance_fund = U128(0);
+
+        self.loss_coverage = Some(LossCoverage {
+            step: LossCoverageStep::Deposit,
+            difference,
+            vex_deposited: U128(0),
+            min_usdc_out: U128(0),
+            usdc_to_withdraw: U128(0),
+            in_flight: false,
+            last_call_timestamp: U64(0),
+            queued_loss: U128(0),
+        });
+
+        PromiseOrValue::Promise(self.deposit_vex_to_cover_loss())
+    }
+
+    // Continues covering a loss from the step that failed, or from the deposits in the DEX
+    // if the callback of the last call to the DEX has not run after DEX_CALL_TIMEOUT
+    pub fn resume_loss_coverage(&mut self) -> Promise {
+        self.assert_role(Role::Keeper);
+
+        self.require_dex_gas(resume_loss_coverage_gas);
+
+        let loss_coverage = self
+            .loss_coverage
+            .as_ref()
+            .unwrap_or_else(|| panic!("No loss is being covered"));
+
+        let in_flight = loss_coverage.in_flight;
+
+        require!(
+            !in_flight
+                || env::block_timestamp() >= loss_coverage.last_call_timestamp.0 + DEX_CALL_TIMEOUT,
+            "A call to the DEX is still in progress"
+        );
+
+        let step = loss_coverage.step.clone();
+
+        Event::ResumeLossCoverage {
+            account_id: &env::predecessor_account_id(),
+            step: step.clone(),
+        }
+        .emit();
+
+        // The call may have gone through without being recorded so the deposits are checked first
+        if in_flight {
+            self.start_loss_coverage_call();
+
+            return self.dex_get_deposits().then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(self.dex_callback_gas(DexCallback::LossDeposits))
+                    .ref_loss_coverage_deposits_callback(),
+            );
+        }
+
+        match step {
+            LossCoverageStep::Deposit => self.deposit_vex_to_cover_loss(),
+            LossCoverageStep::Swap => self.swap_vex_to_cover_loss(),
+            LossCoverageStep::Withdraw => self.withdraw_usdc_to_cover_loss(),
+        }
+    }
 
-        // If the promise fails then we need to call the end match function again
-        PromiseOrValue::Promise(
-            ref_contract::ext(self.ref_contract.clone())
-                .with_attached_deposit(NearToken::from_yoctonear(1))
-                .with_static_gas(Gas::from_tgas(30))
-                .get_return_by_output(
-                    self.ref_pool_id,
-                    self.vex_token_contract.clone(),
-                    difference,
-                    self.usdc_token_contract.clone(),
-                )
-                .then(
-                    Self::ext(env::current_account_id())
-                        .with_static_gas(Gas::from_tgas(250))
-                        .ref_loss_view_callback(difference),
-                ),
-        )
-    }
-
-    // Callback after getting the return from ref finance
+    // Callback after getting the return from the DEX
     #[private]
     pub fn ref_loss_view_callback(
         &mut self,
         #[callback_result] call_result: Result<U128, PromiseError>,
-        difference: U128,
     ) {
-        let amount_in = call_result.unwrap_or_else(|_| panic!("View in ref finance failed"));
+        let Ok(amount_in) = call_result else {
+            // No state has changed so the deposit can be resumed
+            self.loss_coverage_failed("View in the DEX failed");
+            return;
+        };
+
+        // A price far from the TWAP may be manipulated so no VEX is deposited
+        let difference = self.loss_coverage.as_ref().unwrap().difference;
+        if !self.quote_within_twap(difference.0, amount_in.0) {
+            self.loss_coverage_failed("The DEX price is too far from the TWAP");
+            return;
+        }
 
         // Add an extra 5% to the amount to swap to account for price change between blocks
         let amount_to_swap =
             (U256::from(105) * U256::from(amount_in.0) / U256::from(100)).as_u128();
 
-        // Call to ref finance to deposit the USDC rewards
+        // The minimum output is fixed before anything is deposited, the quoted VEX buys the difference
+        // so the extra VEX must buy at least the difference less the max slippage
+        let min_usdc_out = min_amount_out(difference, self.config.max_slippage_bps);
+        self.loss_coverage.as_mut().unwrap().min_usdc_out = min_usdc_out;
+
+        // Call to the DEX to deposit the VEX to swap
         // Callback to ref_loss_deposit_callback
-        // If this call fails we can call the function again
-        // as no state is changed
-        ft_contract::ext(self.vex_token_contract.clone())
-            .with_attached_deposit(NearToken::from_yoctonear(1))
-            .with_static_gas(Gas::from_tgas(30))
-            .ft_transfer_call(
-                self.ref_contract.clone(),
-                U128(amount_to_swap),
-                "".to_string(),
-            )
+        self.dex_deposit(self.vex_token_contract.clone(), U128(amount_to_swap))
             .then(
                 Self::ext(env::current_account_id())
-                    .with_static_gas(Gas::from_tgas(200))
-                    .ref_loss_deposit_callback(difference),
+                    .with_static_gas(self.dex_callback_gas(DexCallback::LossDeposit))
+                    .ref_loss_deposit_callback(),
             );
     }
 
@@ -72,87 +141,287 @@ impl Contract {
     pub fn ref_loss_deposit_callback(
         &mut self,
         #[callback_result] call_result: Result<U128, PromiseError>,
-        difference: U128,
     ) {
-        let amount_deposited =
-            call_result.unwrap_or_else(|_| panic!("Deposit to ref finance failed"));
+        let amount_deposited = match call_result {
+            Ok(amount_deposited) if amount_deposited.0 > 0 => amount_deposited,
+            _ => {
+                // The VEX is refunded so the deposit can be resumed
+                self.loss_coverage_failed("Deposit to the DEX failed");
+                return;
+            }
+        };
 
-        // Set the insurance fund to zero as it will all be used
-        self.insurance_fund = U128(0);
+        self.record_loss_deposit(amount_deposited);
+    }
 
-        // Remove the amount of VEX deposited from the total staked balance
-        self.total_staked_balance = U128(self.total_staked_balance.0 - amount_deposited.0);
+    // Callback after getting the contract's deposits in the DEX when resuming a loss coverage
+    // whose last call to the DEX timed out
+    #[private]
+    pub fn ref_loss_coverage_deposits_callback(
+        &mut self,
+        #[callback_result] call_result: Result<HashMap<AccountId, U128>, PromiseError>,
+    ) {
+        let Ok(deposits) = call_result else {
+            self.loss_coverage_failed("Getting deposits from the DEX failed");
+            return;
+        };
 
-        let action = create_swap_args(
-            self.ref_pool_id,
-            self.vex_token_contract.clone(),
-            self.usdc_token_contract.clone(),
-            amount_deposited,
-            U128(0),
-        );
+        // Tokens held in the DEX for a stake swap do not belong to the loss coverage
+        let (swap_usdc, swap_vex) = self.stake_swap_deposits();
+        let deposit_of = |token: &AccountId, reserved: u128| {
+            deposits
+                .get(token)
+                .map_or(0, |amount| amount.0)
+                .saturating_sub(reserved)
+        };
+        let usdc_in_ref = deposit_of(&self.usdc_token_contract, swap_usdc);
+        let vex_in_ref = deposit_of(&self.vex_token_contract, swap_vex);
 
-        // Call to ref finance to swap the deposited VEX for USDC
-        // Callback to ref_loss_swap_callback
-        // If this call fails there will be VEX funds locked in ref finance
-        // also state will have been changed so
-        // we will implement a function to carry on the swap from this point
-        ref_contract::ext(self.ref_contract.clone())
-            .with_attached_deposit(NearToken::from_yoctonear(1))
-            .with_static_gas(Gas::from_tgas(30))
-            .swap(action)
-            .then(
-                Self::ext(env::current_account_id())
-                    .with_static_gas(Gas::from_tgas(150))
-                    .ref_loss_swap_callback(difference),
-            );
+        let loss_coverage = self.loss_coverage.as_mut().unwrap();
+
+        match loss_coverage.step {
+            LossCoverageStep::Deposit if vex_in_ref > 0 => {
+                // The deposit went through but was not recorded
+                log!("The VEX has already been deposited");
+                self.record_loss_deposit(U128(vex_in_ref));
+            }
+            LossCoverageStep::Deposit => {
+                self.loss_coverage_failed("The VEX was not deposited");
+            }
+            LossCoverageStep::Swap if vex_in_ref >= loss_coverage.vex_deposited.0 => {
+                self.swap_vex_to_cover_loss();
+            }
+            LossCoverageStep::Swap => {
+                // The swap went through but was not recorded so the VEX sold is removed
+                // from the stakers and the USDC bought is withdrawn
+                log!("The VEX deposited has already been swapped");
+                let vex_swapped = loss_coverage.vex_deposited;
+                self.remove_vex_for_loss(vex_swapped.0);
+
+                let loss_coverage = self.loss_coverage.as_mut().unwrap();
+                loss_coverage.step = LossCoverageStep::Withdraw;
+                loss_coverage.vex_deposited = U128(0);
+                loss_coverage.usdc_to_withdraw = U128(usdc_in_ref);
+                self.withdraw_usdc_to_cover_loss();
+            }
+            LossCoverageStep::Withdraw if usdc_in_ref == 0 => {
+                // The withdraw went through but was not recorded
+                log!("The USDC has already been withdrawn");
+                let amount_withdrawn = loss_coverage.usdc_to_withdraw;
+                self.complete_loss_coverage(amount_withdrawn);
+            }
+            LossCoverageStep::Withdraw => {
+                // Only withdraw the USDC that is still in the DEX
+                if usdc_in_ref < loss_coverage.usdc_to_withdraw.0 {
+                    log!("Only {} USDC is left to withdraw", usdc_in_ref);
+                    loss_coverage.usdc_to_withdraw = U128(usdc_in_ref);
+                }
+                self.withdraw_usdc_to_cover_loss();
+            }
+        }
+    }
+
+    // Callback after getting the quote for the swap from the DEX
+    #[private]
+    pub fn ref_loss_quote_callback(
+        &mut self,
+        #[callback_result] call_result: Result<U128, PromiseError>,
+    ) {
+        let quote = match call_result {
+            Ok(quote) if quote.0 > 0 => quote,
+            _ => {
+                // Nothing has been swapped so the swap can be resumed
+                self.loss_coverage_failed("Quote from the DEX failed");
+                return;
+            }
+        };
+
+        let amount_deposited = self.loss_coverage.as_ref().unwrap().vex_deposited;
+
+        // A price far from the TWAP may be manipulated so the VEX is not swapped
+        if !self.quote_within_twap(quote.0, amount_deposited.0) {
+            self.loss_coverage_failed("The DEX price is too far from the TWAP");
+            return;
+        }
+
+        // A resumed swap is checked against a new quote as the price may have moved since the deposit
+        let min_usdc_out = min_amount_out(quote, self.config.max_slippage_bps);
+        self.loss_coverage.as_mut().unwrap().min_usdc_out = min_usdc_out;
+
+        self.swap_deposited_vex();
     }
 
-    // Callback after the swap in ref finance
+    // Callback after the swap in the DEX
     #[private]
     pub fn ref_loss_swap_callback(
         &mut self,
         #[callback_result] call_result: Result<U128, PromiseError>,
-        difference: U128,
     ) {
-        let amount_swapped_for =
-            call_result.unwrap_or_else(|_| panic!("Swap in ref finance failed"));
+        let Ok(amount_swapped_for) = call_result else {
+            // The VEX is still deposited in the DEX so the swap can be resumed
+            self.loss_coverage_failed("Swap in the DEX failed");
+            return;
+        };
 
-        // Call to ref finance to withdraw the USDC that was swapped into
-        // Callback to ref_loss_withdraw_callback
-        // If this call fails there will be USDC funds locked in ref finance
-        // we will implement a function to carry on the withdraw from this point
-        ref_contract::ext(self.ref_contract.clone())
-            .with_attached_deposit(NearToken::from_yoctonear(1))
-            .with_static_gas(Gas::from_tgas(30))
-            .withdraw(self.usdc_token_contract.clone(), amount_swapped_for)
-            .then(
-                Self::ext(env::current_account_id())
-                    .with_static_gas(Gas::from_tgas(100))
-                    .ref_loss_withdraw_callback(difference),
-            );
+        let vex_swapped = self.loss_coverage.as_ref().unwrap().vex_deposited;
+        self.record_price(amount_swapped_for.0, vex_swapped.0);
+
+        // The VEX is only removed from the stakers once it has been sold
+        self.remove_vex_for_loss(vex_swapped.0);
+
+        let loss_coverage = self.loss_coverage.as_mut().unwrap();
+        loss_coverage.step = LossCoverageStep::Withdraw;
+        loss_coverage.vex_deposited = U128(0);
+        loss_coverage.usdc_to_withdraw = amount_swapped_for;
+
+        self.withdraw_usdc_to_cover_loss();
     }
 
-    // Callback after withdrawing the USDC from ref finance
+    // Callback after withdrawing the USDC from the DEX
     #[private]
     pub fn ref_loss_withdraw_callback(
         &mut self,
         #[callback_result] call_result: Result<U128, PromiseError>,
-        difference: U128,
     ) {
-        let amount_withdrawn =
-            call_result.unwrap_or_else(|_| panic!("Withdraw from ref finance failed"));
+        let Ok(amount_withdrawn) = call_result else {
+            // The USDC is still in the DEX so the withdraw can be resumed
+            self.loss_coverage_failed("Withdraw from the DEX failed");
+            return;
+        };
+
+        self.complete_loss_coverage(amount_withdrawn);
+    }
+}
+
+impl Contract {
+    // Records the VEX deposited in the DEX then swaps it for USDC with the minimum output
+    // checked before it was deposited
+    fn record_loss_deposit(&mut self, amount_deposited: U128) {
+        let loss_coverage = self.loss_coverage.as_mut().unwrap();
+        loss_coverage.step = LossCoverageStep::Swap;
+        loss_coverage.vex_deposited = amount_deposited;
+
+        self.swap_deposited_vex();
+    }
+
+    // Finishes covering the loss with the USDC withdrawn from the DEX
+    fn complete_loss_coverage(&mut self, amount_withdrawn: U128) {
+        let loss_coverage = self.loss_coverage.take().unwrap();
+        let difference = loss_coverage.difference;
 
         // Check if the amount received is indeed greater than
         // the difference that is needed to be covered
         if amount_withdrawn >= difference {
             // If we have excess USDC then we can add it to the insurance fund
             let excess = U128(amount_withdrawn.0 - difference.0);
-            self.insurance_fund = excess;
+            self.insurance_fund = U128(self.insurance_fund.0 + excess.0);
         } else {
             // In the very rare case that the amount received is less than the difference
             // we will log this and add to the state some amount needs to be added
             log!("URGENT: Need to add funds to the contract!");
             self.funds_to_add = U128(self.funds_to_add.0 + difference.0 - amount_withdrawn.0);
         }
+
+        // Losses that happened while this one was being covered are recorded but there is not
+        // enough gas left to call the DEX, they are deposited with the next loss or when resumed
+        if loss_coverage.queued_loss.0 > 0 {
+            let queued_loss = loss_coverage.queued_loss.0;
+
+            if queued_loss < self.insurance_fund.0 {
+                self.insurance_fund = U128(self.insurance_fund.0 - queued_loss);
+            } else {
+                self.loss_coverage = Some(LossCoverage {
+                    step: LossCoverageStep::Deposit,
+                    difference: U128(queued_loss - self.insurance_fund.0),
+                    vex_deposited: U128(0),
+                    min_usdc_out: U128(0),
+                    usdc_to_withdraw: U128(0),
+                    in_flight: false,
+                    last_call_timestamp: U64(0),
+                    queued_loss: U128(0),
+                });
+                self.insurance_fund = U128(0);
+            }
+        }
+    }
+
+    // Gets the amount of VEX needed to cover the loss then deposits it in the DEX
+    fn deposit_vex_to_cover_loss(&mut self) -> Promise {
+        let difference = self.start_loss_coverage_call().difference;
+
+        // Call to the DEX to get the VEX needed for the difference
+        // Callback to ref_loss_view_callback
+        // If this call fails we can call the function again
+        // as no state is changed
+        self.dex_quote(DexQuote::LossDeposit, 0, difference)
+    }
+
+    // Swaps the VEX deposited in the DEX for at least the minimum USDC
+    fn swap_deposited_vex(&mut self) -> Promise {
+        let loss_coverage = self.start_loss_coverage_call();
+        let amount_deposited = loss_coverage.vex_deposited;
+        let min_usdc_out = loss_coverage.min_usdc_out;
+
+        // Call to the DEX to swap the deposited VEX for USDC
+        // Callback to ref_loss_swap_callback
+        // If this call fails or the output is below the minimum the VEX stays in the DEX
+        // and the swap can be resumed with a new quote
+        self.dex_swap(&self.vex_token_contract, amount_deposited, min_usdc_out)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(self.dex_callback_gas(DexCallback::LossSwap))
+                    .ref_loss_swap_callback(),
+            )
+    }
+
+    // Gets a quote for the VEX deposited in the DEX then swaps it for USDC when a swap is resumed
+    fn swap_vex_to_cover_loss(&mut self) -> Promise {
+        let amount_deposited = self.start_loss_coverage_call().vex_deposited;
+
+        // Call to the DEX to get the USDC the deposited VEX would swap for
+        // Callback to ref_loss_quote_callback
+        self.dex_quote(DexQuote::LossSwap, 0, amount_deposited)
+    }
+
+    // Withdraws the USDC that was swapped into from the DEX
+    fn withdraw_usdc_to_cover_loss(&mut self) -> Promise {
+        let amount_swapped_for = self.start_loss_coverage_call().usdc_to_withdraw;
+
+        // Call to the DEX to withdraw the USDC that was swapped into
+        // Callback to ref_loss_withdraw_callback
+        // If this call fails the USDC stays in the DEX and the withdraw can be resumed
+        self.dex_withdraw(self.usdc_token_contract.clone(), amount_swapped_for)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(self.dex_callback_gas(DexCallback::LossWithdraw))
+                    .ref_loss_withdraw_callback(),
+            )
+    }
+
+    // Marks that a call to the DEX is in progress for the loss being covered
+    fn start_loss_coverage_call(&mut self) -> &LossCoverage {
+        let loss_coverage = self
+            .loss_coverage
+            .as_mut()
+            .unwrap_or_else(|| panic!("No loss is being covered"));
+        loss_coverage.in_flight = true;
+        loss_coverage.last_call_timestamp = U64(env::block_timestamp());
+        loss_coverage
+    }
+
+    // The USDC and VEX held in the DEX for the stake swap in progress
+    fn stake_swap_deposits(&self) -> (u128, u128) {
+        match &self.stake_swap {
+            Some(stake_swap) => (stake_swap.usdc_deposited.0, stake_swap.vex_to_withdraw.0),
+            None => (0, 0),
+        }
+    }
+
+    // Records that the current step failed so it can be resumed
+    pub(crate) fn loss_coverage_failed(&mut self, reason: &str) {
+        log!("{}, the loss coverage can be resumed", reason);
+        if let Some(loss_coverage) = self.loss_coverage.as_mut() {
+            loss_coverage.in_flight = false;
+        }
     }
 }
@@ -0,0 +1,237 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{AccountId, PromiseError};
+use std::collections::HashMap;
+
+use crate::dex::adapter::min_amount_out;
+use crate::test_utils::*;
+use crate::*;
+
+// The contract's deposits in the DEX
+fn deposits(usdc_amount: u128, vex_amount: u128) -> HashMap<AccountId, U128> {
+    HashMap::from([(usdc(), U128(usdc_amount)), (vex(), U128(vex_amount))])
+}
+
+fn setup() -> Contract {
+    let mut contract = init_contract(100);
+    contract.insurance_fund = U128(10 * ONE_USDC);
+    contract
+}
+
+fn loss_coverage(contract: &Contract) -> &LossCoverage {
+    contract.loss_coverage.as_ref().unwrap()
+}
+
+#[test]
+fn test_loss_covered_by_insurance_fund() {
+    let mut contract = setup();
+
+    new_call(0);
+    let _ = contract.handle_loss(4 * ONE_USDC);
+
+    assert_eq!(contract.insurance_fund, U128(6 * ONE_USDC));
+    assert!(contract.loss_coverage.is_none());
+}
+
+#[test]
+fn test_resume_loss_coverage() {
+    let mut contract = setup();
+    let total_staked_balance = contract.total_staked_balance.0;
+
+    // 50 VEX are worth 16 USDC over a TWAP window of 100 nanoseconds
+    contract.config.twap_window = U64(100);
+    contract.record_price(16 * ONE_USDC, FIFTY_VEX);
+
+    new_call(0);
+    let _ = contract.handle_loss(25 * ONE_USDC);
+
+    assert_eq!(contract.insurance_fund, U128(0));
+    assert_eq!(loss_coverage(&contract).difference, U128(15 * ONE_USDC));
+    assert_eq!(loss_coverage(&contract).step, LossCoverageStep::Deposit);
+    assert!(loss_coverage(&contract).in_flight);
+
+    // The view fails so the deposit is resumed
+    new_call(0);
+    contract.ref_loss_view_callback(Err(PromiseError::Failed));
+    assert!(!loss_coverage(&contract).in_flight);
+
+    // The price is too far from the TWAP so nothing is deposited
+    new_call(100);
+    contract.resume_loss_coverage();
+    new_call(100);
+    contract.ref_loss_view_callback(Ok(U128(FIFTY_VEX / 2)));
+    assert_eq!(loss_coverage(&contract).step, LossCoverageStep::Deposit);
+    assert!(!loss_coverage(&contract).in_flight);
+
+    // Once the observations cover the TWAP window the minimum output is set before the deposit
+    new_call(100);
+    contract.resume_loss_coverage();
+    assert!(loss_coverage(&contract).in_flight);
+
+    new_call(100);
+    contract.ref_loss_view_callback(Ok(U128(FIFTY_VEX * 15 / 16)));
+    assert_eq!(
+        loss_coverage(&contract).min_usdc_out,
+        min_amount_out(U128(15 * ONE_USDC), DEFAULT_MAX_SLIPPAGE_BPS)
+    );
+
+    // The VEX deposited is swapped straight away and is not removed from the stakers until it is sold
+    new_call(100);
+    contract.ref_loss_deposit_callback(Ok(U128(FIFTY_VEX)));
+    assert_eq!(loss_coverage(&contract).step, LossCoverageStep::Swap);
+    assert_eq!(loss_coverage(&contract).vex_deposited, U128(FIFTY_VEX));
+    assert_eq!(contract.total_staked_balance.0, total_staked_balance);
+    assert!(loss_coverage(&contract).in_flight);
+
+    // The swap fails so the VEX stays in the DEX until the swap is resumed
+    new_call(100);
+    contract.ref_loss_swap_callback(Err(PromiseError::Failed));
+    assert_eq!(loss_coverage(&contract).step, LossCoverageStep::Swap);
+    assert!(!loss_coverage(&contract).in_flight);
+    assert_eq!(contract.total_staked_balance.0, total_staked_balance);
+
+    // The quote of the resumed swap fails so nothing is swapped
+    new_call(100);
+    contract.resume_loss_coverage();
+    new_call(100);
+    contract.ref_loss_quote_callback(Ok(U128(0)));
+    assert_eq!(loss_coverage(&contract).step, LossCoverageStep::Swap);
+    assert!(!loss_coverage(&contract).in_flight);
+
+    new_call(100);
+    contract.resume_loss_coverage();
+    new_call(100);
+    contract.ref_loss_quote_callback(Ok(U128(16 * ONE_USDC)));
+    assert!(loss_coverage(&contract).in_flight);
+    assert_eq!(
+        loss_coverage(&contract).min_usdc_out,
+        min_amount_out(U128(16 * ONE_USDC), DEFAULT_MAX_SLIPPAGE_BPS)
+    );
+
+    new_call(100);
+    contract.ref_loss_swap_callback(Ok(U128(16 * ONE_USDC)));
+    assert_eq!(
+        contract.total_staked_balance,
+        U128(total_staked_balance - FIFTY_VEX)
+    );
+    assert_eq!(loss_coverage(&contract).step, LossCoverageStep::Withdraw);
+    assert_eq!(
+        loss_coverage(&contract).usdc_to_withdraw,
+        U128(16 * ONE_USDC)
+    );
+
+    // The withdraw fails so the USDC stays in the DEX until the withdraw is resumed
+    new_call(100);
+    contract.ref_loss_withdraw_callback(Err(PromiseError::Failed));
+    assert_eq!(loss_coverage(&contract).step, LossCoverageStep::Withdraw);
+
+    new_call(100);
+    contract.resume_loss_coverage();
+    new_call(100);
+    contract.ref_loss_withdraw_callback(Ok(U128(16 * ONE_USDC)));
+
+    // The excess is added to the insurance fund
+    assert!(contract.loss_coverage.is_none());
+    assert_eq!(contract.insurance_fund, U128(ONE_USDC));
+    assert_eq!(
+        contract.total_staked_balance.0,
+        total_staked_balance - FIFTY_VEX
+    );
+}
+
+#[test]
+#[should_panic(expected = "A call to the DEX is still in progress")]
+fn test_resume_while_in_flight() {
+    let mut contract = setup();
+
+    new_call(0);
+    let _ = contract.handle_loss(25 * ONE_USDC);
+    new_call(0);
+    contract.resume_loss_coverage();
+}
+
+#[test]
+fn test_resume_after_timeout() {
+    let mut contract = setup();
+    let total_staked_balance = contract.total_staked_balance.0;
+
+    new_call(0);
+    let _ = contract.handle_loss(25 * ONE_USDC);
+
+    // The deposit went through but its callback never ran
+    new_call(DEX_CALL_TIMEOUT);
+    contract.resume_loss_coverage();
+    assert!(loss_coverage(&contract).in_flight);
+
+    new_call(DEX_CALL_TIMEOUT);
+    contract.ref_loss_coverage_deposits_callback(Ok(deposits(0, FIFTY_VEX)));
+    assert_eq!(loss_coverage(&contract).step, LossCoverageStep::Swap);
+    assert_eq!(loss_coverage(&contract).vex_deposited, U128(FIFTY_VEX));
+    assert_eq!(contract.total_staked_balance.0, total_staked_balance);
+
+    // The swap went through but its callback never ran so the VEX sold is removed from the stakers
+    new_call(2 * DEX_CALL_TIMEOUT);
+    contract.resume_loss_coverage();
+    new_call(2 * DEX_CALL_TIMEOUT);
+    contract.ref_loss_coverage_deposits_callback(Ok(deposits(16 * ONE_USDC, 0)));
+    assert_eq!(
+        contract.total_staked_balance,
+        U128(total_staked_balance - FIFTY_VEX)
+    );
+    assert_eq!(loss_coverage(&contract).step, LossCoverageStep::Withdraw);
+    assert_eq!(
+        loss_coverage(&contract).usdc_to_withdraw,
+        U128(16 * ONE_USDC)
+    );
+
+    // The withdraw went through but its callback never ran
+    new_call(3 * DEX_CALL_TIMEOUT);
+    contract.resume_loss_coverage();
+    new_call(3 * DEX_CALL_TIMEOUT);
+    contract.ref_loss_coverage_deposits_callback(Ok(deposits(0, 0)));
+    assert!(contract.loss_coverage.is_none());
+    assert_eq!(contract.insurance_fund, U128(ONE_USDC));
+}
+
+#[test]
+fn test_loss_queued_while_covering() {
+    let mut contract = setup();
+
+    new_call(0);
+    let _ = contract.handle_loss(25 * ONE_USDC);
+
+    // A loss made while another is being covered is queued
+    new_call(0);
+    let _ = contract.handle_loss(3 * ONE_USDC);
+    assert_eq!(loss_coverage(&contract).queued_loss, U128(3 * ONE_USDC));
+    assert_eq!(loss_coverage(&contract).difference, U128(15 * ONE_USDC));
+
+    new_call(0);
+    contract.ref_loss_deposit_callback(Ok(U128(FIFTY_VEX)));
+    new_call(0);
+    contract.ref_loss_swap_callback(Ok(U128(16 * ONE_USDC)));
+    new_call(0);
+    contract.ref_loss_withdraw_callback(Ok(U128(16 * ONE_USDC)));
+
+    // The excess of one USDC is used so the rest must be covered with the DEX
+    assert_eq!(contract.insurance_fund, U128(0));
+    assert_eq!(loss_coverage(&contract).difference, U128(2 * ONE_USDC));
+    assert_eq!(loss_coverage(&contract).step, LossCoverageStep::Deposit);
+    assert!(!loss_coverage(&contract).in_flight);
+
+    // The queued loss is started together with the next loss
+    new_call(0);
+    let _ = contract.handle_loss(ONE_USDC);
+    assert_eq!(loss_coverage(&contract).difference, U128(3 * ONE_USDC));
+    assert!(loss_coverage(&contract).in_flight);
+}
+
+#[test]
+fn test_min_amount_out() {
+    // The default max slippage of 1% is allowed below the quote
+    assert_eq!(
+        min_amount_out(U128(16 * ONE_USDC), DEFAULT_MAX_SLIPPAGE_BPS),
+        U128(15_840_000)
+    );
+    assert_eq!(min_amount_out(U128(16 * ONE_USDC), 0), U128(16 * ONE_USDC));
+    assert_eq!(min_amount_out(U128(99), DEFAULT_MAX_SLIPPAGE_BPS), U128(98));
+}
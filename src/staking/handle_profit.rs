@@ -8,9 +8,9 @@ impl Contract {
     // Handles the case when a match finishes and there is a profit
     pub(crate) fn handle_profit(&mut self, profit: u128) -> PromiseOrValue<()> {
         // Calculate how profit is distributed
-        let usdc_for_staking = (U256::from(60) * U256::from(profit) / U256::from(100)).as_u128();
-        let treasury_rewards = (U256::from(30) * U256::from(profit) / U256::from(100)).as_u128();
-        let insurace_rewards = (U256::from(5) * U256::from(profit) / U256::from(100)).as_u128();
+        let usdc_for_staking = share_of(profit, self.config.staking_share_bps);
+        let treasury_rewards = share_of(profit, self.config.treasury_share_bps);
+        let insurace_rewards = share_of(profit, self.config.insurance_share_bps);
         let fees_rewards = profit - usdc_for_staking - treasury_rewards - insurace_rewards;
 
         // Increase fees and insurance funds
@@ -20,7 +20,7 @@ impl Contract {
         // Send funds to treasury
         ft_contract::ext(self.usdc_token_contract.clone())
             .with_attached_deposit(NearToken::from_yoctonear(1))
-            .with_static_gas(Gas::from_tgas(30))
+            .with_static_gas(Gas::from_tgas(DEX_CALL_GAS))
             .ft_transfer(self.treasury.clone(), U128(treasury_rewards));
 
         // Peform stake swap so rewards are distributed at the timestamp of the
@@ -30,3 +30,8 @@ impl Contract {
         self.perform_stake_swap_internal(U128(usdc_for_staking))
     }
 }
+
+// Calculates a share in basis points of an amount, rounded down
+pub fn share_of(amount: u128, share_bps: u32) -> u128 {
+    (U256::from(amount) * U256::from(share_bps) / U256::from(BASIS_POINTS)).as_u128()
+}
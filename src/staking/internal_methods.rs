@@ -6,32 +6,22 @@ use crate::*;
 #[near]
 impl Contract {
     // Staking VEX tokens
+    // Returns the amount of VEX to refund to the staker
     pub(crate) fn stake(&mut self, sender_id: AccountId, amount: U128) -> U128 {
-        require!(
-            env::predecessor_account_id() == self.vex_token_contract,
-            "Only VEX can be staked"
-        );
-
-        // Get the rounded down number of stake shares
-        let num_shares = self.num_shares_from_staked_amount_rounded_down(amount.0);
-
-        // Get the amount of VEX for the rounded down stake shares
-        let charge_amount = self.staked_amount_from_num_shares_rounded_down(num_shares);
-        require!(
-            charge_amount > 0,
-            "Invariant violation. Calculated staked amount must be positive, because \"stake\" share price should be at least 1"
-        );
-
-        // Check if the user's staked VEX + the amount they are staking is at least 50
-        let stake_shares = self
-            .users_stake
-            .get(&sender_id)
-            .map_or(0, |account| account.stake_shares.0);
-        let staked_balance = self.staked_amount_from_num_shares_rounded_down(stake_shares);
-        require!(
-            staked_balance + amount.0 >= FIFTY_VEX,
-            "You must stake at least 50 VEX"
-        );
+        // Validate the stake, if it is rejected the full amount is refunded
+        let num_shares = match self.validate_stake(&sender_id, amount) {
+            Ok(num_shares) => num_shares,
+            Err(reason) => {
+                Event::StakeRejected {
+                    account_id: &sender_id,
+                    amount,
+                    reason,
+                }
+                .emit();
+
+                return amount;
+            }
+        };
 
         // Get the user's stake account or create a new one if it doesn't exist
         let relevant_account = self
@@ -40,7 +30,8 @@ impl Contract {
             .or_insert_with(UserStake::default);
 
         // Set the unstake timestamp to 1 week from now
-        relevant_account.unstake_timestamp = U64(env::block_timestamp() + self.unstake_time_buffer);
+        relevant_account.unstake_timestamp =
+            U64(env::block_timestamp() + self.config.unstake_time_buffer.0);
 
         // Update the user's staked shares balance
         relevant_account.stake_shares = U128(relevant_account.stake_shares.0 + num_shares);
@@ -65,11 +56,19 @@ impl Contract {
     }
 
     // Add USDC to the contract
-    pub(crate) fn add_usdc(&mut self, amount: U128) -> U128 {
-        require!(
-            env::predecessor_account_id() == self.usdc_token_contract,
-            "Only USDC can be added"
-        );
+    // Returns the amount of tokens to refund to the sender
+    pub(crate) fn add_usdc(&mut self, sender_id: AccountId, amount: U128) -> U128 {
+        if env::predecessor_account_id() != self.usdc_token_contract {
+            Event::TransferRejected {
+                account_id: &sender_id,
+                token_id: &env::predecessor_account_id(),
+                amount,
+                reason: RejectionReason::WrongToken,
+            }
+            .emit();
+
+            return amount;
+        }
 
         // First check if USDC needs to be added to funds_to_add
         // send the rest to the insurance fund
@@ -89,6 +88,44 @@ impl Contract {
         U128(0)
     }
 
+    // Checks VEX can be staked and returns the number of stake shares it is worth
+    // or the reason the stake is rejected
+    pub(crate) fn validate_stake(
+        &self,
+        sender_id: &AccountId,
+        amount: U128,
+    ) -> Result<u128, RejectionReason> {
+        if env::predecessor_account_id() != self.vex_token_contract {
+            return Err(RejectionReason::WrongToken);
+        }
+
+        if self.pause_state.staking {
+            return Err(RejectionReason::Paused);
+        }
+
+        // Get the rounded down number of stake shares
+        let num_shares = self.num_shares_from_staked_amount_rounded_down(amount.0);
+
+        // Get the amount of VEX for the rounded down stake shares
+        // this is zero if the amount is too small to be worth a single stake share
+        let charge_amount = self.staked_amount_from_num_shares_rounded_down(num_shares);
+        if charge_amount == 0 {
+            return Err(RejectionReason::StakeTooSmall);
+        }
+
+        // Check if the user's staked VEX + the amount they are staking is at least the minimum stake
+        let stake_shares = self
+            .users_stake
+            .get(sender_id)
+            .map_or(0, |account| account.stake_shares.0);
+        let staked_balance = self.staked_amount_from_num_shares_rounded_down(stake_shares);
+        if staked_balance + amount.0 < self.config.min_stake.0 {
+            return Err(RejectionReason::StakeTooSmall);
+        }
+
+        Ok(num_shares)
+    }
+
     // Helper function to calculate the number of stake shares from a staked amount
     // rounded down
     pub(crate) fn num_shares_from_staked_amount_rounded_down(&self, amount: u128) -> u128 {
@@ -139,4 +176,45 @@ impl Contract {
             / U256::from(self.total_stake_shares.0))
         .as_u128()
     }
+
+    // Helper function to calculate the number of shares of the VEX in withdrawal tickets from an amount
+    // rounded down
+    pub(crate) fn unstaking_shares_from_amount(&self, amount: u128) -> u128 {
+        if self.total_unstaking_shares.0 == 0 {
+            return amount;
+        }
+
+        require!(
+            self.total_unstaking_balance.0 > 0,
+            "The total unstaking balance can't be 0"
+        );
+
+        (U256::from(self.total_unstaking_shares.0) * U256::from(amount)
+            / U256::from(self.total_unstaking_balance.0))
+        .as_u128()
+    }
+
+    // Helper function to calculate the amount of VEX in withdrawal tickets from a number of shares
+    // rounded down
+    pub(crate) fn unstaking_amount_from_shares(&self, num_shares: u128) -> u128 {
+        if self.total_unstaking_shares.0 == 0 {
+            return 0;
+        }
+
+        (U256::from(self.total_unstaking_balance.0) * U256::from(num_shares)
+            / U256::from(self.total_unstaking_shares.0))
+        .as_u128()
+    }
+
+    // Removes VEX sold to cover a loss from the staked balance and the withdrawal tickets
+    // in proportion to their size so unstaking does not avoid a loss
+    pub(crate) fn remove_vex_for_loss(&mut self, amount: u128) {
+        let total_balance = self.total_staked_balance.0 + self.total_unstaking_balance.0;
+        let from_unstaking = (U256::from(amount) * U256::from(self.total_unstaking_balance.0)
+            / U256::from(total_balance))
+        .as_u128();
+
+        self.total_unstaking_balance = U128(self.total_unstaking_balance.0 - from_unstaking);
+        self.total_staked_balance = U128(self.total_staked_balance.0 - (amount - from_unstaking));
+    }
 }
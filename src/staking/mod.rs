@@ -4,3 +4,10 @@ pub mod internal_methods;
 pub mod stake_swap;
 pub mod staking;
 pub mod view_staking;
+
+#[cfg(test)]
+mod handle_loss_tests;
+#[cfg(test)]
+mod stake_swap_tests;
+#[cfg(test)]
+mod staking_tests;
@@ -1,6 +1,11 @@
-use near_sdk::{env, near, require, Gas, NearToken, PromiseError, PromiseOrValue};
-
-pub use crate::ext::*;
+use near_sdk::{env, log, near, require, Gas, NearToken, Promise, PromiseError, PromiseOrValue};
+use std::collections::HashMap;
+
+use crate::dex::adapter::{min_amount_out, DexQuote};
+use crate::dex::gas::{resume_stake_swap_gas, stake_swap_gas, DexCallback};
+use crate::events::Event;
+use crate::ext::ft_contract;
+use crate::staking::handle_profit::share_of;
 use crate::*;
 
 #[near]
@@ -8,9 +13,18 @@ impl Contract {
     // Swap the USDC staking rewards for VEX
     // In the frontend this is only calable by those who are registered in the VEX token contract
     pub fn perform_stake_swap(&mut self) -> PromiseOrValue<()> {
+        self.require_dex_gas(stake_swap_gas);
+
+        require!(!self.pause_state.stake_swaps, "Stake swaps are paused");
+
+        require!(
+            self.stake_swap.is_none(),
+            "A stake swap is already in progress"
+        );
+
         require!(
-            env::prepaid_gas() >= Gas::from_tgas(300),
-            "You need to attach 300 TGas"
+            self.stake_swap_interval_passed(),
+            "The minimum interval between stake swaps has not passed"
         );
 
         self.perform_stake_swap_internal(U128(0))
@@ -20,199 +34,296 @@ impl Contract {
         &mut self,
         extra_usdc_for_staking: U128,
     ) -> PromiseOrValue<()> {
+        // If stake swaps are paused then the new rewards are distributed from now
+        // and swapped once they are unpaused
+        if self.pause_state.stake_swaps {
+            self.add_staking_rewards(extra_usdc_for_staking);
+            return PromiseOrValue::Value(());
+        }
+
+        // If a stake swap is in progress then the new rewards are distributed from now
+        // and swapped in the next stake swap
+        if self.stake_swap.is_some() {
+            self.add_staking_rewards(extra_usdc_for_staking);
+            return PromiseOrValue::Value(());
+        }
+
         let previous_timestamp = self.last_stake_swap_timestamp;
 
-        // If the staking queue is empty then skip and update the last stake swap timestamp
-        if self.staking_rewards_queue.is_empty() {
+        // If the staking queue is empty and nothing was carried over
+        // then skip and update the last stake swap timestamp
+        if self.staking_rewards_queue.is_empty() && self.pending_stake_swap_usdc.0 == 0 {
             self.last_stake_swap_timestamp = U64(env::block_timestamp());
+            self.add_staking_rewards(extra_usdc_for_staking);
 
-            if extra_usdc_for_staking.0 > 0 {
-                let new_match_stake_info = MatchStakeInfo {
-                    staking_rewards: extra_usdc_for_staking,
-                    stake_end_time: U64(env::block_timestamp() + self.rewards_period),
-                };
-
-                self.staking_rewards_queue.push_back(new_match_stake_info);
-                self.usdc_staking_rewards =
-                    U128(self.usdc_staking_rewards.0 + extra_usdc_for_staking.0);
-            }
+            return PromiseOrValue::Value(());
+        }
 
+        // If the last stake swap was too recent then the new rewards are distributed from now
+        // and swapped in a later stake swap
+        if !self.stake_swap_interval_passed() {
+            self.add_staking_rewards(extra_usdc_for_staking);
             return PromiseOrValue::Value(());
         }
 
-        // Get time passed since last stake swap
-        let time_passed = env::block_timestamp() - self.last_stake_swap_timestamp.0;
-        // Check if the first item in staking rewards queue has expired
-        // repeat until the first item in the queue has not expired
-        // if expired remove and calculate rewards
-        // save matches to remove and rewards to remove for later use in callback
-        let mut finished_matches_rewards: u128 = 0;
-        let mut new_usdc_staking_rewards = self.usdc_staking_rewards.0;
-        let mut num_to_pop: u16 = 0;
-        for i in self.staking_rewards_queue.iter() {
-            if i.stake_end_time.0 < env::block_timestamp() {
-                let finished_match_time_passed =
-                    i.stake_end_time.0 - self.last_stake_swap_timestamp.0;
+        let (distributed_rewards, expired_rewards, num_to_pop) = self.distributed_staking_rewards();
 
-                // Get rewards for this match has passed
-                let passed_match_reward = (U256::from(finished_match_time_passed)
-                    * U256::from(i.staking_rewards.0)
-                    / U256::from(self.rewards_period))
-                .as_u128();
+        // The USDC carried over from the last stake swap is swapped first
+        let total_rewards_to_swap = self.pending_stake_swap_usdc.0 + distributed_rewards;
 
-                finished_matches_rewards += passed_match_reward;
-                new_usdc_staking_rewards -= i.staking_rewards.0;
-                num_to_pop += 1;
-            } else {
-                break;
-            }
-        }
+        require!(
+            total_rewards_to_swap > self.config.min_swap_amount.0,
+            "Rewards to swap must be greater than the minimum swap amount"
+        );
 
-        // Calculate the rewards for the matches that have not expired
-        let active_match_rewards = (U256::from(time_passed) * U256::from(new_usdc_staking_rewards)
-            / U256::from(self.rewards_period))
-        .as_u128();
+        // In tranche mode only the max stake swap amount is swapped and the rest is carried over
+        let rewards_to_swap = match self.config.max_stake_swap_amount.0 {
+            0 => total_rewards_to_swap,
+            max_stake_swap_amount => total_rewards_to_swap.min(max_stake_swap_amount),
+        };
+        let usdc_carried_over = total_rewards_to_swap - rewards_to_swap;
+
+        self.last_stake_swap_timestamp = U64(env::block_timestamp());
+
+        // The new rewards are added after the rewards to swap are calculated
+        // so they are distributed from now
+        self.add_staking_rewards(extra_usdc_for_staking);
+
+        // Record the stake swap so a second one cannot start until it completes
+        // the queue is only updated once the deposit succeeds
+        self.stake_swap = Some(StakeSwap {
+            step: StakeSwapStep::Deposit,
+            usdc_to_deposit: U128(rewards_to_swap),
+            usdc_deposited: U128(0),
+            min_vex_out: U128(0),
+            vex_to_withdraw: U128(0),
+            caller: env::predecessor_account_id(),
+            in_flight: true,
+            last_call_timestamp: U64(env::block_timestamp()),
+            previous_timestamp,
+            num_to_pop,
+            expired_rewards: U128(expired_rewards),
+            usdc_carried_over: U128(usdc_carried_over),
+        });
+
+        // Call to the DEX to get the VEX the USDC rewards would swap for before they are deposited
+        // Callback to ref_profit_quote_callback
+        // If the quote fails or is too far from the TWAP the stake swap is reverted
+        PromiseOrValue::Promise(self.dex_quote(DexQuote::StakeSwap, 0, U128(rewards_to_swap)))
+    }
+
+    // Continues a stake swap after a call to the DEX failed or its callback has not run
+    // after DEX_CALL_TIMEOUT, the deposits in the DEX are checked first so nothing
+    // is swapped or withdrawn twice
+    pub fn resume_stake_swap(&mut self) -> Promise {
+        self.assert_role(Role::Keeper);
 
-        let total_rewards_to_swap = finished_matches_rewards + active_match_rewards;
+        self.require_dex_gas(resume_stake_swap_gas);
+
+        let stake_swap = self
+            .stake_swap
+            .as_ref()
+            .unwrap_or_else(|| panic!("No stake swap is in progress"));
 
         require!(
-            total_rewards_to_swap > self.min_swap_amount,
-            "Rewards to swap must be greather than 100"
+            !stake_swap.in_flight
+                || env::block_timestamp() >= stake_swap.last_call_timestamp.0 + DEX_CALL_TIMEOUT,
+            "A call to the DEX is still in progress"
         );
 
-        self.last_stake_swap_timestamp = U64(env::block_timestamp());
+        let step = self.start_stake_swap_call().step.clone();
 
-        let caller = env::predecessor_account_id();
+        Event::ResumeStakeSwap {
+            account_id: &env::predecessor_account_id(),
+            step,
+        }
+        .emit();
 
-        // Call to ref finance to deposit the USDC rewards
-        // Callback to ref_profit_deposit_callback
-        // If this function fails we can call the function again
-        // as state is reversed in the callback
-        PromiseOrValue::Promise(
-            ft_contract::ext(self.usdc_token_contract.clone())
-                .with_attached_deposit(NearToken::from_yoctonear(1))
-                .with_static_gas(Gas::from_tgas(30))
-                .ft_transfer_call(
-                    self.ref_contract.clone(),
-                    U128(total_rewards_to_swap),
-                    "".to_string(),
-                )
-                .then(
-                    Self::ext(env::current_account_id())
-                        .with_static_gas(Gas::from_tgas(220))
-                        .ref_profit_deposit_callback(
-                            num_to_pop, // May need to pop before so it cannot be called again or set something in state so it can't be called
-                            U128(new_usdc_staking_rewards),
-                            previous_timestamp,
-                            caller,
-                            extra_usdc_for_staking,
-                        ),
-                ),
+        self.dex_get_deposits().then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(self.dex_callback_gas(DexCallback::StakeSwapDeposits))
+                .ref_stake_swap_deposits_callback(),
         )
     }
 
-    // Callback after the deposit to ref finance
+    // Callback after the deposit to the DEX
     #[private]
     pub fn ref_profit_deposit_callback(
         &mut self,
         #[callback_result] call_result: Result<U128, PromiseError>,
-        num_to_pop: u16,
-        new_usdc_staking_rewards: U128,
-        previous_timestamp: U64,
-        caller: AccountId,
-        extra_usdc_for_staking: U128,
     ) {
-        // If the call to ref finance failed then revert the state
-        if call_result.is_err() {
-            self.last_stake_swap_timestamp = previous_timestamp;
-            return;
+        match call_result {
+            Ok(amount_deposited) if amount_deposited.0 > 0 => {
+                self.record_stake_swap_deposit(amount_deposited)
+            }
+            // If the call to the DEX failed then revert the state
+            _ => self.revert_stake_swap_deposit(),
         }
+    }
 
-        let amount_deposited = call_result.unwrap();
-
-        // Set the new staking rewards since some matches have expired and we may have added extra from handle_profit
-        self.usdc_staking_rewards = U128(new_usdc_staking_rewards.0 + extra_usdc_for_staking.0);
+    // Callback after getting the contract's deposits in the DEX when resuming a stake swap
+    #[private]
+    pub fn ref_stake_swap_deposits_callback(
+        &mut self,
+        #[callback_result] call_result: Result<HashMap<AccountId, U128>, PromiseError>,
+    ) {
+        let Ok(deposits) = call_result else {
+            self.stake_swap_failed("Getting deposits from the DEX failed");
+            return;
+        };
+
+        // Tokens held in the DEX for a loss being covered do not belong to the stake swap
+        let (loss_vex, loss_usdc) = self.loss_coverage_deposits();
+        let deposit_of = |token: &AccountId, reserved: u128| {
+            deposits
+                .get(token)
+                .map_or(0, |amount| amount.0)
+                .saturating_sub(reserved)
+        };
+        let usdc_in_ref = deposit_of(&self.usdc_token_contract, loss_usdc);
+        let vex_in_ref = deposit_of(&self.vex_token_contract, loss_vex);
+
+        let stake_swap = self.stake_swap.as_mut().unwrap();
+
+        match stake_swap.step {
+            StakeSwapStep::Deposit if usdc_in_ref > 0 => {
+                // The deposit went through but was not recorded
+                log!("The USDC has already been deposited");
+                self.record_stake_swap_deposit(U128(usdc_in_ref));
+            }
+            StakeSwapStep::Deposit => {
+                log!("The USDC was not deposited");
+                self.revert_stake_swap_deposit();
+            }
+            StakeSwapStep::Swap if usdc_in_ref >= stake_swap.usdc_deposited.0 => {
+                self.swap_usdc_for_stake_swap();
+            }
+            StakeSwapStep::Swap => {
+                // The swap went through but was not recorded so the VEX bought is withdrawn
+                log!("The USDC deposited has already been swapped");
+                stake_swap.step = StakeSwapStep::Withdraw;
+                stake_swap.usdc_deposited = U128(0);
+                stake_swap.vex_to_withdraw = U128(vex_in_ref);
+                self.withdraw_vex_for_stake_swap();
+            }
+            StakeSwapStep::Withdraw if vex_in_ref == 0 => {
+                // The withdraw went through but was not recorded
+                log!("The VEX has already been withdrawn");
+                let amount_withdrawn = stake_swap.vex_to_withdraw;
+                self.complete_stake_swap(amount_withdrawn);
+            }
+            StakeSwapStep::Withdraw => {
+                // Only withdraw the VEX that is still in the DEX
+                if vex_in_ref < stake_swap.vex_to_withdraw.0 {
+                    log!("Only {} VEX is left to withdraw", vex_in_ref);
+                    stake_swap.vex_to_withdraw = U128(vex_in_ref);
+                }
+                self.withdraw_vex_for_stake_swap();
+            }
+        }
+    }
 
-        // Remove the finished matches from the queue
-        for _ in 0..num_to_pop {
-            self.staking_rewards_queue.pop_front();
+    // Callback after getting the quote for the swap from the DEX, before the USDC is deposited
+    // or when a swap is resumed
+    #[private]
+    pub fn ref_profit_quote_callback(
+        &mut self,
+        #[callback_result] call_result: Result<U128, PromiseError>,
+    ) {
+        let quote = match call_result {
+            Ok(quote) if quote.0 > 0 => quote,
+            _ => {
+                // Nothing has been deposited or swapped
+                self.stake_swap_quote_failed("Quote from the DEX failed");
+                return;
+            }
+        };
+
+        let stake_swap = self.stake_swap.as_ref().unwrap();
+        let depositing = stake_swap.step == StakeSwapStep::Deposit;
+        let amount_in = if depositing {
+            stake_swap.usdc_to_deposit
+        } else {
+            stake_swap.usdc_deposited
+        };
+
+        // A price far from the TWAP may be manipulated so the USDC is not deposited or swapped
+        if !self.quote_within_twap(amount_in.0, quote.0) {
+            self.stake_swap_quote_failed("The DEX price is too far from the TWAP");
+            return;
         }
 
-        // Add the new staking rewards to the queue
-        if extra_usdc_for_staking.0 > 0 {
-            let new_match_stake_info = MatchStakeInfo {
-                staking_rewards: extra_usdc_for_staking,
-                stake_end_time: U64(env::block_timestamp() + self.rewards_period),
-            };
+        let min_vex_out = min_amount_out(quote, self.config.max_slippage_bps);
+        self.stake_swap.as_mut().unwrap().min_vex_out = min_vex_out;
 
-            self.staking_rewards_queue.push_back(new_match_stake_info);
+        if !depositing {
+            self.swap_deposited_usdc();
+            return;
         }
 
-        let action = create_swap_args(
-            self.ref_pool_id,
-            self.usdc_token_contract.clone(),
-            self.vex_token_contract.clone(),
-            amount_deposited,
-            U128(0),
-        );
-
-        // Call to ref finance to swap the deposited USDC for VEX
-        // Callback to ref_profit_swap_callback
-        // If this call fails there will be USDC funds locked in ref finance
-        // we will implement a function to carry on the swap from this point
-        ref_contract::ext(self.ref_contract.clone())
-            .with_attached_deposit(NearToken::from_yoctonear(1))
-            .with_static_gas(Gas::from_tgas(30))
-            .swap(action)
+        // Call to the DEX to deposit the USDC rewards
+        // Callback to ref_profit_deposit_callback
+        // If this call fails the state is reverted in the callback
+        self.dex_deposit(self.usdc_token_contract.clone(), amount_in)
             .then(
                 Self::ext(env::current_account_id())
-                    .with_static_gas(Gas::from_tgas(150))
-                    .ref_profit_swap_callback(caller),
+                    .with_static_gas(self.dex_callback_gas(DexCallback::ProfitDeposit))
+                    .ref_profit_deposit_callback(),
             );
     }
 
-    // Callback after the swap in ref finance
+    // Callback after the swap in the DEX
     #[private]
     pub fn ref_profit_swap_callback(
         &mut self,
         #[callback_result] call_result: Result<U128, PromiseError>,
-        caller: AccountId,
     ) {
-        let amount_swapped = call_result.unwrap_or_else(|_| panic!("Swap in ref finance failed"));
+        let Ok(amount_swapped) = call_result else {
+            // The USDC is still deposited in the DEX so the swap can be resumed
+            self.stake_swap_failed("Swap in the DEX failed");
+            return;
+        };
 
-        // Call to ref finance to withdraw the VEX that was swapped into
-        // Callback to ref_profit_withdraw_callback
-        // If this call fails there will be VEX funds locked in ref finance
-        // we will implement a function to carry on the withdraw from this point
-        ref_contract::ext(self.ref_contract.clone())
-            .with_attached_deposit(NearToken::from_yoctonear(1))
-            .with_static_gas(Gas::from_tgas(30))
-            .withdraw(self.vex_token_contract.clone(), amount_swapped)
-            .then(
-                Self::ext(env::current_account_id())
-                    .with_static_gas(Gas::from_tgas(100))
-                    .ref_profit_withdraw_callback(caller),
-            );
+        let usdc_swapped = self.stake_swap.as_ref().unwrap().usdc_deposited;
+        self.record_price(usdc_swapped.0, amount_swapped.0);
+
+        let stake_swap = self.stake_swap.as_mut().unwrap();
+        stake_swap.step = StakeSwapStep::Withdraw;
+        stake_swap.usdc_deposited = U128(0);
+        stake_swap.vex_to_withdraw = amount_swapped;
+
+        self.withdraw_vex_for_stake_swap();
     }
 
-    // Callback after withdrawing the VEX from ref finance
+    // Callback after withdrawing the VEX from the DEX
     #[private]
     pub fn ref_profit_withdraw_callback(
         &mut self,
         #[callback_result] call_result: Result<U128, PromiseError>,
-        caller: AccountId,
     ) {
-        let amount_withdrawn =
-            call_result.unwrap_or_else(|_| panic!("Withdraw from ref finance failed"));
+        let Ok(amount_withdrawn) = call_result else {
+            // The VEX is still in the DEX so the withdraw can be resumed
+            self.stake_swap_failed("Withdraw from the DEX failed");
+            return;
+        };
+
+        self.complete_stake_swap(amount_withdrawn);
+    }
+}
+
+impl Contract {
+    // Finishes the stake swap with the VEX withdrawn from the DEX
+    fn complete_stake_swap(&mut self, amount_withdrawn: U128) {
+        let caller = self.stake_swap.take().unwrap().caller;
 
         // Reward the initial caller for some amount of VEX
-        let passed_match_reward = (U256::from(amount_withdrawn.0) / U256::from(100)).as_u128();
+        let passed_match_reward = share_of(amount_withdrawn.0, self.config.stake_swap_reward_bps);
 
         // They will only be rewarded if they are registered in the VEX token contract
         // if they are not registered the VEX reward will be locked in the contract
         ft_contract::ext(self.vex_token_contract.clone())
             .with_attached_deposit(NearToken::from_yoctonear(1))
-            .with_static_gas(Gas::from_tgas(30))
+            .with_static_gas(Gas::from_tgas(DEX_CALL_GAS))
             .ft_transfer(caller, U128(passed_match_reward));
 
         let left_over_rewards = amount_withdrawn.0 - passed_match_reward;
@@ -220,4 +331,193 @@ impl Contract {
         // Add the withdrawn VEX to the total staked balance
         self.total_staked_balance = U128(self.total_staked_balance.0 + left_over_rewards);
     }
+
+    // Adds USDC from a profit to the staking rewards, it is distributed over the rewards period from now
+    fn add_staking_rewards(&mut self, usdc_for_staking: U128) {
+        if usdc_for_staking.0 == 0 {
+            return;
+        }
+
+        let new_match_stake_info = MatchStakeInfo {
+            staking_rewards: usdc_for_staking,
+            stake_end_time: U64(env::block_timestamp() + self.config.rewards_period.0),
+        };
+
+        self.staking_rewards_queue.push_back(new_match_stake_info);
+        self.usdc_staking_rewards = U128(self.usdc_staking_rewards.0 + usdc_for_staking.0);
+    }
+
+    // Calculates the USDC staking rewards distributed since the last stake swap
+    // returns the rewards, the rewards of the finished matches and the number of finished matches
+    pub(crate) fn distributed_staking_rewards(&self) -> (u128, u128, u16) {
+        // Get time passed since last stake swap
+        let time_passed = env::block_timestamp() - self.last_stake_swap_timestamp.0;
+        // Check if the first item in staking rewards queue has expired
+        // repeat until the first item in the queue has not expired
+        // if expired remove and calculate rewards
+        // save matches to remove and rewards to remove for later use in callback
+        let mut finished_matches_rewards: u128 = 0;
+        let mut expired_rewards: u128 = 0;
+        let mut num_to_pop: u16 = 0;
+        for i in self.staking_rewards_queue.iter() {
+            if i.stake_end_time.0 < env::block_timestamp() {
+                let finished_match_time_passed =
+                    i.stake_end_time.0 - self.last_stake_swap_timestamp.0;
+
+                // Get rewards for this match has passed
+                let passed_match_reward = (U256::from(finished_match_time_passed)
+                    * U256::from(i.staking_rewards.0)
+                    / U256::from(self.config.rewards_period.0))
+                .as_u128();
+
+                finished_matches_rewards += passed_match_reward;
+                expired_rewards += i.staking_rewards.0;
+                num_to_pop += 1;
+            } else {
+                break;
+            }
+        }
+
+        // Calculate the rewards for the matches that have not expired
+        let active_match_rewards = (U256::from(time_passed)
+            * U256::from(self.usdc_staking_rewards.0 - expired_rewards)
+            / U256::from(self.config.rewards_period.0))
+        .as_u128();
+
+        (
+            finished_matches_rewards + active_match_rewards,
+            expired_rewards,
+            num_to_pop,
+        )
+    }
+
+    // Checks the minimum interval has passed since the last stake swap started
+    pub(crate) fn stake_swap_interval_passed(&self) -> bool {
+        env::block_timestamp()
+            >= self.last_stake_swap_timestamp.0 + self.config.min_stake_swap_interval.0
+    }
+
+    // Removes the finished matches from the queue and carries over the rewards
+    // over the max stake swap amount once the USDC is deposited, then swaps it for VEX
+    fn record_stake_swap_deposit(&mut self, amount_deposited: U128) {
+        let stake_swap = self.stake_swap.as_mut().unwrap();
+        stake_swap.step = StakeSwapStep::Swap;
+        stake_swap.usdc_deposited = amount_deposited;
+        let num_to_pop = stake_swap.num_to_pop;
+        let expired_rewards = stake_swap.expired_rewards;
+        let usdc_carried_over = stake_swap.usdc_carried_over;
+
+        // Remove the rewards of the finished matches
+        self.usdc_staking_rewards = U128(self.usdc_staking_rewards.0 - expired_rewards.0);
+
+        // Remove the finished matches from the queue
+        for _ in 0..num_to_pop {
+            self.staking_rewards_queue.pop_front();
+        }
+
+        // The rewards over the max stake swap amount are swapped in the next stake swap
+        self.pending_stake_swap_usdc = usdc_carried_over;
+
+        // The USDC is swapped with the minimum output checked before it was deposited
+        self.swap_deposited_usdc();
+    }
+
+    // Clears a stake swap whose quote or deposit failed and restores the last stake swap timestamp
+    // so the rewards are swapped in the next stake swap
+    fn revert_stake_swap_deposit(&mut self) {
+        let stake_swap = self.stake_swap.take().unwrap();
+        self.last_stake_swap_timestamp = stake_swap.previous_timestamp;
+    }
+
+    // Swaps the USDC deposited in the DEX for at least the minimum VEX
+    fn swap_deposited_usdc(&mut self) -> Promise {
+        let stake_swap = self.start_stake_swap_call();
+        let amount_deposited = stake_swap.usdc_deposited;
+        let min_vex_out = stake_swap.min_vex_out;
+
+        // Call to the DEX to swap the deposited USDC for VEX
+        // Callback to ref_profit_swap_callback
+        // If this call fails or the output is below the minimum the USDC stays in the DEX
+        // and the swap can be resumed with a new quote
+        self.dex_swap(&self.usdc_token_contract, amount_deposited, min_vex_out)
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(self.dex_callback_gas(DexCallback::ProfitSwap))
+                    .ref_profit_swap_callback(),
+            )
+    }
+
+    // Gets a quote for the USDC deposited in the DEX then swaps it for VEX when a swap is resumed
+    fn swap_usdc_for_stake_swap(&mut self) -> Promise {
+        let amount_deposited = self.start_stake_swap_call().usdc_deposited;
+
+        // Call to the DEX to get the VEX the deposited USDC would swap for
+        // Callback to ref_profit_quote_callback
+        self.dex_quote(DexQuote::StakeSwap, 0, amount_deposited)
+    }
+
+    // Withdraws the VEX that was swapped into from the DEX
+    fn withdraw_vex_for_stake_swap(&mut self) -> PromiseOrValue<()> {
+        let amount_swapped = self.start_stake_swap_call().vex_to_withdraw;
+
+        // There is nothing left to withdraw so the stake swap is complete
+        if amount_swapped.0 == 0 {
+            self.stake_swap = None;
+            return PromiseOrValue::Value(());
+        }
+
+        // Call to the DEX to withdraw the VEX that was swapped into
+        // Callback to ref_profit_withdraw_callback
+        // If this call fails the VEX stays in the DEX and the withdraw can be resumed
+        PromiseOrValue::Promise(
+            self.dex_withdraw(self.vex_token_contract.clone(), amount_swapped)
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(self.dex_callback_gas(DexCallback::ProfitWithdraw))
+                        .ref_profit_withdraw_callback(),
+                ),
+        )
+    }
+
+    // The VEX and USDC held in the DEX for the loss being covered
+    fn loss_coverage_deposits(&self) -> (u128, u128) {
+        match &self.loss_coverage {
+            Some(loss_coverage) => (
+                loss_coverage.vex_deposited.0,
+                loss_coverage.usdc_to_withdraw.0,
+            ),
+            None => (0, 0),
+        }
+    }
+
+    // Marks that a call to the DEX is in progress for the stake swap
+    fn start_stake_swap_call(&mut self) -> &StakeSwap {
+        let stake_swap = self
+            .stake_swap
+            .as_mut()
+            .unwrap_or_else(|| panic!("No stake swap is in progress"));
+        stake_swap.in_flight = true;
+        stake_swap.last_call_timestamp = U64(env::block_timestamp());
+        stake_swap
+    }
+
+    // Reverts the stake swap if its quote failed before anything was deposited,
+    // otherwise records that the swap failed so it can be resumed
+    pub(crate) fn stake_swap_quote_failed(&mut self, reason: &str) {
+        match self.stake_swap.as_ref() {
+            Some(stake_swap) if stake_swap.step == StakeSwapStep::Deposit => {
+                log!("{}, the stake swap is reverted", reason);
+                self.revert_stake_swap_deposit();
+            }
+            _ => self.stake_swap_failed(reason),
+        }
+    }
+
+    // Records that the current step failed so it can be resumed
+    pub(crate) fn stake_swap_failed(&mut self, reason: &str) {
+        log!("{}, the stake swap can be resumed", reason);
+        if let Some(stake_swap) = self.stake_swap.as_mut() {
+            stake_swap.in_flight = false;
+        }
+    }
 }
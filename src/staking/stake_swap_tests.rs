@@ -0,0 +1,369 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::PromiseError;
+use std::collections::HashMap;
+
+use crate::dex::adapter::min_amount_out;
+use crate::test_utils::*;
+use crate::*;
+
+const REWARDS_PERIOD: u64 = 1_000;
+
+// Sets up a contract with 100 USDC of staking rewards added at timestamp 0
+fn setup() -> Contract {
+    let mut contract = init_contract(REWARDS_PERIOD);
+    let _ = contract.perform_stake_swap_internal(U128(100 * ONE_USDC));
+    contract
+}
+
+fn stake_swap(contract: &Contract) -> &StakeSwap {
+    contract.stake_swap.as_ref().unwrap()
+}
+
+#[test]
+fn test_stake_swap_in_progress() {
+    let mut contract = setup();
+
+    // Half of the rewards period passes
+    new_call(REWARDS_PERIOD / 2);
+    let _ = contract.perform_stake_swap();
+    assert_eq!(stake_swap(&contract).step, StakeSwapStep::Deposit);
+    assert!(stake_swap(&contract).in_flight);
+
+    // A profit made while the swap is in progress is added to the queue without a second swap
+    new_call(REWARDS_PERIOD / 2);
+    let _ = contract.perform_stake_swap_internal(U128(10 * ONE_USDC));
+    assert_eq!(contract.staking_rewards_queue.len(), 2);
+    assert_eq!(contract.usdc_staking_rewards, U128(110 * ONE_USDC));
+    assert_eq!(stake_swap(&contract).step, StakeSwapStep::Deposit);
+}
+
+#[test]
+fn test_profit_while_stake_swaps_paused() {
+    let mut contract = setup();
+    contract.pause_state.stake_swaps = true;
+
+    // A match settles with a profit of 100 USDC while stake swaps are paused
+    new_call(REWARDS_PERIOD / 2);
+    let _ = contract.settle(110 * ONE_USDC, 10 * ONE_USDC);
+
+    // The staking share is added to the queue without starting a swap
+    assert!(contract.stake_swap.is_none());
+    assert_eq!(contract.last_stake_swap_timestamp, U64(0));
+    assert_eq!(contract.staking_rewards_queue.len(), 2);
+    assert_eq!(contract.usdc_staking_rewards, U128(160 * ONE_USDC));
+}
+
+#[test]
+#[should_panic(expected = "A stake swap is already in progress")]
+fn test_second_stake_swap() {
+    let mut contract = setup();
+
+    new_call(REWARDS_PERIOD / 2);
+    let _ = contract.perform_stake_swap();
+
+    new_call(REWARDS_PERIOD / 2);
+    let _ = contract.perform_stake_swap();
+}
+
+#[test]
+fn test_failed_deposit_is_reverted() {
+    let mut contract = setup();
+
+    new_call(REWARDS_PERIOD / 2);
+    let _ = contract.perform_stake_swap();
+
+    new_call(REWARDS_PERIOD / 2);
+    contract.ref_profit_deposit_callback(Err(PromiseError::Failed));
+
+    assert!(contract.stake_swap.is_none());
+    assert_eq!(contract.last_stake_swap_timestamp, U64(0));
+    assert_eq!(contract.usdc_staking_rewards, U128(100 * ONE_USDC));
+}
+
+#[test]
+fn test_resume_stake_swap() {
+    let mut contract = setup();
+    let total_staked_balance = contract.total_staked_balance.0;
+
+    // 100 VEX are worth 100 USDC over a TWAP window of the rewards period
+    contract.config.twap_window = U64(REWARDS_PERIOD);
+    contract.record_price(100 * ONE_USDC, 2 * FIFTY_VEX);
+
+    // The rewards period ends so the match is popped from the queue once the USDC is deposited
+    // the price is observed again so the TWAP is not stale
+    new_call(REWARDS_PERIOD + 1);
+    contract.record_price(100 * ONE_USDC, 2 * FIFTY_VEX);
+    let _ = contract.perform_stake_swap();
+    assert_eq!(stake_swap(&contract).usdc_to_deposit, U128(100 * ONE_USDC));
+
+    // The minimum output is set from the quote before the USDC is deposited
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_quote_callback(Ok(U128(2 * FIFTY_VEX)));
+    assert_eq!(stake_swap(&contract).step, StakeSwapStep::Deposit);
+    assert_eq!(
+        stake_swap(&contract).min_vex_out,
+        min_amount_out(U128(2 * FIFTY_VEX), DEFAULT_MAX_SLIPPAGE_BPS)
+    );
+    assert_eq!(contract.staking_rewards_queue.len(), 1);
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_deposit_callback(Ok(U128(100 * ONE_USDC)));
+    assert!(contract.staking_rewards_queue.is_empty());
+    assert_eq!(contract.usdc_staking_rewards, U128(0));
+    assert_eq!(stake_swap(&contract).step, StakeSwapStep::Swap);
+    assert_eq!(stake_swap(&contract).usdc_deposited, U128(100 * ONE_USDC));
+    assert!(stake_swap(&contract).in_flight);
+
+    // The swap is below the minimum output so the USDC stays in the DEX until the swap is resumed
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_swap_callback(Err(PromiseError::Failed));
+    assert_eq!(stake_swap(&contract).step, StakeSwapStep::Swap);
+    assert!(!stake_swap(&contract).in_flight);
+
+    // The quote of the resumed swap fails so nothing is swapped and the state is left as it was
+    new_call(REWARDS_PERIOD + 1);
+    contract.resume_stake_swap();
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_stake_swap_deposits_callback(Ok(HashMap::from([(usdc(), U128(100 * ONE_USDC))])));
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_quote_callback(Err(PromiseError::Failed));
+    assert_eq!(stake_swap(&contract).step, StakeSwapStep::Swap);
+    assert_eq!(stake_swap(&contract).usdc_deposited, U128(100 * ONE_USDC));
+    assert!(!stake_swap(&contract).in_flight);
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.resume_stake_swap();
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_stake_swap_deposits_callback(Ok(HashMap::from([(usdc(), U128(100 * ONE_USDC))])));
+    assert_eq!(stake_swap(&contract).step, StakeSwapStep::Swap);
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_quote_callback(Ok(U128(2 * FIFTY_VEX)));
+    assert!(stake_swap(&contract).in_flight);
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_swap_callback(Ok(U128(2 * FIFTY_VEX)));
+    assert_eq!(stake_swap(&contract).step, StakeSwapStep::Withdraw);
+    assert_eq!(stake_swap(&contract).vex_to_withdraw, U128(2 * FIFTY_VEX));
+
+    // The withdraw fails and part of the VEX has already left the DEX
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_withdraw_callback(Err(PromiseError::Failed));
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.resume_stake_swap();
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_stake_swap_deposits_callback(Ok(HashMap::from([(vex(), U128(FIFTY_VEX))])));
+    assert_eq!(stake_swap(&contract).vex_to_withdraw, U128(FIFTY_VEX));
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_withdraw_callback(Ok(U128(FIFTY_VEX)));
+
+    // The caller is rewarded one percent and the rest is staked
+    assert!(contract.stake_swap.is_none());
+    assert_eq!(
+        contract.total_staked_balance,
+        U128(total_staked_balance + FIFTY_VEX * 99 / 100)
+    );
+}
+
+#[test]
+fn test_quote_far_from_twap_is_reverted() {
+    let mut contract = setup();
+
+    // 100 VEX are worth 100 USDC over a TWAP window of the rewards period
+    contract.config.twap_window = U64(REWARDS_PERIOD);
+    contract.record_price(100 * ONE_USDC, 2 * FIFTY_VEX);
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.record_price(100 * ONE_USDC, 2 * FIFTY_VEX);
+    let _ = contract.perform_stake_swap();
+
+    // The quote is half the TWAP price so nothing is deposited and the queue is left as it was
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_quote_callback(Ok(U128(FIFTY_VEX)));
+    assert!(contract.stake_swap.is_none());
+    assert_eq!(contract.last_stake_swap_timestamp, U64(0));
+    assert_eq!(contract.staking_rewards_queue.len(), 1);
+    assert_eq!(contract.usdc_staking_rewards, U128(100 * ONE_USDC));
+    assert_eq!(contract.pending_stake_swap_usdc, U128(0));
+}
+
+#[test]
+fn test_resume_after_timeout() {
+    let mut contract = setup();
+
+    // The deposit callback never runs
+    new_call(REWARDS_PERIOD + 1);
+    let _ = contract.perform_stake_swap();
+
+    // The stake swap can be resumed once the timeout passes
+    new_call(REWARDS_PERIOD + 1 + DEX_CALL_TIMEOUT);
+    contract.resume_stake_swap();
+    assert_eq!(
+        stake_swap(&contract).last_call_timestamp,
+        U64(REWARDS_PERIOD + 1 + DEX_CALL_TIMEOUT)
+    );
+
+    // The USDC reached the DEX so the deposit is recorded and the USDC is swapped
+    new_call(REWARDS_PERIOD + 1 + DEX_CALL_TIMEOUT);
+    contract.ref_stake_swap_deposits_callback(Ok(HashMap::from([(usdc(), U128(100 * ONE_USDC))])));
+    assert!(contract.staking_rewards_queue.is_empty());
+    assert_eq!(contract.usdc_staking_rewards, U128(0));
+    assert_eq!(stake_swap(&contract).step, StakeSwapStep::Swap);
+    assert_eq!(stake_swap(&contract).usdc_deposited, U128(100 * ONE_USDC));
+}
+
+#[test]
+fn test_resume_after_timeout_without_deposit() {
+    let mut contract = setup();
+
+    new_call(REWARDS_PERIOD + 1);
+    let _ = contract.perform_stake_swap();
+
+    new_call(REWARDS_PERIOD + 1 + DEX_CALL_TIMEOUT);
+    contract.resume_stake_swap();
+
+    // The USDC never reached the DEX so the stake swap is reverted
+    new_call(REWARDS_PERIOD + 1 + DEX_CALL_TIMEOUT);
+    contract.ref_stake_swap_deposits_callback(Ok(HashMap::new()));
+    assert!(contract.stake_swap.is_none());
+    assert_eq!(contract.last_stake_swap_timestamp, U64(0));
+    assert_eq!(contract.staking_rewards_queue.len(), 1);
+    assert_eq!(contract.usdc_staking_rewards, U128(100 * ONE_USDC));
+}
+
+#[test]
+#[should_panic(expected = "A call to the DEX is still in progress")]
+fn test_resume_before_timeout() {
+    let mut contract = setup();
+
+    new_call(REWARDS_PERIOD + 1);
+    let _ = contract.perform_stake_swap();
+
+    new_call(REWARDS_PERIOD + DEX_CALL_TIMEOUT);
+    contract.resume_stake_swap();
+}
+
+#[test]
+fn test_resume_after_unrecorded_swap() {
+    let mut contract = setup();
+
+    new_call(REWARDS_PERIOD + 1);
+    let _ = contract.perform_stake_swap();
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_deposit_callback(Ok(U128(100 * ONE_USDC)));
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_swap_callback(Err(PromiseError::Failed));
+
+    // VEX deposited for a loss being covered is not withdrawn for the stake swap
+    contract.loss_coverage = Some(LossCoverage {
+        step: LossCoverageStep::Swap,
+        difference: U128(10 * ONE_USDC),
+        vex_deposited: U128(FIFTY_VEX),
+        min_usdc_out: U128(0),
+        usdc_to_withdraw: U128(0),
+        in_flight: false,
+        last_call_timestamp: U64(0),
+        queued_loss: U128(0),
+    });
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.resume_stake_swap();
+
+    // The USDC has already been swapped for VEX
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_stake_swap_deposits_callback(Ok(HashMap::from([
+        (usdc(), U128(0)),
+        (vex(), U128(3 * FIFTY_VEX)),
+    ])));
+    assert_eq!(stake_swap(&contract).step, StakeSwapStep::Withdraw);
+    assert_eq!(stake_swap(&contract).vex_to_withdraw, U128(2 * FIFTY_VEX));
+}
+
+#[test]
+fn test_resume_after_unrecorded_withdraw() {
+    let mut contract = setup();
+    let total_staked_balance = contract.total_staked_balance.0;
+
+    new_call(REWARDS_PERIOD + 1);
+    let _ = contract.perform_stake_swap();
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_deposit_callback(Ok(U128(100 * ONE_USDC)));
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_swap_callback(Ok(U128(2 * FIFTY_VEX)));
+    assert_eq!(stake_swap(&contract).step, StakeSwapStep::Withdraw);
+
+    // The withdraw callback never runs
+    new_call(REWARDS_PERIOD + 1 + DEX_CALL_TIMEOUT);
+    contract.resume_stake_swap();
+
+    // The VEX has already left the DEX so the stake swap is finished with the recorded amount
+    new_call(REWARDS_PERIOD + 1 + DEX_CALL_TIMEOUT);
+    contract.ref_stake_swap_deposits_callback(Ok(HashMap::new()));
+    assert!(contract.stake_swap.is_none());
+    assert_eq!(
+        contract.total_staked_balance,
+        U128(total_staked_balance + 2 * FIFTY_VEX * 99 / 100)
+    );
+}
+
+#[test]
+fn test_stake_swap_tranches() {
+    let mut contract = setup();
+    contract.config.max_stake_swap_amount = U128(40 * ONE_USDC);
+    contract.config.min_stake_swap_interval = U64(100);
+
+    // Only the max stake swap amount is deposited and the rest is carried over
+    new_call(REWARDS_PERIOD + 1);
+    assert_eq!(contract.get_usdc_to_swap(), U128(100 * ONE_USDC));
+    let _ = contract.perform_stake_swap();
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_deposit_callback(Ok(U128(40 * ONE_USDC)));
+    assert!(contract.staking_rewards_queue.is_empty());
+    assert_eq!(contract.get_pending_stake_swap_usdc(), U128(60 * ONE_USDC));
+
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_quote_callback(Ok(U128(FIFTY_VEX)));
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_swap_callback(Ok(U128(FIFTY_VEX)));
+    new_call(REWARDS_PERIOD + 1);
+    contract.ref_profit_withdraw_callback(Ok(U128(FIFTY_VEX)));
+    assert!(contract.stake_swap.is_none());
+
+    // A profit made before the interval passes is added to the queue without a swap
+    new_call(REWARDS_PERIOD + 50);
+    assert!(!contract.can_stake_swap_happen());
+    let _ = contract.perform_stake_swap_internal(U128(10 * ONE_USDC));
+    assert!(contract.stake_swap.is_none());
+    assert_eq!(contract.staking_rewards_queue.len(), 1);
+
+    // The next tranche is taken from the USDC carried over
+    new_call(REWARDS_PERIOD + 101);
+    assert!(contract.can_stake_swap_happen());
+    assert_eq!(contract.get_usdc_to_swap(), U128(61 * ONE_USDC));
+    let _ = contract.perform_stake_swap();
+
+    new_call(REWARDS_PERIOD + 101);
+    contract.ref_profit_deposit_callback(Ok(U128(40 * ONE_USDC)));
+    assert_eq!(contract.get_pending_stake_swap_usdc(), U128(21 * ONE_USDC));
+}
+
+#[test]
+#[should_panic(expected = "The minimum interval between stake swaps has not passed")]
+fn test_stake_swap_before_interval() {
+    let mut contract = setup();
+    contract.config.min_stake_swap_interval = U64(REWARDS_PERIOD);
+
+    new_call(REWARDS_PERIOD - 1);
+    let _ = contract.perform_stake_swap();
+}
@@ -6,8 +6,18 @@ use crate::*;
 
 #[near]
 impl Contract {
-    // Unstake a given amount of VEX to their unstaked balance
-    pub fn unstake(&mut self, amount: U128) -> Promise {
+    // Unstake a given amount of VEX into a withdrawal ticket
+    // the VEX keeps bearing losses until it is withdrawn after the unstake cooldown
+    // Returns the ID of the withdrawal ticket
+    pub fn request_unstake(&mut self, amount: U128) -> TicketId {
+        require!(!self.pause_state.staking, "Staking is paused");
+
+        // A loss being covered has not been taken from the staked balance yet
+        require!(
+            self.loss_coverage.is_none(),
+            "A loss is being covered, try again once it is complete"
+        );
+
         require!(amount.0 > 0, "Unstaking amount should be positive");
 
         let account_id = env::predecessor_account_id();
@@ -16,6 +26,12 @@ impl Contract {
             .get(&account_id)
             .unwrap_or_else(|| panic!("You do not have any stake"));
 
+        let tickets = self.withdrawal_tickets.get(&account_id).map_or(0, Vec::len);
+        require!(
+            tickets < MAX_WITHDRAWAL_TICKETS,
+            "You have too many withdrawal tickets, withdraw some first"
+        );
+
         require!(
             relevant_account.stake_shares.0 > 0,
             "You do not have any stake"
@@ -40,7 +56,7 @@ impl Contract {
             "Not enough staked balance to unstake"
         );
 
-        // Calculating the amount of tokens the account will receive by unstaking the corresponding
+        // Calculating the amount of tokens that will be put in the withdrawal ticket by unstaking the corresponding
         // number of "stake" shares, rounding up.
         let receive_amount = self.staked_amount_from_num_shares_rounded_up(num_shares);
         require!(
@@ -58,9 +74,9 @@ impl Contract {
             self.staked_amount_from_num_shares_rounded_up(relevant_account.stake_shares.0);
 
         require!(
-            staked_balance - unstake_amount >= 50
+            staked_balance - unstake_amount >= self.config.min_stake.0
                 || relevant_account.stake_shares.0 - num_shares == 0,
-            "You must keep at least 50 VEX staked or withdraw all"
+            "You must keep at least the minimum stake staked or withdraw all"
         );
 
         let relevant_account = self
@@ -79,6 +95,25 @@ impl Contract {
             self.users_stake.remove(&account_id);
         }
 
+        // Move the VEX into a withdrawal ticket where it keeps bearing losses
+        let unstaking_shares = self.unstaking_shares_from_amount(receive_amount);
+        self.total_unstaking_balance = U128(self.total_unstaking_balance.0 + receive_amount);
+        self.total_unstaking_shares = U128(self.total_unstaking_shares.0 + unstaking_shares);
+
+        self.last_ticket_id = U64(self.last_ticket_id.0 + 1);
+        let ticket_id = self.last_ticket_id;
+
+        self.withdrawal_tickets
+            .entry(account_id.clone())
+            .or_default()
+            .push(WithdrawalTicket {
+                ticket_id,
+                unstaking_shares: U128(unstaking_shares),
+                withdrawable_timestamp: U64(
+                    env::block_timestamp() + self.config.unstake_cooldown.0,
+                ),
+            });
+
         Event::UnstakeVex {
             account_id: &account_id,
             amount,
@@ -86,15 +121,11 @@ impl Contract {
         }
         .emit();
 
-        // Transfer the amount of VEX to the user
-        ft_contract::ext(self.vex_token_contract.clone())
-            .with_attached_deposit(NearToken::from_yoctonear(1))
-            .with_static_gas(Gas::from_tgas(30))
-            .ft_transfer(account_id, U128(receive_amount))
+        ticket_id
     }
 
-    // Unstake all VEX to their unstaked balance
-    pub fn unstake_all(&mut self) -> Promise {
+    // Unstake all VEX into a withdrawal ticket
+    pub fn request_unstake_all(&mut self) -> TicketId {
         let account_id = env::predecessor_account_id();
 
         let relevant_account = self
@@ -106,7 +137,72 @@ impl Contract {
         let amount =
             self.staked_amount_from_num_shares_rounded_down(relevant_account.stake_shares.0);
 
-        // Call unstake with the full amount from staked balance
-        self.unstake(U128(amount))
+        // Call request_unstake with the full amount from staked balance
+        self.request_unstake(U128(amount))
+    }
+
+    // Kept for callers of the method before withdrawal tickets, unstakes into a withdrawal ticket
+    // instead of sending the VEX
+    pub fn unstake(&mut self, amount: U128) -> TicketId {
+        self.request_unstake(amount)
+    }
+
+    // Kept for callers of the method before withdrawal tickets, unstakes all VEX into a
+    // withdrawal ticket instead of sending the VEX
+    pub fn unstake_all(&mut self) -> TicketId {
+        self.request_unstake_all()
+    }
+
+    // Withdraw the VEX in a withdrawal ticket once the unstake cooldown has passed
+    // the amount paid out is what the ticket is worth after any losses
+    pub fn withdraw_unstaked(&mut self, ticket_id: TicketId) -> Promise {
+        require!(!self.pause_state.staking, "Staking is paused");
+
+        // A loss being covered has not been taken from the tickets yet
+        require!(
+            self.loss_coverage.is_none(),
+            "A loss is being covered, try again once it is complete"
+        );
+
+        let account_id = env::predecessor_account_id();
+        let tickets = self
+            .withdrawal_tickets
+            .get_mut(&account_id)
+            .unwrap_or_else(|| panic!("You do not have any withdrawal tickets"));
+
+        let index = tickets
+            .iter()
+            .position(|ticket| ticket.ticket_id == ticket_id)
+            .unwrap_or_else(|| panic!("Withdrawal ticket not found"));
+
+        require!(
+            env::block_timestamp() >= tickets[index].withdrawable_timestamp.0,
+            "The unstake cooldown has not passed yet"
+        );
+
+        let ticket = tickets.remove(index);
+
+        // If the user has no tickets left, remove them from the map
+        if tickets.is_empty() {
+            self.withdrawal_tickets.remove(&account_id);
+        }
+
+        let amount = self.unstaking_amount_from_shares(ticket.unstaking_shares.0);
+        self.total_unstaking_balance = U128(self.total_unstaking_balance.0 - amount);
+        self.total_unstaking_shares =
+            U128(self.total_unstaking_shares.0 - ticket.unstaking_shares.0);
+
+        Event::WithdrawUnstaked {
+            account_id: &account_id,
+            ticket_id,
+            amount: U128(amount),
+        }
+        .emit();
+
+        // Transfer the amount of VEX to the user
+        ft_contract::ext(self.vex_token_contract.clone())
+            .with_attached_deposit(NearToken::from_yoctonear(1))
+            .with_static_gas(Gas::from_tgas(30))
+            .ft_transfer(account_id, U128(amount))
     }
 }
@@ -0,0 +1,121 @@
+use near_sdk::json_types::{U128, U64};
+
+use crate::test_utils::*;
+use crate::*;
+
+// Sets up a contract where Alice has staked as much VEX as the contract was initialized with
+// then requested to unstake all of it
+fn setup() -> Contract {
+    let mut contract = init_contract(100);
+
+    new_call_from(vex(), 0);
+    contract.stake(alice(), contract.total_staked_balance);
+
+    new_call_from(alice(), UNSTAKE_TIME_BUFFER);
+    let ticket_id = contract.request_unstake_all();
+    assert_eq!(ticket_id, U64(1));
+
+    contract
+}
+
+// Sells VEX to cover a loss the insurance fund could not
+fn cover_loss(contract: &mut Contract, vex_sold: u128) {
+    new_call(UNSTAKE_TIME_BUFFER);
+    let _ = contract.handle_loss(ONE_USDC);
+
+    new_call(UNSTAKE_TIME_BUFFER);
+    contract.ref_loss_deposit_callback(Ok(U128(vex_sold)));
+
+    new_call(UNSTAKE_TIME_BUFFER);
+    contract.ref_loss_swap_callback(Ok(U128(ONE_USDC)));
+}
+
+#[test]
+fn test_withdrawal_ticket_bears_loss() {
+    let mut contract = setup();
+    let total_staked_balance = contract.total_staked_balance.0;
+
+    // Alice's stake is in a withdrawal ticket and no longer staked
+    assert!(contract.get_user_staked_bal(alice()).is_none());
+    assert_eq!(contract.total_unstaking_balance.0, total_staked_balance);
+
+    // The ticket holds half of the VEX so it pays half of the loss
+    cover_loss(&mut contract, 2 * FIFTY_VEX);
+    assert_eq!(
+        contract.total_staked_balance.0,
+        total_staked_balance - FIFTY_VEX
+    );
+
+    let tickets = contract.get_withdrawal_tickets(alice());
+    assert_eq!(tickets.len(), 1);
+    assert_eq!(tickets[0].amount.0, total_staked_balance - FIFTY_VEX);
+    assert_eq!(
+        tickets[0].withdrawable_timestamp,
+        U64(UNSTAKE_TIME_BUFFER + DEFAULT_UNSTAKE_COOLDOWN)
+    );
+
+    // Once the loss is covered and the cooldown has passed Alice withdraws what the ticket is worth
+    contract.loss_coverage = None;
+    new_call_from(alice(), UNSTAKE_TIME_BUFFER + DEFAULT_UNSTAKE_COOLDOWN);
+    contract.withdraw_unstaked(U64(1));
+
+    assert!(contract.get_withdrawal_tickets(alice()).is_empty());
+    assert_eq!(contract.total_unstaking_balance, U128(0));
+    assert_eq!(contract.total_unstaking_shares, U128(0));
+}
+
+#[test]
+#[should_panic(expected = "The unstake cooldown has not passed yet")]
+fn test_withdraw_before_cooldown() {
+    let mut contract = setup();
+
+    new_call_from(alice(), UNSTAKE_TIME_BUFFER + DEFAULT_UNSTAKE_COOLDOWN - 1);
+    contract.withdraw_unstaked(U64(1));
+}
+
+#[test]
+#[should_panic(expected = "A loss is being covered, try again once it is complete")]
+fn test_withdraw_during_loss_coverage() {
+    let mut contract = setup();
+
+    cover_loss(&mut contract, FIFTY_VEX);
+
+    new_call_from(alice(), UNSTAKE_TIME_BUFFER + DEFAULT_UNSTAKE_COOLDOWN);
+    contract.withdraw_unstaked(U64(1));
+}
+
+#[test]
+#[should_panic(expected = "A loss is being covered, try again once it is complete")]
+fn test_request_unstake_during_loss_coverage() {
+    let mut contract = setup();
+
+    new_call_from(vex(), UNSTAKE_TIME_BUFFER);
+    contract.stake(alice(), U128(2 * FIFTY_VEX));
+
+    cover_loss(&mut contract, FIFTY_VEX);
+
+    new_call_from(alice(), 2 * UNSTAKE_TIME_BUFFER);
+    let _ = contract.request_unstake_all();
+}
+
+#[test]
+fn test_withdrawal_ticket_after_loss() {
+    let mut contract = setup();
+
+    // The first ticket pays half of the loss so its shares are worth less than the VEX in them
+    cover_loss(&mut contract, 2 * FIFTY_VEX);
+    contract.loss_coverage = None;
+    let first_ticket = contract.get_withdrawal_tickets(alice())[0].amount.0;
+
+    new_call_from(vex(), UNSTAKE_TIME_BUFFER);
+    contract.stake(alice(), U128(2 * FIFTY_VEX));
+
+    // The second ticket is worth the VEX unstaked and the first is unchanged
+    new_call_from(alice(), 2 * UNSTAKE_TIME_BUFFER);
+    let ticket_id = contract.unstake(U128(FIFTY_VEX));
+    assert_eq!(ticket_id, U64(2));
+
+    let tickets = contract.get_withdrawal_tickets(alice());
+    assert_eq!(tickets[0].amount.0, first_ticket);
+    assert_eq!(tickets[1].amount.0, FIFTY_VEX);
+}
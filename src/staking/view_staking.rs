@@ -1,7 +1,13 @@
-use near_sdk::env;
-
 use crate::*;
 
+// A withdrawal ticket and the amount of VEX it is worth now
+#[near(serializers = [json])]
+pub struct WithdrawalTicketInfo {
+    pub ticket_id: TicketId,
+    pub amount: U128,
+    pub withdrawable_timestamp: U64,
+}
+
 #[near]
 impl Contract {
     // Get $VEX staking balance for a user if they were to unstake now
@@ -23,6 +29,27 @@ impl Contract {
             .unwrap_or_else(|| panic!("You do not have any stake"))
     }
 
+    // Get a user's withdrawal tickets that have not been withdrawn and what they are worth now
+    pub fn get_withdrawal_tickets(&self, account_id: AccountId) -> Vec<WithdrawalTicketInfo> {
+        self.withdrawal_tickets
+            .get(&account_id)
+            .map_or_else(Vec::new, |tickets| {
+                tickets
+                    .iter()
+                    .map(|ticket| WithdrawalTicketInfo {
+                        ticket_id: ticket.ticket_id,
+                        amount: U128(self.unstaking_amount_from_shares(ticket.unstaking_shares.0)),
+                        withdrawable_timestamp: ticket.withdrawable_timestamp,
+                    })
+                    .collect()
+            })
+    }
+
+    // Get total VEX in withdrawal tickets
+    pub fn get_total_unstaking_balance(&self) -> U128 {
+        self.total_unstaking_balance
+    }
+
     // Get USDC staking rewards queue
     pub fn get_staking_rewards_queue(&self) -> &VecDeque<MatchStakeInfo> {
         &self.staking_rewards_queue
@@ -63,45 +90,35 @@ impl Contract {
         self.funds_to_add
     }
 
-    pub fn can_stake_swap_happen(&self) -> bool {
-        if self.staking_rewards_queue.is_empty() {
-            return false;
-        }
+    // Get the stake swap in progress and the amounts involved, None if there is none
+    pub fn get_stake_swap(&self) -> Option<&StakeSwap> {
+        self.stake_swap.as_ref()
+    }
 
-        let time_passed = env::block_timestamp() - self.last_stake_swap_timestamp.0;
-        // Check if the first item in staking rewards queue has expired
-        // repeat until the first item in the queue has not expired
-        let mut finished_matches_rewards: u128 = 0;
-        let mut new_usdc_staking_rewards = self.usdc_staking_rewards.0;
-        for i in self.staking_rewards_queue.iter() {
-            if i.stake_end_time.0 < env::block_timestamp() {
-                let finished_match_time_passed =
-                    i.stake_end_time.0 - self.last_stake_swap_timestamp.0;
-
-                // Get rewards for this match has passed
-                let passed_match_reward = (U256::from(finished_match_time_passed)
-                    * U256::from(i.staking_rewards.0)
-                    / U256::from(self.rewards_period))
-                .as_u128();
-
-                finished_matches_rewards += passed_match_reward;
-                new_usdc_staking_rewards -= i.staking_rewards.0;
-            } else {
-                break;
-            }
-        }
+    // Get the loss being covered with the DEX and the amounts involved, None if there is none
+    pub fn get_loss_coverage(&self) -> Option<&LossCoverage> {
+        self.loss_coverage.as_ref()
+    }
 
-        // Calculate the rewards for the matches that have not expired
-        let active_match_rewards = (U256::from(time_passed) * U256::from(new_usdc_staking_rewards)
-            / U256::from(self.rewards_period))
-        .as_u128();
+    // Get the USDC staking rewards carried over to the next stake swap
+    pub fn get_pending_stake_swap_usdc(&self) -> U128 {
+        self.pending_stake_swap_usdc
+    }
 
-        let total_rewards_to_swap = finished_matches_rewards + active_match_rewards;
+    // Get the USDC that is waiting to be swapped, including the rewards distributed since the last stake swap
+    pub fn get_usdc_to_swap(&self) -> U128 {
+        let (distributed_rewards, _, _) = self.distributed_staking_rewards();
+        U128(self.pending_stake_swap_usdc.0 + distributed_rewards)
+    }
 
-        if total_rewards_to_swap > self.min_swap_amount {
-            return true;
+    pub fn can_stake_swap_happen(&self) -> bool {
+        if (self.staking_rewards_queue.is_empty() && self.pending_stake_swap_usdc.0 == 0)
+            || self.stake_swap.is_some()
+            || !self.stake_swap_interval_passed()
+        {
+            return false;
         }
 
-        false
+        self.get_usdc_to_swap().0 > self.config.min_swap_amount.0
     }
 }
@@ -1,62 +1,68 @@
-use std::str::FromStr;
+// Accounts and setup shared by the unit tests
+use near_sdk::json_types::{U128, U64};
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, AccountId, Gas};
 
-use near_sdk::{json_types::{U128, U64}, test_utils::VMContextBuilder, testing_env, AccountId, NearToken};
+use crate::*;
 
-use crate::Contract;
+// The time in nanoseconds before a user can unstake since last staking
+pub const UNSTAKE_TIME_BUFFER: u64 = 10;
 
-fn owner() -> AccountId {
-    AccountId::from_str("owner.testnet").unwrap()
+pub fn admin() -> AccountId {
+    "admin.testnet".parse().unwrap()
 }
 
-fn admin() -> AccountId {
-    AccountId::from_str("admin.testnet").unwrap()
+pub fn alice() -> AccountId {
+    "alice.testnet".parse().unwrap()
 }
 
-fn usdc_account() -> AccountId {
-    AccountId::from_str("usdc.testnet").unwrap()
+pub fn bob() -> AccountId {
+    "bob.testnet".parse().unwrap()
 }
 
-fn vex_token_account() -> AccountId {
-    AccountId::from_str("vex_token.testnet").unwrap()
+pub fn usdc() -> AccountId {
+    "usdc.testnet".parse().unwrap()
 }
 
-fn treasury_account() -> AccountId {
-    AccountId::from_str("treasury.testnet").unwrap()
+pub fn vex() -> AccountId {
+    "vex.testnet".parse().unwrap()
 }
 
-fn ref_finance_account() -> AccountId {
-    AccountId::from_str("ref_finance.testnet").unwrap()
+// Starts a new call from the admin at a timestamp so each call has its own gas
+pub fn new_call(timestamp: u64) {
+    new_call_from(admin(), timestamp);
 }
 
-const TEST_REF_POOL_ID: U64 = U64(1);
-const REWARDS_PERIOD: U64 = U64(100);
-const UNSTAKE_TIME_BUFFER: U64 = U64(10);
-const MIN_SWAP_AMMOUNT: U128 = U128(500);
-
-
-pub fn setup(
-    contract_owner_account_id: Option<AccountId>,
-    contract_predecessor_account_id: Option<AccountId>,
-) -> (Contract, VMContextBuilder) {
-    let mut context = VMContextBuilder::new();
-
-    let contract_owner_account_id = contract_owner_account_id.unwrap_or(owner());
-    context.current_account_id(contract_owner_account_id.clone());
-
-    //setting predecessor to owner to simulate contract deployment.
-    //with contract deployment the predecessor will be equal to current account.
-    context.predecessor_account_id(contract_owner_account_id.clone());
-
-    context.account_balance(NearToken::from_near(50));
-
-    testing_env!(context.build());
-
-    let contract = Contract::init(admin(), usdc_account(), vex_token_account(), treasury_account(), refinance_account(), TEST_REF_POOL_ID, REWARDS_PERIOD, UNSTAKE_TIME_BUFFER, MIN_SWAP_AMMOUNT);
-
-    //now, after the contract has been deployed we can switch predecessor to whatever our test requires.
-    context.predecessor_account_id(contract_predecessor_account_id.unwrap_or(owner()));
-
-    testing_env!(context.build());
+// Starts a new call from an account at a timestamp so each call has its own gas
+pub fn new_call_from(predecessor: AccountId, timestamp: u64) {
+    testing_env!(VMContextBuilder::new()
+        .predecessor_account_id(predecessor)
+        .block_timestamp(timestamp)
+        .prepaid_gas(Gas::from_tgas(300))
+        .build());
+}
 
-    (contract, context)
-}
\ No newline at end of file
+// Initializes a contract from the admin at timestamp 0 that swaps through a single Ref pool
+pub fn init_contract(rewards_period: u64) -> Contract {
+    new_call(0);
+
+    Contract::init(
+        admin(),
+        usdc(),
+        vex(),
+        admin(),
+        DexAdapter::RefFinance {
+            contract_id: "ref.testnet".parse().unwrap(),
+            route: vec![RoutePool {
+                pool_id: U64(0),
+                token_out: usdc(),
+            }],
+        },
+        Config {
+            rewards_period: U64(rewards_period),
+            unstake_time_buffer: U64(UNSTAKE_TIME_BUFFER),
+            min_swap_amount: U128(ONE_USDC),
+            ..Config::default()
+        },
+    )
+}
@@ -0,0 +1,144 @@
+use near_sdk::json_types::U64;
+use near_sdk::{env, near, require};
+
+use crate::config::validate_config;
+use crate::events::Event;
+use crate::*;
+
+#[near]
+impl Contract {
+    // Queues a sensitive admin action which can be executed once the timelock delay has passed
+    // Returns the ID of the scheduled action
+    pub fn schedule_action(&mut self, action: TimelockAction) -> ActionId {
+        self.assert_action_role(&action);
+
+        let eta = U64(env::block_timestamp() + self.config.timelock_delay.0);
+
+        // Reject actions that are certain to fail when they are executed
+        match &action {
+            TimelockAction::UpdateConfig { config } => {
+                validate_config(config);
+                self.validate_resolver_threshold(config);
+            }
+            TimelockAction::SetDexAdapter { dex_adapter } => self.validate_dex_adapter(dex_adapter),
+            TimelockAction::ProposeAdmin {
+                expires_at: Some(expires_at),
+                ..
+            } => require!(
+                expires_at.0 > eta.0,
+                "The expiry must be after the action can be executed"
+            ),
+            _ => {}
+        }
+
+        self.last_action_id.0 += 1;
+
+        let scheduled_by = env::predecessor_account_id();
+
+        self.pending_actions.insert(
+            self.last_action_id,
+            PendingAction {
+                action: action.clone(),
+                scheduled_by: scheduled_by.clone(),
+                eta,
+            },
+        );
+
+        Event::ScheduleAction {
+            account_id: &scheduled_by,
+            action_id: self.last_action_id,
+            action,
+            eta,
+        }
+        .emit();
+
+        self.last_action_id
+    }
+
+    // Executes a scheduled action once the timelock delay has passed
+    pub fn execute_action(&mut self, action_id: ActionId) {
+        let pending_action = self
+            .pending_actions
+            .remove(&action_id)
+            .unwrap_or_else(|| panic!("No action exists with action id: {:?}", action_id));
+
+        self.assert_action_role(&pending_action.action);
+
+        require!(
+            env::block_timestamp() >= pending_action.eta.0,
+            "The timelock delay has not passed"
+        );
+
+        match pending_action.action {
+            TimelockAction::TakeFromFeesFund { amount, receiver } => {
+                self.take_from_fees_fund(amount, receiver)
+            }
+            TimelockAction::TakeFromInsuranceFund { amount, receiver } => {
+                self.take_from_insurance_fund(amount, receiver)
+            }
+            TimelockAction::UpdateConfig { config } => self.update_config(config),
+            TimelockAction::ProposeAdmin {
+                new_admin,
+                expires_at,
+            } => self.propose_admin(new_admin, expires_at),
+            TimelockAction::SetDexAdapter { dex_adapter } => self.set_dex_adapter(dex_adapter),
+            TimelockAction::UpgradeContract { code_hash } => {
+                self.deploy_staged_code(code_hash).as_return();
+            }
+        }
+
+        Event::ExecuteAction {
+            account_id: &env::predecessor_account_id(),
+            action_id,
+        }
+        .emit();
+    }
+
+    // Cancels a scheduled action before it is executed
+    pub fn cancel_action(&mut self, action_id: ActionId) {
+        let pending_action = self
+            .pending_actions
+            .remove(&action_id)
+            .unwrap_or_else(|| panic!("No action exists with action id: {:?}", action_id));
+
+        self.assert_action_role(&pending_action.action);
+
+        Event::CancelAction {
+            account_id: &env::predecessor_account_id(),
+            action_id,
+        }
+        .emit();
+    }
+
+    // Returns a list of actions waiting to be executed within a range
+    pub fn get_pending_actions(
+        &self,
+        from_index: &Option<u32>,
+        limit: &Option<u32>,
+    ) -> Vec<(ActionId, &PendingAction)> {
+        let from = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(self.pending_actions.len());
+
+        self.pending_actions
+            .iter()
+            .skip(from as usize)
+            .take(limit as usize)
+            .map(|(&action_id, pending_action)| (action_id, pending_action))
+            .collect()
+    }
+
+    // Returns a specific action waiting to be executed by its ID
+    pub fn get_pending_action(&self, action_id: &ActionId) -> &PendingAction {
+        self.pending_actions
+            .get(action_id)
+            .unwrap_or_else(|| panic!("No action exists with action id: {:?}", action_id))
+    }
+
+    // Checks the caller can schedule, execute and cancel the action
+    pub(crate) fn assert_action_role(&self, action: &TimelockAction) {
+        match action.role() {
+            Some(role) => self.assert_role(role),
+            None => self.assert_admin(),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod tracker;
+
+#[cfg(test)]
+mod tracker_tests;
@@ -0,0 +1,173 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{env, log, near, require, Promise, PromiseError};
+use std::collections::VecDeque;
+
+use crate::dex::adapter::DexQuote;
+use crate::dex::gas::price_ping_gas;
+use crate::*;
+
+// Calculates the price of one VEX in USDC as a fixed-point number from an amount of USDC and VEX
+// saturates so a price seen in a callback can never panic
+pub fn price_of(usdc: u128, vex: u128) -> u128 {
+    let price = U256::from(usdc) * U256::from(FIXED_POINT_ONE) * U256::from(ONE_VEX)
+        / (U256::from(ONE_USDC) * U256::from(vex));
+    u128::try_from(price).unwrap_or(u128::MAX)
+}
+
+// Calculates the amount of VEX worth an amount of USDC at a price
+// saturates like price_of so a tiny TWAP can never panic
+pub fn vex_for_usdc(usdc: u128, price: u128) -> u128 {
+    let vex = U256::from(usdc) * U256::from(FIXED_POINT_ONE) * U256::from(ONE_VEX)
+        / (U256::from(ONE_USDC) * U256::from(price));
+    u128::try_from(vex).unwrap_or(u128::MAX)
+}
+
+// The cumulative price of an observation carried forward to a later timestamp
+fn cumulative_at(observation: &PriceObservation, timestamp: u64) -> u128 {
+    let time_passed = u128::from(timestamp - observation.timestamp.0);
+    observation
+        .price_cumulative
+        .0
+        .wrapping_add(observation.price.0.wrapping_mul(time_passed))
+}
+
+#[near]
+impl Contract {
+    // Records the current price on the DEX so the TWAP stays up to date between swaps
+    pub fn ping_price(&mut self) -> Promise {
+        self.assert_role(Role::Keeper);
+
+        self.require_dex_gas(price_ping_gas);
+
+        require!(
+            self.can_record_price(),
+            "The minimum interval between price observations has not passed"
+        );
+
+        // Call to the DEX to get the USDC a fixed amount of VEX would swap for
+        // Callback to price_ping_callback
+        self.dex_quote(DexQuote::PricePing, 0, U128(PRICE_PING_AMOUNT))
+    }
+
+    // Callback after getting the quote for a price ping
+    #[private]
+    pub fn price_ping_callback(
+        &mut self,
+        #[callback_result] call_result: Result<U128, PromiseError>,
+    ) {
+        match call_result {
+            Ok(usdc) if usdc.0 > 0 => self.record_price(usdc.0, PRICE_PING_AMOUNT),
+            _ => log!("Quote from the DEX failed, no price was recorded"),
+        }
+    }
+
+    // Returns the time-weighted average price of one VEX in USDC over a window in nanoseconds
+    // None if the observations do not cover the whole window or none was made within it
+    pub fn get_twap(&self, window: U64) -> Option<U128> {
+        require!(window.0 > 0, "The window must be more than zero");
+
+        self.twap(window.0).map(U128)
+    }
+
+    // Returns the price observations from oldest to newest
+    pub fn get_price_observations(&self) -> &VecDeque<PriceObservation> {
+        &self.price_observations
+    }
+
+    // Returns the amount of VEX worth the USDC that needs to be added at the TWAP
+    // None if there is no TWAP over the TWAP window
+    pub fn get_funds_to_add_in_vex(&self) -> Option<U128> {
+        let twap = self
+            .twap(self.config.twap_window.0)
+            .filter(|&twap| twap > 0)?;
+
+        Some(U128(vex_for_usdc(self.funds_to_add.0, twap)))
+    }
+}
+
+impl Contract {
+    // Adds an observation of the price from an amount of USDC and VEX swapped or quoted
+    // the oldest observation is removed once the ring buffer is full
+    pub(crate) fn record_price(&mut self, usdc: u128, vex: u128) {
+        if vex == 0 {
+            return;
+        }
+
+        // Observations closer together than the minimum interval are skipped
+        // so the ring buffer cannot be filled within the TWAP window
+        if !self.can_record_price() {
+            log!("A price was recorded less than the minimum interval ago");
+            return;
+        }
+
+        let timestamp = env::block_timestamp();
+        let price = price_of(usdc, vex);
+
+        let price_cumulative = match self.price_observations.back() {
+            Some(last) => cumulative_at(last, timestamp),
+            None => 0,
+        };
+
+        self.price_observations.push_back(PriceObservation {
+            timestamp: U64(timestamp),
+            price: U128(price),
+            price_cumulative: U128(price_cumulative),
+        });
+
+        if self.price_observations.len() > MAX_PRICE_OBSERVATIONS {
+            self.price_observations.pop_front();
+        }
+    }
+
+    // Checks the minimum interval of the TWAP window divided by PRICE_OBSERVATIONS_PER_WINDOW
+    // has passed since the last observation
+    pub(crate) fn can_record_price(&self) -> bool {
+        let min_interval = (self.config.twap_window.0 / PRICE_OBSERVATIONS_PER_WINDOW).max(1);
+
+        self.price_observations
+            .back()
+            .is_none_or(|last| env::block_timestamp() >= last.timestamp.0 + min_interval)
+    }
+
+    // Calculates the time-weighted average price over a window ending now
+    // the latest observation must be within the window so a stale price is never carried forward
+    pub(crate) fn twap(&self, window: u64) -> Option<u128> {
+        let now = env::block_timestamp();
+        let start = now.checked_sub(window)?;
+
+        let last = self
+            .price_observations
+            .back()
+            .filter(|last| last.timestamp.0 >= start)?;
+
+        // The price at the start of the window is the latest observation before it
+        let first = self
+            .price_observations
+            .iter()
+            .rev()
+            .find(|observation| observation.timestamp.0 <= start)?;
+
+        let price_sum = cumulative_at(last, now).wrapping_sub(cumulative_at(first, start));
+
+        Some(price_sum / u128::from(window))
+    }
+
+    // Checks the price of a quote is within the max price deviation of the TWAP
+    // quotes are rejected until the observations cover the TWAP window
+    // and while no price has been observed within it
+    pub(crate) fn quote_within_twap(&self, usdc: u128, vex: u128) -> bool {
+        let Some(twap) = self.twap(self.config.twap_window.0) else {
+            log!("There is no recent TWAP over the window so the quote cannot be checked");
+            return false;
+        };
+
+        if vex == 0 {
+            return false;
+        }
+
+        let deviation = price_of(usdc, vex).abs_diff(twap);
+
+        U256::from(deviation) * U256::from(BASIS_POINTS)
+            <= U256::from(twap) * U256::from(self.config.max_price_deviation_bps)
+    }
+}
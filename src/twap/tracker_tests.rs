@@ -0,0 +1,146 @@
+use near_sdk::json_types::{U128, U64};
+
+use crate::test_utils::*;
+use crate::twap::tracker::vex_for_usdc;
+use crate::*;
+
+// The price of VEX when 50 VEX are worth one USDC, 0.02 USDC
+const TWO_CENTS: u128 = FIXED_POINT_ONE / 50;
+
+// Sets up a contract with a TWAP window of 100 nanoseconds
+fn setup() -> Contract {
+    let mut contract = init_contract(100);
+    contract.config.twap_window = U64(100);
+    contract
+}
+
+#[test]
+fn test_twap() {
+    let mut contract = setup();
+
+    // 50 VEX are worth one USDC then 25 VEX are worth one USDC
+    contract.record_price(ONE_USDC, FIFTY_VEX);
+    new_call(100);
+    contract.record_price(ONE_USDC, FIFTY_VEX / 2);
+    assert_eq!(contract.price_observations[1].price, U128(2 * TWO_CENTS));
+    assert_eq!(
+        contract.price_observations[1].price_cumulative,
+        U128(100 * TWO_CENTS)
+    );
+
+    // Each price lasted half of the window
+    new_call(200);
+    assert_eq!(contract.get_twap(U64(200)), Some(U128(3 * TWO_CENTS / 2)));
+    assert_eq!(contract.get_twap(U64(100)), Some(U128(2 * TWO_CENTS)));
+    assert_eq!(contract.get_twap(U64(150)), Some(U128(5 * TWO_CENTS / 3)));
+
+    // The observations do not cover a longer window
+    assert_eq!(contract.get_twap(U64(201)), None);
+}
+
+#[test]
+fn test_stale_twap() {
+    let mut contract = setup();
+    contract.record_price(ONE_USDC, FIFTY_VEX);
+    new_call(100);
+    contract.record_price(ONE_USDC, FIFTY_VEX);
+
+    // The latest observation is at the start of the window
+    new_call(200);
+    assert_eq!(contract.get_twap(U64(100)), Some(U128(TWO_CENTS)));
+    assert!(contract.quote_within_twap(ONE_USDC, FIFTY_VEX));
+
+    // No price has been observed within the window so the last price is not carried forward
+    new_call(201);
+    assert_eq!(contract.get_twap(U64(100)), None);
+    assert!(!contract.quote_within_twap(ONE_USDC, FIFTY_VEX));
+    assert_eq!(contract.get_twap(U64(101)), Some(U128(TWO_CENTS)));
+}
+
+#[test]
+fn test_vex_for_usdc_saturates() {
+    assert_eq!(vex_for_usdc(ONE_USDC, TWO_CENTS), FIFTY_VEX);
+    assert_eq!(vex_for_usdc(u128::MAX, 1), u128::MAX);
+}
+
+#[test]
+fn test_observations_ring_buffer() {
+    let mut contract = setup();
+
+    // The minimum interval between observations is 3 nanoseconds
+    for observation in 0..(MAX_PRICE_OBSERVATIONS as u64 + 6) {
+        new_call(observation * 3);
+        contract.record_price(ONE_USDC, FIFTY_VEX);
+    }
+
+    // The oldest observations are removed
+    assert_eq!(contract.price_observations.len(), MAX_PRICE_OBSERVATIONS);
+    assert_eq!(contract.price_observations[0].timestamp, U64(18));
+
+    // An observation before the minimum interval has passed is skipped
+    let last_timestamp = contract.price_observations.back().unwrap().timestamp.0;
+    new_call(last_timestamp + 2);
+    contract.record_price(ONE_USDC, FIFTY_VEX / 2);
+    assert!(!contract.can_record_price());
+    assert_eq!(contract.price_observations.len(), MAX_PRICE_OBSERVATIONS);
+    assert_eq!(
+        contract.price_observations.back().unwrap().price,
+        U128(TWO_CENTS)
+    );
+}
+
+#[test]
+#[should_panic(expected = "The minimum interval between price observations has not passed")]
+fn test_ping_before_interval() {
+    let mut contract = setup();
+    contract.record_price(ONE_USDC, FIFTY_VEX);
+
+    new_call(2);
+    let _ = contract.ping_price();
+}
+
+#[test]
+fn test_quote_far_from_twap() {
+    let mut contract = setup();
+    contract.insurance_fund = U128(ONE_USDC);
+    contract.funds_to_add = U128(ONE_USDC);
+
+    // Every quote is rejected before the observations cover the window
+    new_call(0);
+    assert!(!contract.quote_within_twap(ONE_USDC, FIFTY_VEX));
+    assert_eq!(contract.get_funds_to_add_in_vex(), None);
+
+    contract.record_price(ONE_USDC, FIFTY_VEX);
+
+    new_call(100);
+    assert_eq!(contract.get_funds_to_add_in_vex(), Some(U128(FIFTY_VEX)));
+    let _ = contract.handle_loss(2 * ONE_USDC);
+
+    // Twice as much VEX is needed as the TWAP says so nothing is deposited
+    new_call(100);
+    contract.ref_loss_view_callback(Ok(U128(2 * FIFTY_VEX)));
+    let loss_coverage = contract.loss_coverage.as_ref().unwrap();
+    assert_eq!(loss_coverage.step, LossCoverageStep::Deposit);
+    assert!(!loss_coverage.in_flight);
+
+    // A quote within 5% of the TWAP is accepted
+    new_call(100);
+    contract.resume_loss_coverage();
+    new_call(100);
+    contract.ref_loss_view_callback(Ok(U128(FIFTY_VEX * 104 / 100)));
+    assert!(contract.loss_coverage.as_ref().unwrap().in_flight);
+
+    // The swap is recorded as an observation
+    new_call(100);
+    contract.ref_loss_deposit_callback(Ok(U128(FIFTY_VEX)));
+    new_call(150);
+    contract.ref_loss_swap_callback(Ok(U128(ONE_USDC)));
+    assert_eq!(
+        contract.price_observations.back(),
+        Some(&PriceObservation {
+            timestamp: U64(150),
+            price: U128(TWO_CENTS),
+            price_cumulative: U128(150 * TWO_CENTS),
+        })
+    );
+}
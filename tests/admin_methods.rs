@@ -1,4 +1,6 @@
-use vex_contracts::Team;
+use near_sdk::json_types::{U128, U64};
+use vex_contracts::betting::view_betting::DisplayMatch;
+use vex_contracts::{Team, TimelockAction};
 mod setup;
 use crate::setup::*;
 
@@ -7,15 +9,17 @@ use crate::setup::*;
 async fn test_admin_methods() -> Result<(), Box<dyn std::error::Error>> {
     let TestSetup {
         alice,
+        bob,
         admin,
         main_contract,
+        sandbox,
         ..
     } = setup::TestSetup::new(false).await?;
 
     // Non admin tries to create a match
     let mut result = alice
         .call(main_contract.id(), "create_match")
-        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": 1.2, "in_odds_2": 1.6, "date": "17/08/2024"}))
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
         .transact()
         .await?;
 
@@ -24,12 +28,46 @@ async fn test_admin_methods() -> Result<(), Box<dyn std::error::Error>> {
     // Admin creates a match
     result = admin
         .call(main_contract.id(), "create_match")
-        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": 1.2, "in_odds_2": 1.6, "date": "17/08/2024"}))
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME, "external_ref": "1001"}))
         .transact()
         .await?;
 
     assert!(result.is_success(), "Admin failed to create a match");
 
+    // Admin tries to create the same match again
+    result = admin
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(ONE_ODDS), "in_odds_2": U128(ONE_ODDS), "date": "17/08/2024", "start_time": START_TIME}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin was able to overwrite an existing match"
+    );
+
+    // Admin tries to create a different match with the same external ref
+    result = admin
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(ONE_ODDS), "in_odds_2": U128(ONE_ODDS), "date": "18/08/2024", "start_time": START_TIME, "external_ref": "1001"}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin was able to reuse an external ref"
+    );
+
+    let match_view: DisplayMatch = main_contract
+        .view("get_match_by_external_ref")
+        .args_json(serde_json::json!({"external_ref": "1001"}))
+        .await?
+        .json()?;
+    assert_eq!(
+        match_view.match_id, "RUBY-Nexus-17/08/2024",
+        "Failed to get the match by its external ref"
+    );
+
     // Non admin tries to end betting
     result = end_betting(alice.clone(), main_contract.id(), "RUBY-Nexus-17/08/2024").await?;
 
@@ -62,19 +100,115 @@ async fn test_admin_methods() -> Result<(), Box<dyn std::error::Error>> {
     assert!(result.is_failure(), "Non admin was able to cancel match");
 
     // Non admin tries to change admin
-    result = change_admin(alice.clone(), main_contract.id(), alice.id().clone()).await?;
+    result = schedule_action(
+        alice.clone(),
+        main_contract.id(),
+        TimelockAction::ProposeAdmin {
+            new_admin: alice.id().clone(),
+            expires_at: None,
+        },
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Non admin was able to propose an admin"
+    );
+
+    // Admin tries to propose an admin with an expiry in the past
+    result = schedule_action(
+        admin.clone(),
+        main_contract.id(),
+        TimelockAction::ProposeAdmin {
+            new_admin: alice.id().clone(),
+            expires_at: Some(U64(1)),
+        },
+    )
+    .await?;
 
-    assert!(result.is_failure(), "Non admin was able to change admin");
+    assert!(
+        result.is_failure(),
+        "Admin was able to propose an admin with an expiry in the past"
+    );
+
+    // Admin proposes the wrong account and cancels the action
+    result = schedule_action(
+        admin.clone(),
+        main_contract.id(),
+        TimelockAction::ProposeAdmin {
+            new_admin: bob.id().clone(),
+            expires_at: None,
+        },
+    )
+    .await?;
+
+    assert!(result.is_success(), "Admin failed to propose an admin");
+    let action_id: U64 = result.json()?;
+
+    result = admin
+        .call(main_contract.id(), "cancel_action")
+        .args_json(serde_json::json!({"action_id": action_id}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to cancel the action");
+
+    result = execute_action(admin.clone(), main_contract.id(), action_id).await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin was able to execute a cancelled action"
+    );
 
     // Admin changes admin
-    result = change_admin(admin.clone(), main_contract.id(), alice.id().clone()).await?;
+    result = schedule_action(
+        admin.clone(),
+        main_contract.id(),
+        TimelockAction::ProposeAdmin {
+            new_admin: alice.id().clone(),
+            expires_at: None,
+        },
+    )
+    .await?;
+
+    assert!(result.is_success(), "Admin failed to propose an admin");
+    let action_id: U64 = result.json()?;
+
+    result = execute_action(admin.clone(), main_contract.id(), action_id).await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin was able to execute the action before the timelock delay"
+    );
+
+    wait_for_timelock(&sandbox, &main_contract, action_id).await?;
+
+    result = execute_action(alice.clone(), main_contract.id(), action_id).await?;
+
+    assert!(
+        result.is_failure(),
+        "Non admin was able to execute the action"
+    );
+
+    result = execute_action(admin.clone(), main_contract.id(), action_id).await?;
+
+    assert!(result.is_success(), "Admin failed to execute the action");
+
+    result = accept_admin(bob.clone(), main_contract.id()).await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to accept a proposal for Alice"
+    );
+
+    result = accept_admin(alice.clone(), main_contract.id()).await?;
 
-    assert!(result.is_success(), "Admin failed to change admin");
+    assert!(result.is_success(), "Alice failed to accept the proposal");
 
     // Old admin tries to create a match
     result = admin
         .call(main_contract.id(), "create_match")
-        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": 1.2, "in_odds_2": 1.6, "date": "17/08/2024"}))
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "18/08/2024", "start_time": START_TIME}))
         .transact()
         .await?;
 
@@ -83,7 +217,7 @@ async fn test_admin_methods() -> Result<(), Box<dyn std::error::Error>> {
     // New admin creates a match
     result = alice
         .call(main_contract.id(), "create_match")
-        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": 1.2, "in_odds_2": 1.6, "date": "17/08/2024"}))
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "18/08/2024", "start_time": START_TIME}))
         .transact()
         .await?;
 
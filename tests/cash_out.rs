@@ -0,0 +1,153 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::Gas;
+use vex_contracts::betting::view_betting::DisplayMatch;
+use vex_contracts::Team;
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_cash_out() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        bob,
+        admin,
+        main_contract,
+        usdc_token_contract,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    let match_id = "RUBY-Nexus-17/08/2024";
+
+    // Create a new match
+    let mut result = admin
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to create a match");
+
+    // Alice places a bet of 10 USDC on RUBY
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": match_id, "team": Team::Team1}}).to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's bet"
+    );
+
+    // Bob places a bet of 100 USDC on RUBY
+    result = ft_transfer_call(
+        bob.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(100 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": match_id, "team": Team::Team1}}).to_string(),
+    )
+    .await?;
+
+    assert!(result.is_success(), "ft_transfer_call failed on Bob's bet");
+
+    // Alice's bet is worth more as RUBY's odds have shortened
+    let cash_out_value: u128 = 10671987;
+    let value: U128 = main_contract
+        .view("get_cash_out_value")
+        .args_json(serde_json::json!({"bettor": alice.id(), "bet_id": U64(1)}))
+        .await?
+        .json()?;
+    assert_eq!(
+        value,
+        U128(cash_out_value),
+        "Alice's cash out value is incorrect"
+    );
+
+    // Bob tries to cash out Alice's bet
+    result = bob
+        .call(main_contract.id(), "cash_out")
+        .args_json(serde_json::json!({"bet_id": U64(1)}))
+        .gas(Gas::from_tgas(150))
+        .transact()
+        .await?;
+
+    assert!(result.is_failure(), "Bob managed to cash out Alice's bet");
+
+    // Alice cashes out her bet
+    result = alice
+        .call(main_contract.id(), "cash_out")
+        .args_json(serde_json::json!({"bet_id": U64(1)}))
+        .gas(Gas::from_tgas(150))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Alice failed to cash out her bet");
+
+    let balance: U128 = ft_balance_of(&usdc_token_contract, alice.id()).await?;
+    assert_eq!(
+        balance,
+        U128(90 * ONE_USDC + cash_out_value),
+        "Alice's balance is not correct after she cashed out"
+    );
+
+    let match_view: DisplayMatch = main_contract
+        .view("get_match")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .await?
+        .json()?;
+    assert_eq!(
+        match_view.team_1_real_bets,
+        U128(110 * ONE_USDC - cash_out_value),
+        "The cash out was not taken out of the pool"
+    );
+
+    // Alice tries to cash out her bet again
+    result = alice
+        .call(main_contract.id(), "cash_out")
+        .args_json(serde_json::json!({"bet_id": U64(1)}))
+        .gas(Gas::from_tgas(150))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice managed to cash out her bet twice"
+    );
+
+    // End betting
+    result = end_betting(admin.clone(), main_contract.id(), match_id).await?;
+
+    assert!(result.is_success(), "Admin failed to end betting");
+
+    // Bob tries to cash out after betting has ended
+    result = bob
+        .call(main_contract.id(), "cash_out")
+        .args_json(serde_json::json!({"bet_id": U64(2)}))
+        .gas(Gas::from_tgas(150))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob managed to cash out after betting ended"
+    );
+
+    // Finish the match, Alice cannot claim the bet she cashed out
+    result = finish_match(admin.clone(), main_contract.id(), match_id, Team::Team1).await?;
+
+    assert!(result.is_success(), "Admin failed to finish the match");
+
+    result = claim(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice managed to claim a bet she cashed out"
+    );
+
+    Ok(())
+}
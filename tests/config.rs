@@ -0,0 +1,207 @@
+use near_sdk::json_types::{U128, U64};
+use vex_contracts::{Config, Role, Team, TimelockAction};
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_config() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        bob,
+        admin,
+        main_contract,
+        sandbox,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    // The parameters given at init are in the config
+    let config: Config = main_contract.view("get_config").await?.json()?;
+    assert_eq!(
+        config.rewards_period,
+        U64(60_000_000_000),
+        "The rewards period is not the one given at init"
+    );
+    assert_eq!(
+        config.min_swap_amount,
+        U128(ONE_USDC),
+        "The minimum swap amount is not the one given at init"
+    );
+    assert_eq!(
+        config.market_margin_bps, 500,
+        "The default market margin is not 5%"
+    );
+    assert_eq!(
+        config.max_slippage_bps, 100,
+        "The default max slippage is not 1%"
+    );
+    assert_eq!(
+        config.max_stake_swap_amount,
+        U128(0),
+        "Stake swaps are split into tranches by default"
+    );
+
+    // Create a new match
+    let mut result = admin
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to create a match");
+
+    let winnings_before: U128 = main_contract
+        .view("get_potential_winnings")
+        .args_json(serde_json::json!({"match_id": "RUBY-Nexus-17/08/2024", "team": Team::Team1, "bet_amount": U128(10 * ONE_USDC)}))
+        .await?
+        .json()?;
+
+    // Alice tries to update the config without a role
+    let mut new_config = config.clone();
+    new_config.market_margin_bps = 1_000;
+
+    result = schedule_action(
+        alice.clone(),
+        main_contract.id(),
+        TimelockAction::UpdateConfig {
+            config: new_config.clone(),
+        },
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice was able to update the config without a role"
+    );
+
+    // Admin makes Bob a config manager
+    result = grant_role(
+        admin.clone(),
+        main_contract.id(),
+        bob.id(),
+        Role::ConfigManager,
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "Admin failed to make Bob a config manager"
+    );
+
+    // Bob tries to set profit shares that add up to more than 100%
+    let mut invalid_config = new_config.clone();
+    invalid_config.staking_share_bps = 8_000;
+
+    result = schedule_action(
+        bob.clone(),
+        main_contract.id(),
+        TimelockAction::UpdateConfig {
+            config: invalid_config,
+        },
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to set profit shares over 100%"
+    );
+
+    // Bob tries to set a max slippage over 10%
+    let mut invalid_config = new_config.clone();
+    invalid_config.max_slippage_bps = 1_500;
+
+    result = schedule_action(
+        bob.clone(),
+        main_contract.id(),
+        TimelockAction::UpdateConfig {
+            config: invalid_config,
+        },
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to set a max slippage over 10%"
+    );
+
+    // Bob tries to set a max stake swap amount that is not more than the minimum swap amount
+    let mut invalid_config = new_config.clone();
+    invalid_config.max_stake_swap_amount = U128(ONE_USDC);
+
+    result = schedule_action(
+        bob.clone(),
+        main_contract.id(),
+        TimelockAction::UpdateConfig {
+            config: invalid_config,
+        },
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to set a max stake swap amount below the minimum swap amount"
+    );
+
+    // Bob tries to remove the timelock delay
+    let mut invalid_config = new_config.clone();
+    invalid_config.timelock_delay = U64(0);
+
+    result = schedule_action(
+        bob.clone(),
+        main_contract.id(),
+        TimelockAction::UpdateConfig {
+            config: invalid_config,
+        },
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to remove the timelock delay"
+    );
+
+    // Bob doubles the market margin
+    result = schedule_action(
+        bob.clone(),
+        main_contract.id(),
+        TimelockAction::UpdateConfig { config: new_config },
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "Bob failed to schedule a config update"
+    );
+    let action_id: U64 = result.json()?;
+
+    wait_for_timelock(&sandbox, &main_contract, action_id).await?;
+
+    result = execute_action(bob.clone(), main_contract.id(), action_id).await?;
+
+    assert!(result.is_success(), "Bob failed to update the config");
+    assert!(
+        result
+            .logs()
+            .iter()
+            .any(|log| log.contains("config_updated")),
+        "No config_updated event was emitted"
+    );
+
+    let config: Config = main_contract.view("get_config").await?.json()?;
+    assert_eq!(
+        config.market_margin_bps, 1_000,
+        "The market margin was not updated"
+    );
+
+    let winnings_after: U128 = main_contract
+        .view("get_potential_winnings")
+        .args_json(serde_json::json!({"match_id": "RUBY-Nexus-17/08/2024", "team": Team::Team1, "bet_amount": U128(10 * ONE_USDC)}))
+        .await?
+        .json()?;
+    assert!(
+        winnings_after < winnings_before,
+        "The potential winnings did not drop after the market margin increased"
+    );
+
+    Ok(())
+}
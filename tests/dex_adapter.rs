@@ -0,0 +1,141 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::NearToken;
+use vex_contracts::{DexAdapter, RoutePool, TimelockAction};
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_dex_adapter() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        admin,
+        main_contract,
+        usdc_token_contract,
+        vex_token_contract,
+        ref_contract,
+        sandbox,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    // The pool given at init is the only pool in the route
+    let contract_info: serde_json::Value = main_contract.view("get_contract_info").await?.json()?;
+    let dex_adapter: DexAdapter = serde_json::from_value(contract_info["dex_adapter"].clone())?;
+    assert_eq!(
+        dex_adapter,
+        DexAdapter::RefFinance {
+            contract_id: ref_contract.id().clone(),
+            route: vec![RoutePool {
+                pool_id: U64(0),
+                token_out: usdc_token_contract.id().clone(),
+            }],
+        },
+        "The DEX adapter is not the Ref Finance pool given at init"
+    );
+
+    // The liquidity moves to a new pool
+    let mut result = admin
+        .call(ref_contract.id(), "add_simple_pool")
+        .args_json(serde_json::json!({
+            "tokens": vec![usdc_token_contract.id(), vex_token_contract.id()],
+            "fee": 25,
+        }))
+        .deposit(NearToken::from_millinear(100))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Failed to create a second ref pool");
+
+    let new_adapter = DexAdapter::RefFinance {
+        contract_id: ref_contract.id().clone(),
+        route: vec![RoutePool {
+            pool_id: U64(1),
+            token_out: usdc_token_contract.id().clone(),
+        }],
+    };
+
+    // Alice tries to change the DEX adapter
+    result = schedule_action(
+        alice.clone(),
+        main_contract.id(),
+        TimelockAction::SetDexAdapter {
+            dex_adapter: new_adapter.clone(),
+        },
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice was able to change the DEX adapter"
+    );
+
+    // Admin tries to set a route that does not end in USDC
+    result = schedule_action(
+        admin.clone(),
+        main_contract.id(),
+        TimelockAction::SetDexAdapter {
+            dex_adapter: DexAdapter::RefFinance {
+                contract_id: ref_contract.id().clone(),
+                route: vec![RoutePool {
+                    pool_id: U64(1),
+                    token_out: vex_token_contract.id().clone(),
+                }],
+            },
+        },
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin was able to set a route that does not end in USDC"
+    );
+
+    // Admin moves the swaps to the new pool
+    result = schedule_action(
+        admin.clone(),
+        main_contract.id(),
+        TimelockAction::SetDexAdapter {
+            dex_adapter: new_adapter.clone(),
+        },
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "Admin failed to schedule a DEX adapter change"
+    );
+    let action_id: U64 = result.json()?;
+
+    wait_for_timelock(&sandbox, &main_contract, action_id).await?;
+
+    result = execute_action(admin.clone(), main_contract.id(), action_id).await?;
+
+    assert!(
+        result.is_success(),
+        "Admin failed to change the DEX adapter"
+    );
+    assert!(
+        result
+            .logs()
+            .iter()
+            .any(|log| log.contains("set_dex_adapter")),
+        "No set_dex_adapter event was emitted"
+    );
+
+    let contract_info: serde_json::Value = main_contract.view("get_contract_info").await?.json()?;
+    let dex_adapter: DexAdapter = serde_json::from_value(contract_info["dex_adapter"].clone())?;
+    assert_eq!(dex_adapter, new_adapter, "The DEX adapter was not changed");
+
+    // Nothing was swapped so the contract has no deposits left in ref finance
+    let deposits: std::collections::HashMap<String, U128> = ref_contract
+        .view("get_deposits")
+        .args_json(serde_json::json!({ "account_id": main_contract.id() }))
+        .await?
+        .json()?;
+    assert!(
+        deposits.values().all(|amount| amount.0 == 0),
+        "The contract has deposits left in ref finance"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,194 @@
+use near_sdk::json_types::{U128, U64};
+use vex_contracts::betting::view_betting::DisplayMatch;
+use vex_contracts::Team;
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_draw_flow() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        bob,
+        admin,
+        main_contract,
+        usdc_token_contract,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    // Create a new three-way match
+    let mut result = admin
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "Chess", "team_1": "Carlsen", "team_2": "Nakamura", "in_odds_1": U128(25 * ONE_ODDS / 10), "in_odds_2": U128(32 * ONE_ODDS / 10), "in_odds_draw": U128(35 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_success(),
+        "Admin failed to create a three-way match"
+    );
+
+    // Create a new two-way match
+    result = admin
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_success(),
+        "Admin failed to create a two-way match"
+    );
+
+    let match_view: DisplayMatch = main_contract
+        .view("get_match")
+        .args_json(serde_json::json!({"match_id": "Carlsen-Nakamura-17/08/2024"}))
+        .await?
+        .json()?;
+    assert_eq!(
+        round_odds(match_view.team_1_odds),
+        2.38,
+        "Team 1 odds are incorrect after match is created"
+    );
+    assert_eq!(
+        round_odds(match_view.team_2_odds),
+        3.04,
+        "Team 2 odds are incorrect after match is created"
+    );
+    assert_eq!(
+        match_view.draw_odds.map(round_odds),
+        Some(3.33),
+        "Draw odds are incorrect after match is created"
+    );
+
+    // Alice tries to bet on a draw in the two-way match
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": "RUBY-Nexus-17/08/2024", "team": Team::Draw}})
+            .to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's draw bet in a two-way match"
+    );
+    assert!(
+        was_rejected(&result, "bet_rejected", "InvalidOutcome"),
+        "Alice's draw bet in a two-way match was not rejected"
+    );
+
+    let mut balance: U128 = ft_balance_of(&usdc_token_contract, alice.id()).await?;
+    assert_eq!(
+        balance,
+        U128(100 * ONE_USDC),
+        "Alice's balance is not correct after her draw bet in a two-way match"
+    );
+
+    // Alice places a bet of 10 USDC on a draw
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": "Carlsen-Nakamura-17/08/2024", "team": Team::Draw}})
+            .to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's draw bet"
+    );
+
+    let bet = main_contract
+        .view("get_bet")
+        .args_json(serde_json::json!({"bettor": alice.id(), "bet_id": U64(1)}))
+        .await;
+    assert!(bet.is_ok(), "Failed to get Alice's draw bet");
+
+    // Bob places a bet of 40 USDC on team 1
+    result = ft_transfer_call(
+        bob.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(40 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": "Carlsen-Nakamura-17/08/2024", "team": Team::Team1}})
+            .to_string(),
+    )
+    .await?;
+
+    assert!(result.is_success(), "ft_transfer_call failed on Bob's bet");
+
+    balance = ft_balance_of(&usdc_token_contract, main_contract.id()).await?;
+    assert_eq!(
+        balance,
+        U128(150 * ONE_USDC),
+        "Vex contract balance is not correct after the bets"
+    );
+
+    // End betting
+    result = end_betting(
+        admin.clone(),
+        main_contract.id(),
+        "Carlsen-Nakamura-17/08/2024",
+    )
+    .await?;
+
+    assert!(result.is_success(), "Admin failed to end betting");
+
+    // Finish the match as a draw
+    result = finish_match(
+        admin.clone(),
+        main_contract.id(),
+        "Carlsen-Nakamura-17/08/2024",
+        Team::Draw,
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "Admin failed to finish the match as a draw"
+    );
+
+    // Bob tries to claim the bet he lost
+    result = claim(bob.clone(), main_contract.id(), U64(2)).await?;
+
+    assert!(result.is_failure(), "Bob managed to claim a bet he lost");
+
+    // Alice claims the draw bet she won
+    result = claim(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(result.is_success(), "Alice failed to claim her draw bet");
+
+    let winnings: u128 = 32893807;
+    balance = ft_balance_of(&usdc_token_contract, alice.id()).await?;
+    assert_eq!(
+        balance,
+        U128(90 * ONE_USDC + winnings),
+        "Alice's balance is not correct after she claimed her draw bet"
+    );
+
+    // Admin tries to finish the two-way match as a draw
+    result = end_betting(admin.clone(), main_contract.id(), "RUBY-Nexus-17/08/2024").await?;
+
+    assert!(result.is_success(), "Admin failed to end betting");
+
+    result = finish_match(
+        admin.clone(),
+        main_contract.id(),
+        "RUBY-Nexus-17/08/2024",
+        Team::Draw,
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin managed to finish a two-way match as a draw"
+    );
+
+    Ok(())
+}
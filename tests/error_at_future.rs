@@ -18,7 +18,7 @@ async fn test_error_at_future() -> Result<(), Box<dyn std::error::Error>> {
     // Create a new match
     let mut result = admin
     .call(main_contract.id(), "create_match")
-    .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": 1.2, "in_odds_2": 1.6, "date": "17/08/2024"}))
+    .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
     .transact()
     .await?;
 
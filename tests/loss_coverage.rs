@@ -0,0 +1,111 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::Gas;
+use vex_contracts::{LossCoverage, Role, Team};
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_loss_coverage() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        bob,
+        admin,
+        main_contract,
+        usdc_token_contract,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    let match_id = "OG-FNATIC-20/08/2024";
+
+    // Bob tries to resume a loss coverage without the keeper role
+    let mut result = bob
+        .call(main_contract.id(), "resume_loss_coverage")
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to resume a loss coverage without a role"
+    );
+
+    result = grant_role(admin.clone(), main_contract.id(), bob.id(), Role::Keeper).await?;
+
+    assert!(result.is_success(), "Admin failed to make Bob a keeper");
+
+    // Bob tries to resume when no loss is being covered
+    result = bob
+        .call(main_contract.id(), "resume_loss_coverage")
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to resume when no loss was being covered"
+    );
+
+    // Create a match where the winning bet is larger than the insurance fund
+    result = admin
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "DOTA2", "team_1": "OG", "team_2": "FNATIC", "in_odds_1": U128(2 * ONE_ODDS), "in_odds_2": U128(11 * ONE_ODDS / 10), "date": "20/08/2024", "start_time": START_TIME}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to create a match");
+
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(30 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": match_id, "team": Team::Team1}}).to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's bet"
+    );
+
+    let total_staked_balance_before: U128 = main_contract
+        .view("get_total_staked_balance")
+        .await?
+        .json()?;
+
+    result = end_betting(admin.clone(), main_contract.id(), match_id).await?;
+
+    assert!(result.is_success(), "Admin failed to end betting");
+
+    result = finish_match(admin.clone(), main_contract.id(), match_id, Team::Team1).await?;
+
+    assert!(result.is_success(), "Admin failed to finish the match");
+
+    // The loss was covered with staked VEX and nothing is left in progress
+    let loss_coverage: Option<LossCoverage> =
+        main_contract.view("get_loss_coverage").await?.json()?;
+    assert!(
+        loss_coverage.is_none(),
+        "The loss coverage did not complete"
+    );
+
+    let total_staked_balance: U128 = main_contract
+        .view("get_total_staked_balance")
+        .await?
+        .json()?;
+    assert!(
+        total_staked_balance.0 < total_staked_balance_before.0,
+        "Staked VEX was not used to cover the loss"
+    );
+
+    let funds_to_add: U128 = main_contract.view("get_funds_to_add").await?.json()?;
+    assert_eq!(funds_to_add, U128(0), "The loss was not fully covered");
+
+    // Alice claims her winnings
+    result = claim(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(result.is_success(), "Alice failed to claim her winnings");
+
+    Ok(())
+}
@@ -0,0 +1,214 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::Gas;
+use vex_contracts::markets::view_markets::DisplayMarket;
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_market_flow() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        bob,
+        admin,
+        main_contract,
+        usdc_token_contract,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    let market_id = "Champions Winner-25/08/2024";
+
+    // Create a new market with four outcomes
+    let mut result = admin
+        .call(main_contract.id(), "create_market")
+        .args_json(serde_json::json!({
+            "game": "Valorant",
+            "name": "Champions Winner",
+            "outcome_labels": ["Sentinels", "Fnatic", "Paper Rex", "LOUD"],
+            "in_odds": [U128(2 * ONE_ODDS), U128(4 * ONE_ODDS), U128(5 * ONE_ODDS), U128(10 * ONE_ODDS)],
+            "date": "25/08/2024",
+            "start_time": START_TIME
+        }))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to create a market");
+
+    // Alice tries to create a market
+    result = alice
+        .call(main_contract.id(), "create_market")
+        .args_json(serde_json::json!({
+            "game": "Valorant",
+            "name": "Masters Winner",
+            "outcome_labels": ["Sentinels", "Fnatic"],
+            "in_odds": [U128(2 * ONE_ODDS), U128(2 * ONE_ODDS)],
+            "date": "25/08/2024",
+            "start_time": START_TIME
+        }))
+        .transact()
+        .await?;
+
+    assert!(result.is_failure(), "Alice managed to create a market");
+
+    let market_view: DisplayMarket = main_contract
+        .view("get_market")
+        .args_json(serde_json::json!({"market_id": market_id}))
+        .await?
+        .json()?;
+    let odds: Vec<f64> = market_view.odds.into_iter().map(round_odds).collect();
+    assert_eq!(
+        odds,
+        vec![2.0, 4.0, 5.01, 10.02],
+        "Odds are incorrect after market is created"
+    );
+
+    // Alice tries to bet on an outcome that does not exist
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"MarketBet" : {"market_id": market_id, "outcome": 4}}).to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's bet on an invalid outcome"
+    );
+    assert!(
+        was_rejected(&result, "market_bet_rejected", "InvalidOutcome"),
+        "Alice's bet on an invalid outcome was not rejected"
+    );
+
+    // Alice places a bet of 10 USDC on LOUD
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"MarketBet" : {"market_id": market_id, "outcome": 3}}).to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's bet"
+    );
+
+    let bet = main_contract
+        .view("get_market_bet")
+        .args_json(serde_json::json!({"bettor": alice.id(), "bet_id": U64(1)}))
+        .await;
+    assert!(bet.is_ok(), "Failed to get Alice's bet");
+
+    // Bob places a bet of 40 USDC on Sentinels
+    result = ft_transfer_call(
+        bob.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(40 * ONE_USDC),
+        serde_json::json!({"MarketBet" : {"market_id": market_id, "outcome": 0}}).to_string(),
+    )
+    .await?;
+
+    assert!(result.is_success(), "ft_transfer_call failed on Bob's bet");
+
+    let market_view: DisplayMarket = main_contract
+        .view("get_market")
+        .args_json(serde_json::json!({"market_id": market_id}))
+        .await?
+        .json()?;
+    assert_eq!(
+        market_view.real_bets,
+        vec![U128(40 * ONE_USDC), U128(0), U128(0), U128(10 * ONE_USDC)],
+        "Real bets are incorrect after the bets"
+    );
+
+    // End betting
+    result = admin
+        .call(main_contract.id(), "end_market_betting")
+        .args_json(serde_json::json!({"market_id": market_id}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to end betting");
+
+    // Bob tries to bet after betting has ended
+    result = ft_transfer_call(
+        bob.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"MarketBet" : {"market_id": market_id, "outcome": 0}}).to_string(),
+    )
+    .await?;
+
+    assert!(
+        was_rejected(&result, "market_bet_rejected", "BettingClosed"),
+        "Bob's bet after betting ended was not rejected"
+    );
+
+    // Admin tries to finish the market with an outcome that does not exist
+    result = admin
+        .call(main_contract.id(), "finish_market")
+        .args_json(serde_json::json!({"market_id": market_id, "winner": 4}))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin managed to finish the market with an invalid outcome"
+    );
+
+    // Finish the market with Sentinels as the winner
+    result = admin
+        .call(main_contract.id(), "finish_market")
+        .args_json(serde_json::json!({"market_id": market_id, "winner": 0}))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to finish the market");
+
+    // Alice tries to claim the bet she lost
+    result = alice
+        .call(main_contract.id(), "claim_market_bet")
+        .args_json(serde_json::json!({"bet_id": U64(1)}))
+        .gas(Gas::from_tgas(150))
+        .transact()
+        .await?;
+
+    assert!(result.is_failure(), "Alice managed to claim a bet she lost");
+
+    // Bob claims the bet he won
+    result = bob
+        .call(main_contract.id(), "claim_market_bet")
+        .args_json(serde_json::json!({"bet_id": U64(2)}))
+        .gas(Gas::from_tgas(150))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Bob failed to claim his bet");
+
+    let winnings: u128 = 79054466;
+    let balance: U128 = ft_balance_of(&usdc_token_contract, bob.id()).await?;
+    assert_eq!(
+        balance,
+        U128(60 * ONE_USDC + winnings),
+        "Bob's balance is not correct after he claimed his bet"
+    );
+
+    // Bob tries to claim the same bet again
+    result = bob
+        .call(main_contract.id(), "claim_market_bet")
+        .args_json(serde_json::json!({"bet_id": U64(2)}))
+        .gas(Gas::from_tgas(150))
+        .transact()
+        .await?;
+
+    assert!(result.is_failure(), "Bob managed to claim his bet twice");
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::Gas;
+use vex_contracts::Team;
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_parlay_flow() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        bob,
+        admin,
+        main_contract,
+        usdc_token_contract,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    // Create three matches
+    for (team_1, team_2) in [("RUBY", "Nexus"), ("Spirit", "Navi"), ("FaZe", "G2")] {
+        let result = admin
+            .call(main_contract.id(), "create_match")
+            .args_json(serde_json::json!({"game": "CSGO", "team_1": team_1, "team_2": team_2, "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
+            .transact()
+            .await?;
+
+        assert!(result.is_success(), "Admin failed to create a match");
+    }
+
+    // Alice tries to make a parlay with two legs on the same match
+    let mut result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Parlay" : {"legs": [
+            {"match_id": "RUBY-Nexus-17/08/2024", "team": Team::Team1},
+            {"match_id": "RUBY-Nexus-17/08/2024", "team": Team::Team2}
+        ]}})
+        .to_string(),
+    )
+    .await?;
+
+    assert!(
+        was_rejected(&result, "parlay_rejected", "InvalidParlay"),
+        "Alice's parlay with two legs on the same match was not rejected"
+    );
+
+    // Alice makes a parlay of 10 USDC on three matches
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Parlay" : {"legs": [
+            {"match_id": "RUBY-Nexus-17/08/2024", "team": Team::Team1},
+            {"match_id": "Spirit-Navi-17/08/2024", "team": Team::Team2},
+            {"match_id": "FaZe-G2-17/08/2024", "team": Team::Team1}
+        ]}})
+        .to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's parlay"
+    );
+
+    // Bob makes a parlay of 10 USDC on two matches
+    result = ft_transfer_call(
+        bob.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Parlay" : {"legs": [
+            {"match_id": "RUBY-Nexus-17/08/2024", "team": Team::Team2},
+            {"match_id": "Spirit-Navi-17/08/2024", "team": Team::Team2}
+        ]}})
+        .to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Bob's parlay"
+    );
+
+    // 10 * 1.6679 * 2.2200 * 1.6679 and 10 * 2.2200 * 2.2200 rounded down
+    let mut exposure: U128 = main_contract
+        .view("get_parlay_exposure")
+        .args_json(serde_json::json!({}))
+        .await?
+        .json()?;
+    assert_eq!(
+        exposure,
+        U128(61759332 + 49284098),
+        "Parlay exposure is incorrect after the parlays"
+    );
+
+    // RUBY win and FaZe-G2 is cancelled
+    for match_id in ["RUBY-Nexus-17/08/2024", "Spirit-Navi-17/08/2024"] {
+        result = end_betting(admin.clone(), main_contract.id(), match_id).await?;
+        assert!(result.is_success(), "Admin failed to end betting");
+    }
+
+    result = finish_match(
+        admin.clone(),
+        main_contract.id(),
+        "RUBY-Nexus-17/08/2024",
+        Team::Team1,
+    )
+    .await?;
+    assert!(result.is_success(), "Admin failed to finish the match");
+
+    result = cancel_match(admin.clone(), main_contract.id(), "FaZe-G2-17/08/2024").await?;
+    assert!(result.is_success(), "Admin failed to cancel the match");
+
+    // Alice settles Bob's parlay as its first leg lost
+    result = alice
+        .call(main_contract.id(), "settle_parlay")
+        .args_json(serde_json::json!({"bettor": bob.id(), "bet_id": U64(2)}))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_success(),
+        "Alice failed to settle Bob's lost parlay"
+    );
+
+    // Bob tries to claim his lost parlay
+    result = claim(bob.clone(), main_contract.id(), U64(2)).await?;
+
+    assert!(result.is_failure(), "Bob managed to claim a lost parlay");
+
+    // Alice tries to settle her parlay before every match is finished
+    result = alice
+        .call(main_contract.id(), "settle_parlay")
+        .args_json(serde_json::json!({"bettor": alice.id(), "bet_id": U64(1)}))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice managed to settle her parlay before every match finished"
+    );
+
+    // Navi win
+    result = finish_match(
+        admin.clone(),
+        main_contract.id(),
+        "Spirit-Navi-17/08/2024",
+        Team::Team2,
+    )
+    .await?;
+    assert!(result.is_success(), "Admin failed to finish the match");
+
+    // Alice tries to claim her parlay before it is settled
+    result = claim(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice managed to claim her parlay before it was settled"
+    );
+
+    result = alice
+        .call(main_contract.id(), "settle_parlay")
+        .args_json(serde_json::json!({"bettor": alice.id(), "bet_id": U64(1)}))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Alice failed to settle her parlay");
+
+    exposure = main_contract
+        .view("get_parlay_exposure")
+        .args_json(serde_json::json!({}))
+        .await?
+        .json()?;
+    assert_eq!(
+        exposure,
+        U128(0),
+        "Parlay exposure is not zero after the parlays were settled"
+    );
+
+    // Alice claims her parlay, the cancelled match drops out of the winnings
+    result = claim(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(result.is_success(), "Alice failed to claim her parlay");
+
+    // 10 * 1.6679 * 2.2200 rounded down
+    let winnings: u128 = 37027807;
+    let balance: U128 = ft_balance_of(&usdc_token_contract, alice.id()).await?;
+    assert_eq!(
+        balance,
+        U128(90 * ONE_USDC + winnings),
+        "Alice's balance is not correct after she claimed her parlay"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use near_sdk::json_types::{U128, U64};
+use vex_contracts::ft_on_transfer::FtTransferAction;
+use vex_contracts::{PauseState, Role, Team};
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_pause() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        bob,
+        admin,
+        main_contract,
+        usdc_token_contract,
+        vex_token_contract,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    // Create a new match
+    let mut result = admin
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to create a match");
+
+    // Bob tries to pause betting without the pauser role
+    result = bob
+        .call(main_contract.id(), "set_pause_state")
+        .args_json(serde_json::json!({"betting": true}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to pause betting without the pauser role"
+    );
+
+    // Admin makes Bob a pauser
+    result = grant_role(admin.clone(), main_contract.id(), bob.id(), Role::Pauser).await?;
+
+    assert!(result.is_success(), "Admin failed to make Bob a pauser");
+
+    // Bob pauses betting and staking
+    result = bob
+        .call(main_contract.id(), "set_pause_state")
+        .args_json(serde_json::json!({"betting": true, "staking": true}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Bob failed to pause the contract");
+
+    let pause_state: PauseState = main_contract.view("get_pause_state").await?.json()?;
+    assert!(
+        pause_state.betting && pause_state.staking,
+        "Betting and staking are not paused"
+    );
+    assert!(
+        !pause_state.claiming && !pause_state.stake_swaps,
+        "Claiming or stake swaps were paused"
+    );
+
+    // Alice tries to bet while betting is paused
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": "RUBY-Nexus-17/08/2024", "team": Team::Team1}})
+            .to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's bet"
+    );
+    assert!(
+        was_rejected(&result, "bet_rejected", "Paused"),
+        "Alice's bet was not rejected while betting was paused"
+    );
+
+    let balance: U128 = ft_balance_of(&usdc_token_contract, alice.id()).await?;
+    assert_eq!(
+        balance,
+        U128(100 * ONE_USDC),
+        "Alice's balance is not correct after the rejected bet"
+    );
+
+    // Alice tries to stake while staking is paused
+    result = ft_transfer_call(
+        alice.clone(),
+        vex_token_contract.id(),
+        main_contract.id(),
+        U128(100 * ONE_VEX),
+        serde_json::json!(FtTransferAction::Stake).to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's stake"
+    );
+    assert!(
+        was_rejected(&result, "stake_rejected", "Paused"),
+        "Alice's stake was not rejected while staking was paused"
+    );
+
+    // Bob unpauses betting and pauses claiming
+    result = bob
+        .call(main_contract.id(), "set_pause_state")
+        .args_json(serde_json::json!({"betting": false, "claiming": true}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Bob failed to change the pause state");
+
+    // Alice places a bet of 10 USDC on team 1
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": "RUBY-Nexus-17/08/2024", "team": Team::Team1}})
+            .to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's bet"
+    );
+    assert!(
+        !was_rejected(&result, "bet_rejected", "Paused"),
+        "Alice's bet was rejected after betting was unpaused"
+    );
+
+    result = finish_match(
+        admin.clone(),
+        main_contract.id(),
+        "RUBY-Nexus-17/08/2024",
+        Team::Team1,
+    )
+    .await?;
+
+    assert!(result.is_success(), "Admin failed to finish the match");
+
+    // Alice tries to claim while claiming is paused
+    result = claim(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice was able to claim while claiming was paused"
+    );
+
+    // Admin unpauses claiming
+    result = admin
+        .call(main_contract.id(), "set_pause_state")
+        .args_json(serde_json::json!({"claiming": false}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to unpause claiming");
+
+    // Alice claims her winnings
+    result = claim(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(result.is_success(), "Alice failed to claim her winnings");
+
+    Ok(())
+}
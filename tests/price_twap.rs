@@ -0,0 +1,107 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::Gas;
+use vex_contracts::{PriceObservation, Role};
+mod setup;
+use crate::setup::*;
+
+// The price of VEX in the sandbox pool, 500,000 USDC for 25,000,000 VEX
+const POOL_PRICE: u128 = ONE_ODDS / 50;
+
+#[tokio::test]
+
+async fn test_price_twap() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        bob,
+        admin,
+        main_contract,
+        sandbox,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    // Bob tries to ping the price without the keeper role
+    let mut result = bob
+        .call(main_contract.id(), "ping_price")
+        .gas(Gas::from_tgas(50))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to ping the price without a role"
+    );
+
+    result = grant_role(admin.clone(), main_contract.id(), bob.id(), Role::Keeper).await?;
+
+    assert!(result.is_success(), "Admin failed to make Bob a keeper");
+
+    // The price was observed twice by the admin in the setup
+    let mut observations: Vec<PriceObservation> =
+        main_contract.view("get_price_observations").await?.json()?;
+    assert_eq!(
+        observations.len(),
+        2,
+        "The price was not observed in the setup"
+    );
+
+    // Bob pings the price once the minimum interval has passed
+    sandbox.fast_forward(100).await?;
+    result = bob
+        .call(main_contract.id(), "ping_price")
+        .gas(Gas::from_tgas(50))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Bob failed to ping the price");
+
+    // Bob tries to ping the price again before the minimum interval has passed
+    result = bob
+        .call(main_contract.id(), "ping_price")
+        .gas(Gas::from_tgas(50))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to ping the price before the minimum interval"
+    );
+
+    observations = main_contract.view("get_price_observations").await?.json()?;
+    assert_eq!(observations.len(), 3, "Three prices were not observed");
+
+    // The quotes include the pool fee so the price is just under the pool's price
+    for observation in observations.iter() {
+        assert!(
+            observation.price.0 < POOL_PRICE && observation.price.0 > POOL_PRICE * 99 / 100,
+            "The observed price is not the pool's price"
+        );
+    }
+
+    // The TWAP over the time since the first ping is between the observed prices
+    let block_timestamp = sandbox.view_block().await?.timestamp();
+    let window = block_timestamp - observations[0].timestamp.0;
+    let twap: Option<U128> = main_contract
+        .view("get_twap")
+        .args_json(serde_json::json!({"window": U64(window)}))
+        .await?
+        .json()?;
+    let twap = twap.expect("There is no TWAP after the prices were observed");
+    let prices = observations.iter().map(|observation| observation.price);
+    let (low, high) = (prices.clone().min().unwrap(), prices.max().unwrap());
+    assert!(
+        twap >= low && twap <= high,
+        "The TWAP is not between the observed prices"
+    );
+
+    // There is no TWAP over a window longer than the observations
+    let twap: Option<U128> = main_contract
+        .view("get_twap")
+        .args_json(serde_json::json!({"window": U64(window + 1)}))
+        .await?
+        .json()?;
+    assert!(
+        twap.is_none(),
+        "There is a TWAP over a window without observations"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,335 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::Gas;
+use vex_contracts::betting::view_betting::DisplayMatch;
+use vex_contracts::{Config, MatchState, Role, Team, TimelockAction};
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_resolution_flow() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        bob,
+        admin,
+        main_contract,
+        usdc_token_contract,
+        sandbox,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    let match_id = "RUBY-Nexus-17/08/2024";
+
+    // Create a new match
+    let mut result = admin
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to create a match");
+
+    // Non admin tries to make themself a resolver
+    result = grant_role(
+        alice.clone(),
+        main_contract.id(),
+        alice.id(),
+        Role::Resolver,
+    )
+    .await?;
+
+    assert!(result.is_failure(), "Non admin was able to add a resolver");
+
+    // Admin makes Bob a resolver
+    result = grant_role(admin.clone(), main_contract.id(), bob.id(), Role::Resolver).await?;
+
+    assert!(result.is_success(), "Admin failed to add a resolver");
+
+    // Admin tries to remove the challenge period
+    let mut config: Config = main_contract.view("get_config").await?.json()?;
+    config.challenge_period = U64(0);
+
+    result = schedule_action(
+        admin.clone(),
+        main_contract.id(),
+        TimelockAction::UpdateConfig {
+            config: config.clone(),
+        },
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin was able to remove the challenge period"
+    );
+
+    // Admin shortens the challenge period to one hour through the timelock
+    config.challenge_period = U64(3_600_000_000_000);
+
+    result = schedule_action(
+        admin.clone(),
+        main_contract.id(),
+        TimelockAction::UpdateConfig { config },
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "Admin failed to schedule a new challenge period"
+    );
+    let action_id: U64 = result.json()?;
+
+    wait_for_timelock(&sandbox, &main_contract, action_id).await?;
+
+    result = execute_action(admin.clone(), main_contract.id(), action_id).await?;
+
+    assert!(
+        result.is_success(),
+        "Admin failed to set the challenge period"
+    );
+
+    // Alice places a bet of 10 USDC on team 1
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": match_id, "team": Team::Team1}}).to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's bet"
+    );
+
+    // Admin ends betting
+    result = end_betting(admin.clone(), main_contract.id(), match_id).await?;
+
+    assert!(result.is_success(), "Admin failed to end betting");
+
+    // Alice tries to propose a result
+    result = alice
+        .call(main_contract.id(), "propose_result")
+        .args_json(serde_json::json!({"match_id": match_id, "winner": Team::Team1}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice was able to propose a result without being a resolver"
+    );
+
+    // Bob proposes the wrong result
+    result = bob
+        .call(main_contract.id(), "propose_result")
+        .args_json(serde_json::json!({"match_id": match_id, "winner": Team::Team2}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Bob failed to propose a result");
+
+    let match_view: DisplayMatch = main_contract
+        .view("get_match")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .await?
+        .json()?;
+    assert!(
+        matches!(match_view.match_state, MatchState::PendingResult),
+        "Match is not PendingResult after a result is proposed"
+    );
+
+    // Alice tries to finalise the result before the challenge period has passed
+    result = alice
+        .call(main_contract.id(), "finalise_result")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice was able to finalise the result during the challenge period"
+    );
+
+    // Admin tries to dispute the result without being a resolver
+    result = admin
+        .call(main_contract.id(), "dispute_result")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin was able to dispute the result without being a resolver"
+    );
+
+    // Admin makes themself a resolver and disputes the result
+    result = grant_role(
+        admin.clone(),
+        main_contract.id(),
+        admin.id(),
+        Role::Resolver,
+    )
+    .await?;
+
+    assert!(result.is_success(), "Admin failed to become a resolver");
+
+    result = admin
+        .call(main_contract.id(), "dispute_result")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to dispute the result");
+
+    // Bob proposes the correct result
+    result = bob
+        .call(main_contract.id(), "propose_result")
+        .args_json(serde_json::json!({"match_id": match_id, "winner": Team::Team1}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Bob failed to propose a new result");
+
+    // Admin tries to dispute the match a second time
+    result = admin
+        .call(main_contract.id(), "dispute_result")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin was able to dispute the match a second time"
+    );
+
+    // Alice tries to claim before the result is final
+    result = claim(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice was able to claim before the result was final"
+    );
+
+    // Move past the challenge period
+    wait_for_challenge_period(&sandbox, &main_contract, match_id).await?;
+
+    // Bob tries to dispute the result after the challenge period has passed
+    result = bob
+        .call(main_contract.id(), "dispute_result")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to dispute the result after the challenge period"
+    );
+
+    // Alice tries to finalise the result before a second resolver has confirmed it
+    result = alice
+        .call(main_contract.id(), "finalise_result")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice was able to finalise a result only the proposer agrees with"
+    );
+
+    // The proposer tries to confirm their own result
+    result = bob
+        .call(main_contract.id(), "confirm_result")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "The proposer was able to confirm their own result"
+    );
+
+    // Admin confirms the result as a resolver
+    result = admin
+        .call(main_contract.id(), "confirm_result")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to confirm the result");
+
+    // Alice finalises the result
+    result = alice
+        .call(main_contract.id(), "finalise_result")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Alice failed to finalise the result");
+
+    let match_view: DisplayMatch = main_contract
+        .view("get_match")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .await?
+        .json()?;
+    assert_eq!(
+        match_view.winner,
+        Some(Team::Team1),
+        "The winner is not the proposed winner"
+    );
+
+    // Alice claims her winnings
+    result = claim(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(result.is_success(), "Alice failed to claim her winnings");
+
+    let balance: U128 = ft_balance_of(&usdc_token_contract, alice.id()).await?;
+    assert!(
+        balance > U128(100 * ONE_USDC),
+        "Alice's balance is not correct after claiming her winnings"
+    );
+
+    // Create a second match
+    let match_id = "RUBY-Nexus-18/08/2024";
+    result = admin
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "18/08/2024", "start_time": START_TIME}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to create a second match");
+
+    result = end_betting(admin.clone(), main_contract.id(), match_id).await?;
+
+    assert!(result.is_success(), "Admin failed to end betting");
+
+    // Bob proposes a result
+    result = bob
+        .call(main_contract.id(), "propose_result")
+        .args_json(serde_json::json!({"match_id": match_id, "winner": Team::Team2}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Bob failed to propose a result");
+
+    // Admin overrides the proposed result
+    result = finish_match(admin.clone(), main_contract.id(), match_id, Team::Team1).await?;
+
+    assert!(
+        result.is_success(),
+        "Admin failed to override the proposed result"
+    );
+    assert!(
+        result
+            .logs()
+            .iter()
+            .any(|log| log.contains("override_result")),
+        "No override event was emitted"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use near_sdk::json_types::{U128, U64};
+use vex_contracts::betting::view_betting::DisplayMatch;
+use vex_contracts::{MatchState, Role, Team};
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_resolver_quorum() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        bob,
+        admin,
+        main_contract,
+        usdc_token_contract,
+        sandbox,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    let carol = sandbox.dev_create_account().await?;
+    let dave = sandbox.dev_create_account().await?;
+
+    // Create two matches
+    for date in ["17/08/2024", "18/08/2024"] {
+        let result = admin
+            .call(main_contract.id(), "create_match")
+            .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": date, "start_time": START_TIME}))
+            .transact()
+            .await?;
+
+        assert!(result.is_success(), "Admin failed to create a match");
+    }
+
+    // Admin adds three resolvers
+    for resolver in [&bob, &carol, &dave] {
+        let result = grant_role(
+            admin.clone(),
+            main_contract.id(),
+            resolver.id(),
+            Role::Resolver,
+        )
+        .await?;
+
+        assert!(result.is_success(), "Admin failed to add a resolver");
+    }
+
+    let resolver_threshold: u32 = main_contract.view("get_resolver_threshold").await?.json()?;
+    assert_eq!(resolver_threshold, 2, "The default threshold is not 2");
+
+    // Alice places a bet of 10 USDC on team 1 in the first match
+    let mut result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": "RUBY-Nexus-17/08/2024", "team": Team::Team1}})
+            .to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's bet"
+    );
+
+    for match_id in ["RUBY-Nexus-17/08/2024", "RUBY-Nexus-18/08/2024"] {
+        result = end_betting(admin.clone(), main_contract.id(), match_id).await?;
+
+        assert!(result.is_success(), "Admin failed to end betting");
+    }
+
+    // Alice tries to submit a result
+    result = submit_result(
+        alice.clone(),
+        main_contract.id(),
+        "RUBY-Nexus-17/08/2024",
+        Team::Team1,
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice was able to submit a result without being a resolver"
+    );
+
+    // Bob submits a result for the first match
+    result = submit_result(
+        bob.clone(),
+        main_contract.id(),
+        "RUBY-Nexus-17/08/2024",
+        Team::Team1,
+    )
+    .await?;
+
+    assert!(result.is_success(), "Bob failed to submit a result");
+
+    let match_view: DisplayMatch = main_contract
+        .view("get_match")
+        .args_json(serde_json::json!({"match_id": "RUBY-Nexus-17/08/2024"}))
+        .await?
+        .json()?;
+    assert!(
+        matches!(match_view.match_state, MatchState::Current),
+        "Match was finished with one vote"
+    );
+
+    // Bob tries to vote twice
+    result = submit_result(
+        bob.clone(),
+        main_contract.id(),
+        "RUBY-Nexus-17/08/2024",
+        Team::Team1,
+    )
+    .await?;
+
+    assert!(result.is_failure(), "Bob was able to vote twice");
+
+    // Carol agrees with Bob which finishes the match
+    result = submit_result(
+        carol.clone(),
+        main_contract.id(),
+        "RUBY-Nexus-17/08/2024",
+        Team::Team1,
+    )
+    .await?;
+
+    assert!(result.is_success(), "Carol failed to submit a result");
+
+    let match_view: DisplayMatch = main_contract
+        .view("get_match")
+        .args_json(serde_json::json!({"match_id": "RUBY-Nexus-17/08/2024"}))
+        .await?
+        .json()?;
+    assert!(
+        matches!(match_view.match_state, MatchState::Finished),
+        "Match was not finished once the threshold was reached"
+    );
+
+    // Alice claims her winnings
+    result = claim(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(result.is_success(), "Alice failed to claim her winnings");
+
+    // Bob and Carol disagree on the second match
+    result = submit_result(
+        bob.clone(),
+        main_contract.id(),
+        "RUBY-Nexus-18/08/2024",
+        Team::Team1,
+    )
+    .await?;
+
+    assert!(result.is_success(), "Bob failed to submit a result");
+
+    result = submit_result(
+        carol.clone(),
+        main_contract.id(),
+        "RUBY-Nexus-18/08/2024",
+        Team::Team2,
+    )
+    .await?;
+
+    assert!(result.is_success(), "Carol failed to submit a result");
+
+    let match_view: DisplayMatch = main_contract
+        .view("get_match")
+        .args_json(serde_json::json!({"match_id": "RUBY-Nexus-18/08/2024"}))
+        .await?
+        .json()?;
+    assert!(
+        matches!(match_view.match_state, MatchState::Disputed),
+        "Match was not disputed after conflicting votes"
+    );
+
+    // Dave tries to settle the disputed match
+    result = submit_result(
+        dave.clone(),
+        main_contract.id(),
+        "RUBY-Nexus-18/08/2024",
+        Team::Team1,
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Dave was able to submit a result for a disputed match"
+    );
+
+    // Admin resolves the dispute
+    result = finish_match(
+        admin.clone(),
+        main_contract.id(),
+        "RUBY-Nexus-18/08/2024",
+        Team::Team1,
+    )
+    .await?;
+
+    assert!(result.is_success(), "Admin failed to resolve the dispute");
+
+    Ok(())
+}
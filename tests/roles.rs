@@ -0,0 +1,161 @@
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+use vex_contracts::{Role, Team, TimelockAction};
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_roles() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        bob,
+        admin,
+        main_contract,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    // Bob tries to create a match without a role
+    let mut result = bob
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to create a match without a role"
+    );
+
+    // Admin makes Bob a match creator and Alice a treasurer
+    result = grant_role(
+        admin.clone(),
+        main_contract.id(),
+        bob.id(),
+        Role::MatchCreator,
+    )
+    .await?;
+
+    assert!(result.is_success(), "Admin failed to grant a role to Bob");
+
+    result = grant_role(
+        admin.clone(),
+        main_contract.id(),
+        alice.id(),
+        Role::Treasurer,
+    )
+    .await?;
+
+    assert!(result.is_success(), "Admin failed to grant a role to Alice");
+
+    // Admin tries to grant Bob the same role twice
+    result = grant_role(
+        admin.clone(),
+        main_contract.id(),
+        bob.id(),
+        Role::MatchCreator,
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin was able to grant the same role twice"
+    );
+
+    // Bob tries to grant a role
+    result = grant_role(bob.clone(), main_contract.id(), bob.id(), Role::Treasurer).await?;
+
+    assert!(result.is_failure(), "Bob was able to grant a role");
+
+    let roles: Vec<Role> = main_contract
+        .view("get_roles")
+        .args_json(serde_json::json!({"account_id": bob.id()}))
+        .await?
+        .json()?;
+    assert_eq!(roles, vec![Role::MatchCreator], "Bob's roles are incorrect");
+
+    // Bob creates a match
+    result = bob
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Bob failed to create a match");
+
+    result = end_betting(bob.clone(), main_contract.id(), "RUBY-Nexus-17/08/2024").await?;
+
+    assert!(result.is_success(), "Bob failed to end betting");
+
+    // Bob tries to finish the match
+    result = finish_match(
+        bob.clone(),
+        main_contract.id(),
+        "RUBY-Nexus-17/08/2024",
+        Team::Team1,
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to finish a match as a match creator"
+    );
+
+    // Bob tries to take from the fees fund
+    result = schedule_action(
+        bob.clone(),
+        main_contract.id(),
+        TimelockAction::TakeFromFeesFund {
+            amount: U128(0),
+            receiver: bob.id().clone(),
+        },
+    )
+    .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to take from the fees fund as a match creator"
+    );
+
+    // Alice schedules taking from the fees fund
+    result = schedule_action(
+        alice.clone(),
+        main_contract.id(),
+        TimelockAction::TakeFromFeesFund {
+            amount: U128(0),
+            receiver: alice.id().clone(),
+        },
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "Alice failed to take from the fees fund as a treasurer"
+    );
+
+    // Admin revokes Bob's role
+    result = admin
+        .call(main_contract.id(), "revoke_role")
+        .args_json(serde_json::json!({"account_id": bob.id(), "role": Role::MatchCreator}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to revoke Bob's role");
+
+    let members: Vec<AccountId> = main_contract
+        .view("get_role_members")
+        .args_json(serde_json::json!({"role": Role::MatchCreator}))
+        .await?
+        .json()?;
+    assert!(members.is_empty(), "Bob is still a match creator");
+
+    // Bob tries to cancel the match after the role is revoked
+    result = cancel_match(bob.clone(), main_contract.id(), "RUBY-Nexus-17/08/2024").await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to cancel a match after the role was revoked"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use near_sdk::json_types::{U128, U64};
+use vex_contracts::betting::view_betting::DisplayMatch;
+use vex_contracts::{MatchState, Team};
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_scheduled_match() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        bob,
+        admin,
+        main_contract,
+        usdc_token_contract,
+        sandbox,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    let block_timestamp = sandbox.view_block().await?.timestamp();
+
+    // Admin tries to create a match that has already started
+    let mut result = admin
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": U64(block_timestamp)}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin managed to create a match that has already started"
+    );
+
+    // Create a new match that starts in one minute
+    let start_time = U64(block_timestamp + 60_000_000_000);
+    result = admin
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": start_time}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to create a match");
+
+    // Alice places a bet of 10 USDC on team 1 before the match starts
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": "RUBY-Nexus-17/08/2024", "team": Team::Team1}})
+            .to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's bet"
+    );
+
+    let bet = main_contract
+        .view("get_bet")
+        .args_json(serde_json::json!({"bettor": alice.id(), "bet_id": U64(1)}))
+        .await;
+    assert!(bet.is_ok(), "Failed to get Alice's bet");
+
+    // Move past the start time without the admin ending betting
+    sandbox.fast_forward(200).await?;
+
+    assert!(
+        sandbox.view_block().await?.timestamp() > start_time.0,
+        "The sandbox did not move past the start time"
+    );
+
+    let match_view: DisplayMatch = main_contract
+        .view("get_match")
+        .args_json(serde_json::json!({"match_id": "RUBY-Nexus-17/08/2024"}))
+        .await?
+        .json()?;
+    assert!(
+        matches!(match_view.match_state, MatchState::Current),
+        "Match is not shown as Current after its start time"
+    );
+
+    // Bob tries to bet after the match has started
+    result = ft_transfer_call(
+        bob.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": "RUBY-Nexus-17/08/2024", "team": Team::Team2}})
+            .to_string(),
+    )
+    .await?;
+
+    assert!(result.is_success(), "ft_transfer_call failed on Bob's bet");
+    assert!(
+        was_rejected(&result, "bet_rejected", "BettingClosed"),
+        "Bob's bet after the start time was not rejected"
+    );
+
+    let balance: U128 = ft_balance_of(&usdc_token_contract, bob.id()).await?;
+    assert_eq!(
+        balance,
+        U128(100 * ONE_USDC),
+        "Bob's balance is not correct after the rejected bet"
+    );
+
+    // Admin finishes the match without ending betting
+    result = finish_match(
+        admin.clone(),
+        main_contract.id(),
+        "RUBY-Nexus-17/08/2024",
+        Team::Team1,
+    )
+    .await?;
+
+    assert!(result.is_success(), "Admin failed to finish the match");
+
+    // Alice claims her winnings
+    result = claim(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(result.is_success(), "Alice failed to claim her winnings");
+
+    let balance: U128 = ft_balance_of(&usdc_token_contract, alice.id()).await?;
+    assert!(
+        balance > U128(100 * ONE_USDC),
+        "Alice's balance is not correct after claiming her winnings"
+    );
+
+    Ok(())
+}
@@ -8,13 +8,24 @@ use near_workspaces::network::Sandbox;
 use near_workspaces::result::ExecutionFinalResult;
 use near_workspaces::{Account, Contract, Result, Worker};
 use serde_json::json;
-use vex_contracts::Team;
+use vex_contracts::staking::view_staking::WithdrawalTicketInfo;
+use vex_contracts::{
+    Config, DexAdapter, PendingAction, PriceObservation, ProposedResult, Role, RoutePool, Team,
+    TimelockAction,
+};
 
 const FIFTY_NEAR: NearToken = NearToken::from_near(50);
 const FT_WASM_FILEPATH: &str = "./tests/external_contracts_wasm/fungible_token.wasm";
 const REF_WASM_FILEPATH: &str = "./tests/external_contracts_wasm/ref_exchange_release.wasm";
+// The contract before its state was versioned
+#[allow(dead_code)]
+pub const V0_WASM_FILEPATH: &str = "./tests/external_contracts_wasm/vex_contracts_v0.wasm";
 pub const ONE_USDC: u128 = 1_000_000;
 pub const ONE_VEX: u128 = 1_000_000_000_000_000_000;
+pub const ONE_ODDS: u128 = 1_000_000_000_000_000_000;
+// A start time far enough in the future that betting never closes automatically
+#[allow(dead_code)]
+pub const START_TIME: U64 = U64(4_102_444_800_000_000_000);
 
 pub struct TestSetup {
     pub alice: Account,
@@ -23,11 +34,39 @@ pub struct TestSetup {
     pub main_contract: Contract,
     pub usdc_token_contract: Contract,
     pub vex_token_contract: Contract,
+    pub ref_contract: Contract,
     pub sandbox: Worker<Sandbox>,
 }
 
 impl TestSetup {
+    #[allow(dead_code)]
     pub async fn new(incorrect_ft: bool) -> Result<Self, Box<dyn std::error::Error>> {
+        let contract_wasm = near_workspaces::compile_project("./").await?;
+        // let contract_wasm = std::fs::read("./target/wasm32-unknown-unknown/release/vex_contracts.wasm")?;
+
+        let setup = Self::new_with_wasm(incorrect_ft, &contract_wasm, false).await?;
+
+        // Quotes are rejected until the price observations cover the TWAP window
+        wait_for_twap(&setup.sandbox, &setup.admin, &setup.main_contract).await?;
+
+        Ok(setup)
+    }
+
+    // Sets up the tests with the contract from before its state was versioned
+    #[allow(dead_code)]
+    pub async fn new_v0() -> Result<Self, Box<dyn std::error::Error>> {
+        let v0_wasm = std::fs::read(V0_WASM_FILEPATH)?;
+
+        Self::new_with_wasm(false, &v0_wasm, true).await
+    }
+
+    // Sets up the tests with the given code deployed to the main contract
+    // the version 0 contract is initialized with its own arguments
+    pub async fn new_with_wasm(
+        incorrect_ft: bool,
+        contract_wasm: &[u8],
+        v0: bool,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         // Create sandbox
         let sandbox = near_workspaces::sandbox().await?;
 
@@ -44,9 +83,7 @@ impl TestSetup {
         // Set up new account for treasury
 
         // Deploy contract
-        let contract_wasm = near_workspaces::compile_project("./").await?;
-        // let contract_wasm = std::fs::read("./target/wasm32-unknown-unknown/release/vex_contracts.wasm")?;
-        let main_contract = main_contract_account.deploy(&contract_wasm).await?.unwrap();
+        let main_contract = main_contract_account.deploy(contract_wasm).await?.unwrap();
 
         // Deploy USDC token contract
         let ft_wasm = std::fs::read(FT_WASM_FILEPATH)?;
@@ -169,19 +206,44 @@ impl TestSetup {
             usdc_token_contract.as_account().id().clone()
         };
 
-        res = main_contract
-            .call("init")
-            .args_json(serde_json::json!({
+        let init_args = if v0 {
+            serde_json::json!({
                 "admin": admin.id(),
                 "usdc_token_contract": ft_contract_id,
                 "vex_token_contract": vex_token_contract.id(),
                 "treasury": admin.id(),
                 "ref_contract": ref_contract.id(),
-                "ref_pool_id": "0",
-                "rewards_period": "60000000000", // 1 minute
-                "unstake_time_buffer": "30000000000", // 30 seconds
-                "min_swap_amount": "1000000", // 1 USDC
-            }))
+                "ref_pool_id": U64(0),
+                "rewards_period": U64(60_000_000_000), // 1 minute
+                "unstake_time_buffer": U64(30_000_000_000), // 30 seconds
+                "min_swap_amount": U128(ONE_USDC),
+            })
+        } else {
+            serde_json::json!({
+                "admin": admin.id(),
+                "usdc_token_contract": ft_contract_id,
+                "vex_token_contract": vex_token_contract.id(),
+                "treasury": admin.id(),
+                "dex_adapter": DexAdapter::RefFinance {
+                    contract_id: ref_contract.id().clone(),
+                    route: vec![RoutePool {
+                        pool_id: U64(0),
+                        token_out: ft_contract_id.clone(),
+                    }],
+                },
+                "config": Config {
+                    rewards_period: U64(60_000_000_000), // 1 minute
+                    unstake_time_buffer: U64(30_000_000_000), // 30 seconds
+                    min_swap_amount: U128(ONE_USDC),
+                    twap_window: U64(600_000_000_000), // 10 minutes
+                    ..Config::default()
+                },
+            })
+        };
+
+        res = main_contract
+            .call("init")
+            .args_json(init_args)
             .transact()
             .await?;
 
@@ -287,6 +349,7 @@ impl TestSetup {
             main_contract,
             usdc_token_contract,
             vex_token_contract,
+            ref_contract,
             sandbox,
         })
     }
@@ -355,6 +418,22 @@ pub async fn ft_transfer_call(
     Ok(transfer)
 }
 
+// Checks whether a call emitted a rejection event with the given reason
+#[allow(dead_code)]
+pub fn was_rejected(result: &ExecutionFinalResult, event: &str, reason: &str) -> bool {
+    result.logs().iter().any(|log| {
+        log.starts_with("EVENT_JSON:")
+            && log.contains(&format!("\"event\":\"{}\"", event))
+            && log.contains(&format!("\"reason\":\"{}\"", reason))
+    })
+}
+
+// Converts fixed-point odds to a float rounded to two decimal places
+#[allow(dead_code)]
+pub fn round_odds(odds: U128) -> f64 {
+    (odds.0 as f64 / ONE_ODDS as f64 * 100.0).round() / 100.0
+}
+
 #[allow(dead_code)]
 pub async fn claim(
     account: Account,
@@ -388,6 +467,39 @@ pub async fn finish_match(
     Ok(finish_match)
 }
 
+#[allow(dead_code)]
+pub async fn submit_result(
+    account: Account,
+    main_contract_id: &AccountId,
+    match_id: &str,
+    winner: Team,
+) -> Result<ExecutionFinalResult, Box<dyn std::error::Error>> {
+    let submit_result = account
+        .call(main_contract_id, "submit_result")
+        .args_json(serde_json::json!({"match_id": match_id, "winner": winner}))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    Ok(submit_result)
+}
+
+#[allow(dead_code)]
+pub async fn grant_role(
+    account: Account,
+    main_contract_id: &AccountId,
+    account_id: &AccountId,
+    role: Role,
+) -> Result<ExecutionFinalResult, Box<dyn std::error::Error>> {
+    let grant_role = account
+        .call(main_contract_id, "grant_role")
+        .args_json(serde_json::json!({"account_id": account_id, "role": role}))
+        .transact()
+        .await?;
+
+    Ok(grant_role)
+}
+
 #[allow(dead_code)]
 pub async fn end_betting(
     account: Account,
@@ -421,19 +533,126 @@ pub async fn cancel_match(
 }
 
 #[allow(dead_code)]
-pub async fn change_admin(
+pub async fn schedule_action(
     account: Account,
     main_contract_id: &AccountId,
-    new_admin: AccountId,
+    action: TimelockAction,
 ) -> Result<ExecutionFinalResult, Box<dyn std::error::Error>> {
-    let change_admin = account
-        .call(main_contract_id, "change_admin")
-        .args_json(serde_json::json!({"new_admin": new_admin}))
+    let schedule_action = account
+        .call(main_contract_id, "schedule_action")
+        .args_json(serde_json::json!({"action": action}))
         .gas(Gas::from_tgas(50))
         .transact()
         .await?;
 
-    Ok(change_admin)
+    Ok(schedule_action)
+}
+
+#[allow(dead_code)]
+pub async fn execute_action(
+    account: Account,
+    main_contract_id: &AccountId,
+    action_id: U64,
+) -> Result<ExecutionFinalResult, Box<dyn std::error::Error>> {
+    let execute_action = account
+        .call(main_contract_id, "execute_action")
+        .args_json(serde_json::json!({"action_id": action_id}))
+        .gas(Gas::from_tgas(100))
+        .transact()
+        .await?;
+
+    Ok(execute_action)
+}
+
+// Fast forwards the sandbox until a scheduled action can be executed
+#[allow(dead_code)]
+pub async fn wait_for_timelock(
+    sandbox: &Worker<Sandbox>,
+    main_contract: &Contract,
+    action_id: U64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pending_action: PendingAction = main_contract
+        .view("get_pending_action")
+        .args_json(serde_json::json!({"action_id": action_id}))
+        .await?
+        .json()?;
+
+    while sandbox.view_block().await?.timestamp() < pending_action.eta.0 {
+        sandbox.fast_forward(10_000).await?;
+    }
+
+    Ok(())
+}
+
+// Pings the price, fast forwards the sandbox until the observation covers the TWAP window
+// and pings the price again so there is an observation within the window
+pub async fn wait_for_twap(
+    sandbox: &Worker<Sandbox>,
+    admin: &Account,
+    main_contract: &Contract,
+) -> Result<(), Box<dyn std::error::Error>> {
+    ping_price(admin, main_contract).await?;
+
+    let observations: Vec<PriceObservation> =
+        main_contract.view("get_price_observations").await?.json()?;
+    let config: Config = main_contract.view("get_config").await?.json()?;
+    let covered_at = observations[0].timestamp.0 + config.twap_window.0;
+
+    while sandbox.view_block().await?.timestamp() < covered_at {
+        sandbox.fast_forward(100).await?;
+    }
+
+    ping_price(admin, main_contract).await?;
+
+    Ok(())
+}
+
+async fn ping_price(
+    admin: &Account,
+    main_contract: &Contract,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let result = admin
+        .call(main_contract.id(), "ping_price")
+        .gas(Gas::from_tgas(50))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to ping the price");
+
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub async fn wait_for_challenge_period(
+    sandbox: &Worker<Sandbox>,
+    main_contract: &Contract,
+    match_id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let proposed_result: ProposedResult = main_contract
+        .view("get_proposed_result")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .await?
+        .json()?;
+
+    while sandbox.view_block().await?.timestamp() < proposed_result.finalise_after.0 {
+        sandbox.fast_forward(10_000).await?;
+    }
+
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub async fn accept_admin(
+    account: Account,
+    main_contract_id: &AccountId,
+) -> Result<ExecutionFinalResult, Box<dyn std::error::Error>> {
+    let accept_admin = account
+        .call(main_contract_id, "accept_admin")
+        .gas(Gas::from_tgas(50))
+        .transact()
+        .await?;
+
+    Ok(accept_admin)
 }
 
 #[allow(dead_code)]
@@ -451,12 +670,12 @@ pub async fn perform_stake_swap(
 }
 
 #[allow(dead_code)]
-pub async fn unstake_all(
+pub async fn request_unstake_all(
     account: Account,
     main_contract_id: &AccountId,
 ) -> Result<ExecutionFinalResult, Box<dyn std::error::Error>> {
     let unstake = account
-        .call(main_contract_id, "unstake_all")
+        .call(main_contract_id, "request_unstake_all")
         .gas(Gas::from_tgas(50))
         .transact()
         .await?;
@@ -465,13 +684,13 @@ pub async fn unstake_all(
 }
 
 #[allow(dead_code)]
-pub async fn unstake(
+pub async fn request_unstake(
     account: Account,
     main_contract_id: &AccountId,
     amount: U128,
 ) -> Result<ExecutionFinalResult, Box<dyn std::error::Error>> {
     let unstake = account
-        .call(main_contract_id, "unstake")
+        .call(main_contract_id, "request_unstake")
         .args_json(serde_json::json!({"amount": amount}))
         .gas(Gas::from_tgas(50))
         .transact()
@@ -479,3 +698,43 @@ pub async fn unstake(
 
     Ok(unstake)
 }
+
+#[allow(dead_code)]
+pub async fn withdraw_unstaked(
+    account: Account,
+    main_contract_id: &AccountId,
+    ticket_id: U64,
+) -> Result<ExecutionFinalResult, Box<dyn std::error::Error>> {
+    let withdraw = account
+        .call(main_contract_id, "withdraw_unstaked")
+        .args_json(serde_json::json!({"ticket_id": ticket_id}))
+        .gas(Gas::from_tgas(50))
+        .transact()
+        .await?;
+
+    Ok(withdraw)
+}
+
+#[allow(dead_code)]
+pub async fn wait_for_withdrawal(
+    sandbox: &Worker<Sandbox>,
+    main_contract: &Contract,
+    account_id: &AccountId,
+    ticket_id: U64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tickets: Vec<WithdrawalTicketInfo> = main_contract
+        .view("get_withdrawal_tickets")
+        .args_json(serde_json::json!({"account_id": account_id}))
+        .await?
+        .json()?;
+    let ticket = tickets
+        .iter()
+        .find(|ticket| ticket.ticket_id == ticket_id)
+        .expect("The withdrawal ticket does not exist");
+
+    while sandbox.view_block().await?.timestamp() < ticket.withdrawable_timestamp.0 {
+        sandbox.fast_forward(10_000).await?;
+    }
+
+    Ok(())
+}
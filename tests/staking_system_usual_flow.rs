@@ -1,11 +1,12 @@
 // TODO: Make these tests good and comprehensive, add proper variable names and comments, calculate and check exact or at least rounded balances
 // Check you can't do actions you shouldn't be able to do at different stages
-// Check that unstake_all deletes the user from the map
+// Check that request_unstake_all deletes the user from the map
 // Consider merging with other tests
 
-use near_sdk::json_types::U128;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::Gas;
 use vex_contracts::ft_on_transfer::FtTransferAction;
-use vex_contracts::{MatchStakeInfo, Team};
+use vex_contracts::{LossCoverage, MatchStakeInfo, StakeSwap, Team};
 mod setup;
 use crate::setup::*;
 
@@ -20,6 +21,7 @@ async fn test_staking_system_usual_flow() -> Result<(), Box<dyn std::error::Erro
         usdc_token_contract,
         vex_token_contract,
         sandbox,
+        ..
     } = setup::TestSetup::new(false).await?;
 
     // Add 25 USDC to the insurance pool
@@ -52,6 +54,32 @@ async fn test_staking_system_usual_flow() -> Result<(), Box<dyn std::error::Erro
         "Insurance pool is not correct after adding to insurance pool"
     );
 
+    // Alice tries to stake less than 50 $VEX
+    result = ft_transfer_call(
+        alice.clone(),
+        vex_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_VEX),
+        serde_json::json!(FtTransferAction::Stake).to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's stake of less than 50 $VEX"
+    );
+    assert!(
+        was_rejected(&result, "stake_rejected", "StakeTooSmall"),
+        "Alice's stake of less than 50 $VEX was not rejected"
+    );
+
+    balance = ft_balance_of(&vex_token_contract, alice.id()).await?;
+    assert_eq!(
+        balance,
+        U128(100 * ONE_VEX),
+        "Alice's balance is not correct after her stake of less than 50 $VEX"
+    );
+
     // Alice stakes 50 $VEX
     result = ft_transfer_call(
         alice.clone(),
@@ -122,7 +150,7 @@ async fn test_staking_system_usual_flow() -> Result<(), Box<dyn std::error::Erro
     // Create a new match
     result = admin
         .call(main_contract.id(), "create_match")
-        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": 1.2, "in_odds_2": 1.6, "date": "17/08/2024"}))
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
         .transact()
         .await?;
 
@@ -273,6 +301,25 @@ async fn test_staking_system_usual_flow() -> Result<(), Box<dyn std::error::Erro
 
     assert!(result.is_success(), "perform_stake_swap failed");
 
+    // Check that the stake swap completed
+    let stake_swap: Option<StakeSwap> = main_contract.view("get_stake_swap").await?.json()?;
+    assert!(
+        stake_swap.is_none(),
+        "Stake swap is still in progress after it was performed"
+    );
+
+    // Admin tries to resume a stake swap that completed
+    result = admin
+        .call(main_contract.id(), "resume_stake_swap")
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin was able to resume a stake swap that completed"
+    );
+
     // Check that the rewards are still in the queue
     let staking_rewards_queue: Vec<MatchStakeInfo> = main_contract
         .view("get_staking_rewards_queue")
@@ -343,7 +390,7 @@ async fn test_staking_system_usual_flow() -> Result<(), Box<dyn std::error::Erro
     // Add another game
     result = admin
         .call(main_contract.id(), "create_match")
-        .args_json(serde_json::json!({"game": "Overwatch", "team_1": "Dallas_Fuel", "team_2": "Seoul_Dynasty", "in_odds_1": 1.0, "in_odds_2": 1.0, "date": "18/08/2024"}))
+        .args_json(serde_json::json!({"game": "Overwatch", "team_1": "Dallas_Fuel", "team_2": "Seoul_Dynasty", "in_odds_1": U128(ONE_ODDS), "in_odds_2": U128(ONE_ODDS), "date": "18/08/2024", "start_time": START_TIME}))
         .transact()
         .await?;
 
@@ -551,7 +598,7 @@ async fn test_staking_system_usual_flow() -> Result<(), Box<dyn std::error::Erro
     // Create a new match
     result = admin
         .call(main_contract.id(), "create_match")
-        .args_json(serde_json::json!({"game": "DOTA2", "team_1": "OG", "team_2": "FNATIC", "in_odds_1": 2.0, "in_odds_2": 1.1, "date": "20/08/2024"}))
+        .args_json(serde_json::json!({"game": "DOTA2", "team_1": "OG", "team_2": "FNATIC", "in_odds_1": U128(2 * ONE_ODDS), "in_odds_2": U128(11 * ONE_ODDS / 10), "date": "20/08/2024", "start_time": START_TIME}))
         .transact()
         .await?;
 
@@ -627,6 +674,14 @@ async fn test_staking_system_usual_flow() -> Result<(), Box<dyn std::error::Erro
         total_staked_balance_three.0 - new_total_staked_balance.0
     );
 
+    // Check that the loss coverage completed
+    let loss_coverage: Option<LossCoverage> =
+        main_contract.view("get_loss_coverage").await?.json()?;
+    assert!(
+        loss_coverage.is_none(),
+        "Loss coverage is still in progress after the loss match has finished"
+    );
+
     // Check that both user's staked balance has decreased
     let alice_fourth_staked_balance: U128 = main_contract
         .view("get_user_staked_bal")
@@ -659,9 +714,27 @@ async fn test_staking_system_usual_flow() -> Result<(), Box<dyn std::error::Erro
     // Alice unstakes and withdraws all her VEX
     let alice_balance_before = ft_balance_of(&vex_token_contract, alice.id()).await?;
 
-    result = unstake_all(alice.clone(), main_contract.id()).await?;
+    result = request_unstake_all(alice.clone(), main_contract.id()).await?;
+
+    assert!(
+        result.is_success(),
+        "request_unstake_all failed on Alice's unstake"
+    );
+    let alice_ticket_id: U64 = result.json()?;
+
+    // Alice cannot withdraw until the cooldown has passed
+    result = withdraw_unstaked(alice.clone(), main_contract.id(), alice_ticket_id).await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice was able to withdraw before the cooldown passed"
+    );
+
+    wait_for_withdrawal(&sandbox, &main_contract, alice.id(), alice_ticket_id).await?;
+
+    result = withdraw_unstaked(alice.clone(), main_contract.id(), alice_ticket_id).await?;
 
-    assert!(result.is_success(), "unstake_all failed on Alice's unstake");
+    assert!(result.is_success(), "Alice failed to withdraw the ticket");
 
     // Check that Alice's staked balance is zero
     let alice_staked_balance: Option<U128> = main_contract
@@ -686,9 +759,19 @@ async fn test_staking_system_usual_flow() -> Result<(), Box<dyn std::error::Erro
     // Bob unstakes and withdraws some of his VEX
     let bob_balance_before = ft_balance_of(&vex_token_contract, bob.id()).await?;
 
-    result = unstake(bob.clone(), main_contract.id(), U128(10 * ONE_VEX)).await?;
+    result = request_unstake(bob.clone(), main_contract.id(), U128(10 * ONE_VEX)).await?;
+
+    assert!(
+        result.is_success(),
+        "request_unstake failed on Bob's unstake"
+    );
+    let bob_ticket_id: U64 = result.json()?;
+
+    wait_for_withdrawal(&sandbox, &main_contract, bob.id(), bob_ticket_id).await?;
+
+    result = withdraw_unstaked(bob.clone(), main_contract.id(), bob_ticket_id).await?;
 
-    assert!(result.is_success(), "unstake failed on Bob's unstake");
+    assert!(result.is_success(), "Bob failed to withdraw the ticket");
 
     // Check that Bob's staked balance has decreased
     let bob_staked_balance: U128 = main_contract
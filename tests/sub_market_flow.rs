@@ -0,0 +1,243 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::Gas;
+use vex_contracts::betting::view_betting::DisplayMatch;
+use vex_contracts::markets::view_markets::DisplayMarket;
+use vex_contracts::{LineKind, Team};
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_sub_market_flow() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        bob,
+        admin,
+        main_contract,
+        usdc_token_contract,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    let match_id = "RUBY-Nexus-17/08/2024";
+    let over_under_id = "RUBY-Nexus-17/08/2024-OverUnder-25";
+    let handicap_id = "RUBY-Nexus-17/08/2024-Handicap--15";
+
+    // Create a new match
+    let mut result = admin
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to create a match");
+
+    // Add an over/under 2.5 maps market
+    result = admin
+        .call(main_contract.id(), "create_sub_market")
+        .args_json(serde_json::json!({"match_id": match_id, "kind": LineKind::OverUnder, "line": 25, "in_odds_1": U128(19 * ONE_ODDS / 10), "in_odds_2": U128(19 * ONE_ODDS / 10)}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_success(),
+        "Admin failed to create an over/under market"
+    );
+
+    // Add a RUBY -1.5 maps handicap market
+    result = admin
+        .call(main_contract.id(), "create_sub_market")
+        .args_json(serde_json::json!({"match_id": match_id, "kind": LineKind::Handicap, "line": -15, "in_odds_1": U128(25 * ONE_ODDS / 10), "in_odds_2": U128(15 * ONE_ODDS / 10)}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_success(),
+        "Admin failed to create a handicap market"
+    );
+
+    // Admin tries to create an over/under market with a negative line
+    result = admin
+        .call(main_contract.id(), "create_sub_market")
+        .args_json(serde_json::json!({"match_id": match_id, "kind": LineKind::OverUnder, "line": -5, "in_odds_1": U128(19 * ONE_ODDS / 10), "in_odds_2": U128(19 * ONE_ODDS / 10)}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin managed to create an over/under market with a negative line"
+    );
+
+    let match_view: DisplayMatch = main_contract
+        .view("get_match")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .await?
+        .json()?;
+    assert_eq!(
+        match_view.sub_markets,
+        vec![over_under_id.to_string(), handicap_id.to_string()],
+        "Markets were not attached to the match"
+    );
+
+    let market_view: DisplayMarket = main_contract
+        .view("get_market")
+        .args_json(serde_json::json!({"market_id": handicap_id}))
+        .await?
+        .json()?;
+    assert_eq!(
+        market_view.outcome_labels,
+        vec!["RUBY".to_string(), "Nexus".to_string()],
+        "Handicap outcomes are not the teams"
+    );
+
+    // Alice bets 10 USDC on RUBY to win the match
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": match_id, "team": Team::Team1}}).to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's bet"
+    );
+
+    // Alice bets 10 USDC on over 2.5 maps
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"MarketBet" : {"market_id": over_under_id, "outcome": 0}}).to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's over bet"
+    );
+
+    // Bob bets 10 USDC on Nexus with the handicap
+    result = ft_transfer_call(
+        bob.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"MarketBet" : {"market_id": handicap_id, "outcome": 1}}).to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Bob's handicap bet"
+    );
+
+    // Admin tries to end betting on a market attached to a match
+    result = admin
+        .call(main_contract.id(), "end_market_betting")
+        .args_json(serde_json::json!({"market_id": over_under_id}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin managed to end betting on a market attached to a match"
+    );
+
+    // End betting on the match and its markets
+    result = end_betting(admin.clone(), main_contract.id(), match_id).await?;
+
+    assert!(result.is_success(), "Admin failed to end betting");
+
+    // Bob tries to bet on a market after betting has ended
+    result = ft_transfer_call(
+        bob.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"MarketBet" : {"market_id": over_under_id, "outcome": 1}}).to_string(),
+    )
+    .await?;
+
+    assert!(
+        was_rejected(&result, "market_bet_rejected", "BettingClosed"),
+        "Bob's bet after betting ended was not rejected"
+    );
+
+    // Admin tries to finish the match without the score
+    result = finish_match(admin.clone(), main_contract.id(), match_id, Team::Team1).await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin managed to finish a match with markets without the score"
+    );
+
+    // Finish the match 2-1 to RUBY
+    result = admin
+        .call(main_contract.id(), "finish_match_with_score")
+        .args_json(serde_json::json!({"match_id": match_id, "team_1_score": 2, "team_2_score": 1}))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_success(),
+        "Admin failed to finish the match with the score"
+    );
+
+    let market_view: DisplayMarket = main_contract
+        .view("get_market")
+        .args_json(serde_json::json!({"market_id": handicap_id}))
+        .await?
+        .json()?;
+    assert_eq!(
+        market_view.winner,
+        Some(1),
+        "Nexus did not cover the handicap"
+    );
+
+    // Alice claims her bets on the match and on over 2.5 maps
+    result = claim(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(result.is_success(), "Alice failed to claim her match bet");
+
+    result = alice
+        .call(main_contract.id(), "claim_market_bet")
+        .args_json(serde_json::json!({"bet_id": U64(2)}))
+        .gas(Gas::from_tgas(150))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Alice failed to claim her over bet");
+
+    let match_winnings: u128 = 16617241;
+    let over_winnings: u128 = 18953632;
+    let mut balance: U128 = ft_balance_of(&usdc_token_contract, alice.id()).await?;
+    assert_eq!(
+        balance,
+        U128(80 * ONE_USDC + match_winnings + over_winnings),
+        "Alice's balance is not correct after she claimed her bets"
+    );
+
+    // Bob claims his handicap bet
+    result = bob
+        .call(main_contract.id(), "claim_market_bet")
+        .args_json(serde_json::json!({"bet_id": U64(3)}))
+        .gas(Gas::from_tgas(150))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Bob failed to claim his handicap bet");
+
+    let handicap_winnings: u128 = 15192862;
+    balance = ft_balance_of(&usdc_token_contract, bob.id()).await?;
+    assert_eq!(
+        balance,
+        U128(90 * ONE_USDC + handicap_winnings),
+        "Bob's balance is not correct after he claimed his handicap bet"
+    );
+
+    Ok(())
+}
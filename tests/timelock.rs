@@ -0,0 +1,121 @@
+use near_sdk::json_types::{U128, U64};
+use vex_contracts::{PendingAction, Role, TimelockAction};
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_timelock() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        bob,
+        admin,
+        main_contract,
+        sandbox,
+        ..
+    } = setup::TestSetup::new(false).await?;
+
+    // Admin makes Alice a treasurer
+    let mut result = grant_role(
+        admin.clone(),
+        main_contract.id(),
+        alice.id(),
+        Role::Treasurer,
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "Admin failed to make Alice a treasurer"
+    );
+
+    // Alice schedules two withdrawals from the fees fund
+    for _ in 0..2 {
+        result = schedule_action(
+            alice.clone(),
+            main_contract.id(),
+            TimelockAction::TakeFromFeesFund {
+                amount: U128(0),
+                receiver: alice.id().clone(),
+            },
+        )
+        .await?;
+
+        assert!(result.is_success(), "Alice failed to schedule an action");
+    }
+
+    let pending_actions: Vec<(U64, PendingAction)> = main_contract
+        .view("get_pending_actions")
+        .args_json(serde_json::json!({}))
+        .await?
+        .json()?;
+    assert_eq!(
+        pending_actions.len(),
+        2,
+        "There are not two pending actions"
+    );
+
+    let block_timestamp = sandbox.view_block().await?.timestamp();
+    assert!(
+        pending_actions
+            .iter()
+            .all(|(_, pending_action)| pending_action.eta.0 > block_timestamp),
+        "The pending actions can be executed straight away"
+    );
+
+    // Bob tries to cancel one of Alice's actions
+    result = bob
+        .call(main_contract.id(), "cancel_action")
+        .args_json(serde_json::json!({"action_id": U64(2)}))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Bob was able to cancel an action without the treasurer role"
+    );
+
+    // Admin cancels the second action
+    result = admin
+        .call(main_contract.id(), "cancel_action")
+        .args_json(serde_json::json!({"action_id": U64(2)}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to cancel the action");
+
+    // Alice tries to execute the first action before the timelock delay
+    result = execute_action(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice was able to execute an action before the timelock delay"
+    );
+
+    wait_for_timelock(&sandbox, &main_contract, U64(1)).await?;
+
+    // Alice executes the first action
+    result = execute_action(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(result.is_success(), "Alice failed to execute the action");
+
+    // Alice tries to execute the cancelled action
+    result = execute_action(alice.clone(), main_contract.id(), U64(2)).await?;
+
+    assert!(
+        result.is_failure(),
+        "Alice was able to execute a cancelled action"
+    );
+
+    let pending_actions: Vec<(U64, PendingAction)> = main_contract
+        .view("get_pending_actions")
+        .args_json(serde_json::json!({}))
+        .await?
+        .json()?;
+    assert!(
+        pending_actions.is_empty(),
+        "Executed or cancelled actions are still pending"
+    );
+
+    Ok(())
+}
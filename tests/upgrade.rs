@@ -0,0 +1,316 @@
+use near_sdk::json_types::{Base58CryptoHash, U128, U64};
+use near_sdk::Gas;
+use vex_contracts::betting::view_betting::DisplayMatch;
+use vex_contracts::{Bet, MatchState, Team, TimelockAction, UserBet, UserStake};
+mod setup;
+use crate::setup::*;
+
+#[tokio::test]
+
+async fn test_upgrade() -> Result<(), Box<dyn std::error::Error>> {
+    let TestSetup {
+        alice,
+        bob,
+        admin,
+        main_contract,
+        usdc_token_contract,
+        vex_token_contract,
+        sandbox,
+        ..
+    } = setup::TestSetup::new_v0().await?;
+
+    let match_id = "RUBY-Nexus-17/08/2024";
+
+    // Create a match with the old contract
+    let mut result = admin
+        .call(main_contract.id(), "create_match")
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": 1.2, "in_odds_2": 1.6, "date": "17/08/2024"}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to create a match");
+
+    // Alice places a bet of 10 USDC on team 1
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": match_id, "team": Team::Team1}}).to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's bet"
+    );
+
+    // Bob stakes 60 VEX
+    result = ft_transfer_call(
+        bob.clone(),
+        vex_token_contract.id(),
+        main_contract.id(),
+        U128(60 * ONE_VEX),
+        serde_json::json!("Stake").to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Bob's stake"
+    );
+
+    let old_bet: Bet = main_contract
+        .view("get_bet")
+        .args_json(serde_json::json!({"bettor": alice.id(), "bet_id": U64(1)}))
+        .await?
+        .json()?;
+    let old_stake: UserStake = main_contract
+        .view("get_user_stake_info")
+        .args_json(serde_json::json!({"account_id": bob.id()}))
+        .await?
+        .json()?;
+    let old_total_staked: U128 = main_contract
+        .view("get_total_staked_balance")
+        .await?
+        .json()?;
+    let old_funds_to_payout: U128 = main_contract.view("get_funds_to_payout").await?.json()?;
+
+    let new_wasm = near_workspaces::compile_project("./").await?;
+
+    // The old contract has no upgrade method so the new code is deployed with the account's key
+    main_contract
+        .as_account()
+        .deploy(&new_wasm)
+        .await?
+        .into_result()?;
+
+    // Bob tries to call migrate directly
+    result = bob
+        .call(main_contract.id(), "migrate")
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(result.is_failure(), "Bob was able to call migrate");
+
+    // The contract migrates its state
+    result = main_contract
+        .call("migrate")
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Failed to migrate the state");
+
+    let state_version: u32 = main_contract.view("get_state_version").await?.json()?;
+    assert_eq!(state_version, 1, "The state version was not updated");
+
+    let legacy_match_count: u32 = main_contract.view("get_legacy_match_count").await?.json()?;
+    assert_eq!(
+        legacy_match_count, 1,
+        "The match was moved before migrate_matches was called"
+    );
+
+    // Bob migrates the matches
+    result = bob
+        .call(main_contract.id(), "migrate_matches")
+        .args_json(serde_json::json!({"limit": 10}))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Bob failed to migrate matches");
+
+    let legacy_match_count: u32 = main_contract.view("get_legacy_match_count").await?.json()?;
+    assert_eq!(legacy_match_count, 0, "The match was not moved");
+
+    // Check the match was migrated
+    let match_view: DisplayMatch = main_contract
+        .view("get_match")
+        .args_json(serde_json::json!({"match_id": match_id}))
+        .await?
+        .json()?;
+    assert!(
+        matches!(match_view.match_state, MatchState::Future),
+        "Match is not Future after the upgrade"
+    );
+    assert_eq!(
+        match_view.team_1_real_bets,
+        U128(10 * ONE_USDC),
+        "Team 1 bets were not preserved"
+    );
+    assert_eq!(match_view.draw_odds, None, "Migrated match has a draw");
+
+    // Check stakes were preserved
+    let stake: UserStake = main_contract
+        .view("get_user_stake_info")
+        .args_json(serde_json::json!({"account_id": bob.id()}))
+        .await?
+        .json()?;
+    assert_eq!(
+        stake.stake_shares, old_stake.stake_shares,
+        "Bob's stake shares were not preserved"
+    );
+    assert_eq!(
+        stake.unstake_timestamp, old_stake.unstake_timestamp,
+        "Bob's unstake timestamp was not preserved"
+    );
+
+    let total_staked: U128 = main_contract
+        .view("get_total_staked_balance")
+        .await?
+        .json()?;
+    assert_eq!(
+        total_staked, old_total_staked,
+        "Total staked balance was not preserved"
+    );
+
+    let funds_to_payout: U128 = main_contract.view("get_funds_to_payout").await?.json()?;
+    assert_eq!(
+        funds_to_payout, old_funds_to_payout,
+        "Funds to payout were not preserved"
+    );
+
+    // Alice's bet can be viewed before it is moved
+    let bet: UserBet = main_contract
+        .view("get_bet")
+        .args_json(serde_json::json!({"bettor": alice.id(), "bet_id": U64(1)}))
+        .await?
+        .json()?;
+    match bet {
+        UserBet::Single(bet) => {
+            assert_eq!(
+                bet.match_id, old_bet.match_id,
+                "Bet match was not preserved"
+            );
+            assert_eq!(
+                bet.bet_amount, old_bet.bet_amount,
+                "Bet amount was not preserved"
+            );
+            assert_eq!(
+                bet.potential_winnings, old_bet.potential_winnings,
+                "Potential winnings were not preserved"
+            );
+        }
+        UserBet::Parlay(_) => panic!("Migrated bet is a parlay"),
+    }
+
+    // Alice places a new bet after the upgrade
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(5 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": match_id, "team": Team::Team1}}).to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's bet after the upgrade"
+    );
+
+    // Alice's bets made before and after the upgrade are both listed
+    let bets: Vec<(U64, UserBet)> = main_contract
+        .view("get_users_bets")
+        .args_json(serde_json::json!({"bettor": alice.id(), "limit": 10}))
+        .await?
+        .json()?;
+    assert_eq!(
+        bets.iter().map(|(bet_id, _)| *bet_id).collect::<Vec<U64>>(),
+        vec![U64(1), U64(2)],
+        "Alice's bets were not listed"
+    );
+
+    // Admin finishes the match and Alice claims her bet from before the upgrade
+    // without migrate_bets being called, the bet is moved when it is claimed
+    result = end_betting(admin.clone(), main_contract.id(), match_id).await?;
+
+    assert!(result.is_success(), "Admin failed to end betting");
+
+    result = finish_match(admin.clone(), main_contract.id(), match_id, Team::Team1).await?;
+
+    assert!(result.is_success(), "Admin failed to finish the match");
+
+    result = claim(alice.clone(), main_contract.id(), U64(1)).await?;
+
+    assert!(
+        result.is_success(),
+        "Alice failed to claim her migrated bet"
+    );
+
+    result = claim(alice.clone(), main_contract.id(), U64(2)).await?;
+
+    assert!(result.is_success(), "Alice failed to claim her new bet");
+
+    // Bob tries to stage new code
+    result = bob
+        .call(main_contract.id(), "stage_code")
+        .args(new_wasm.clone())
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(result.is_failure(), "Bob was able to stage new code");
+
+    // Admin stages the new code and schedules the upgrade
+    result = admin
+        .call(main_contract.id(), "stage_code")
+        .args(new_wasm)
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to stage new code");
+    let code_hash: Base58CryptoHash = result.json()?;
+
+    result = schedule_action(
+        admin.clone(),
+        main_contract.id(),
+        TimelockAction::UpgradeContract { code_hash },
+    )
+    .await?;
+
+    assert!(result.is_success(), "Admin failed to schedule the upgrade");
+    let action_id: U64 = result.json()?;
+
+    // Admin tries to upgrade the contract before the timelock delay has passed
+    result = admin
+        .call(main_contract.id(), "execute_action")
+        .args_json(serde_json::json!({"action_id": action_id}))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(
+        result.is_failure(),
+        "Admin was able to upgrade the contract before the timelock delay"
+    );
+
+    // Admin upgrades the contract once the timelock delay has passed
+    wait_for_timelock(&sandbox, &main_contract, action_id).await?;
+
+    result = admin
+        .call(main_contract.id(), "execute_action")
+        .args_json(serde_json::json!({"action_id": action_id}))
+        .gas(Gas::from_tgas(300))
+        .transact()
+        .await?;
+
+    assert!(result.is_success(), "Admin failed to upgrade the contract");
+
+    let staged_code_hash: Option<Base58CryptoHash> =
+        main_contract.view("get_staged_code_hash").await?.json()?;
+    assert_eq!(
+        staged_code_hash, None,
+        "The staged code was not removed after the upgrade"
+    );
+
+    let state_version: u32 = main_contract.view("get_state_version").await?.json()?;
+    assert_eq!(
+        state_version, 1,
+        "The state version changed after the upgrade"
+    );
+
+    Ok(())
+}
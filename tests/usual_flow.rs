@@ -19,7 +19,7 @@ async fn test_usual_flow() -> Result<(), Box<dyn std::error::Error>> {
     // Create a new match
     let mut result = admin
         .call(main_contract.id(), "create_match")
-        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": 1.2, "in_odds_2": 1.6, "date": "17/08/2024"}))
+        .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
         .transact()
         .await?;
 
@@ -31,12 +31,12 @@ async fn test_usual_flow() -> Result<(), Box<dyn std::error::Error>> {
         .await?
         .json()?;
     assert_eq!(
-        (match_view.team_1_odds * 100.0).round() / 100.0,
+        round_odds(match_view.team_1_odds),
         1.67,
         "Team 1 odds are incorrect after match is created"
     );
     assert_eq!(
-        (match_view.team_2_odds * 100.0).round() / 100.0,
+        round_odds(match_view.team_2_odds),
         2.22,
         "Team 2 odds are incorrect after match is created"
     );
@@ -82,12 +82,12 @@ async fn test_usual_flow() -> Result<(), Box<dyn std::error::Error>> {
         .await?
         .json()?;
     assert_eq!(
-        (match_view.team_1_odds * 100.0).round() / 100.0,
+        round_odds(match_view.team_1_odds),
         1.66,
         "Team 1 odds are incorrect after match is created"
     );
     assert_eq!(
-        (match_view.team_2_odds * 100.0).round() / 100.0,
+        round_odds(match_view.team_2_odds),
         2.24,
         "Team 2 odds are incorrect after match is created"
     );
@@ -133,12 +133,12 @@ async fn test_usual_flow() -> Result<(), Box<dyn std::error::Error>> {
         .await?
         .json()?;
     assert_eq!(
-        (match_view.team_1_odds * 100.0).round() / 100.0,
+        round_odds(match_view.team_1_odds),
         1.66,
         "Team 1 odds are incorrect after match is created"
     );
     assert_eq!(
-        (match_view.team_2_odds * 100.0).round() / 100.0,
+        round_odds(match_view.team_2_odds),
         2.23,
         "Team 2 odds are incorrect after match is created"
     );
@@ -178,6 +178,90 @@ async fn test_usual_flow() -> Result<(), Box<dyn std::error::Error>> {
         .await;
     assert!(bet.is_ok(), "Failed to get Bob's second bet");
 
+    // Alice places a bet with a minimum potential winnings higher than the odds allow
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": "RUBY-Nexus-17/08/2024", "team": Team::Team1, "min_potential_winnings": U128(20 * ONE_USDC)}})
+            .to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's bet with too high minimum potential winnings"
+    );
+    assert!(
+        was_rejected(&result, "bet_rejected", "PotentialWinningsTooLow"),
+        "Alice's bet with too high minimum potential winnings was not rejected"
+    );
+
+    balance = ft_balance_of(&usdc_token_contract, main_contract.id()).await?;
+    assert_eq!(
+        balance,
+        U128(117 * ONE_USDC),
+        "Vex contract balance is not correct after Alice's bet with too high minimum potential winnings"
+    );
+    balance = ft_balance_of(&usdc_token_contract, alice.id()).await?;
+    assert_eq!(
+        balance,
+        U128(90 * ONE_USDC),
+        "Alice's balance is not correct after her bet with too high minimum potential winnings"
+    );
+
+    bet = main_contract
+        .view("get_bet")
+        .args_json(serde_json::json!({"bettor": alice.id(), "bet_id": U64(4)}))
+        .await;
+    assert!(
+        bet.is_err(),
+        "Wrongly managed to get Alice's bet with too high minimum potential winnings"
+    );
+
+    // Alice places a bet after her deadline has passed
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        serde_json::json!({"Bet" : {"match_id": "RUBY-Nexus-17/08/2024", "team": Team::Team1, "deadline": U64(0)}})
+            .to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's bet after her deadline"
+    );
+    assert!(
+        was_rejected(&result, "bet_rejected", "DeadlinePassed"),
+        "Alice's bet after her deadline was not rejected"
+    );
+
+    balance = ft_balance_of(&usdc_token_contract, main_contract.id()).await?;
+    assert_eq!(
+        balance,
+        U128(117 * ONE_USDC),
+        "Vex contract balance is not correct after Alice's bet after her deadline"
+    );
+    balance = ft_balance_of(&usdc_token_contract, alice.id()).await?;
+    assert_eq!(
+        balance,
+        U128(90 * ONE_USDC),
+        "Alice's balance is not correct after her bet after her deadline"
+    );
+
+    bet = main_contract
+        .view("get_bet")
+        .args_json(serde_json::json!({"bettor": alice.id(), "bet_id": U64(4)}))
+        .await;
+    assert!(
+        bet.is_err(),
+        "Wrongly managed to get Alice's bet after her deadline"
+    );
+
     // Alice places a bet on an invalid match
     result = ft_transfer_call(
         alice.clone(),
@@ -193,6 +277,10 @@ async fn test_usual_flow() -> Result<(), Box<dyn std::error::Error>> {
         result.is_success(),
         "ft_transfer_call failed on Alice's invalid match bet"
     );
+    assert!(
+        was_rejected(&result, "bet_rejected", "MatchNotFound"),
+        "Alice's invalid match bet was not rejected"
+    );
 
     balance = ft_balance_of(&usdc_token_contract, main_contract.id()).await?;
     assert_eq!(
@@ -216,6 +304,38 @@ async fn test_usual_flow() -> Result<(), Box<dyn std::error::Error>> {
         "Wrongly managed to get Alice's invalid match bet"
     );
 
+    // Alice sends USDC with an invalid message
+    result = ft_transfer_call(
+        alice.clone(),
+        usdc_token_contract.id(),
+        main_contract.id(),
+        U128(10 * ONE_USDC),
+        "Invalid message".to_string(),
+    )
+    .await?;
+
+    assert!(
+        result.is_success(),
+        "ft_transfer_call failed on Alice's invalid message"
+    );
+    assert!(
+        was_rejected(&result, "transfer_rejected", "InvalidMessage"),
+        "Alice's invalid message was not rejected"
+    );
+
+    balance = ft_balance_of(&usdc_token_contract, main_contract.id()).await?;
+    assert_eq!(
+        balance,
+        U128(117 * ONE_USDC),
+        "Vex contract balance is not correct after Alice's invalid message"
+    );
+    balance = ft_balance_of(&usdc_token_contract, alice.id()).await?;
+    assert_eq!(
+        balance,
+        U128(90 * ONE_USDC),
+        "Alice's balance is not correct after her invalid message"
+    );
+
     // Alice tries to make a bet less than 1 USDC
     result = ft_transfer_call(
         alice.clone(),
@@ -231,6 +351,10 @@ async fn test_usual_flow() -> Result<(), Box<dyn std::error::Error>> {
         result.is_success(),
         "ft_transfer_call failed on Alice's bet less than 1 USDC"
     );
+    assert!(
+        was_rejected(&result, "bet_rejected", "BetTooSmall"),
+        "Alice's bet less than 1 USDC was not rejected"
+    );
 
     balance = ft_balance_of(&usdc_token_contract, main_contract.id()).await?;
     assert_eq!(
@@ -296,6 +420,10 @@ async fn test_usual_flow() -> Result<(), Box<dyn std::error::Error>> {
         result.is_success(),
         "ft_transfer_call failed on Alice's bet after betting ended"
     );
+    assert!(
+        was_rejected(&result, "bet_rejected", "BettingClosed"),
+        "Alice's bet after betting ended was not rejected"
+    );
 
     balance = ft_balance_of(&usdc_token_contract, main_contract.id()).await?;
     assert_eq!(
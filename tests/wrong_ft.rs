@@ -17,7 +17,7 @@ async fn test_wrong_ft() -> Result<(), Box<dyn std::error::Error>> {
     // Create a new match
     let mut result = admin
     .call(main_contract.id(), "create_match")
-    .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": 1.2, "in_odds_2": 1.6, "date": "17/08/2024"}))
+    .args_json(serde_json::json!({"game": "CSGO", "team_1": "RUBY", "team_2": "Nexus", "in_odds_1": U128(12 * ONE_ODDS / 10), "in_odds_2": U128(16 * ONE_ODDS / 10), "date": "17/08/2024", "start_time": START_TIME}))
     .transact()
     .await?;
 
@@ -38,6 +38,10 @@ async fn test_wrong_ft() -> Result<(), Box<dyn std::error::Error>> {
         result.is_success(),
         "ft_transfer_call failed on Alice's bet"
     );
+    assert!(
+        was_rejected(&result, "bet_rejected", "WrongToken"),
+        "Alice's bet with the wrong token was not rejected"
+    );
 
     let mut balance = ft_balance_of(&usdc_token_contract, main_contract.id()).await?;
     assert_eq!(